@@ -4,7 +4,7 @@ use proc_macro2::Span;
 use quote::{ToTokens, format_ident, quote};
 use syn::{
     DeriveInput, Expr, GenericParam, Generics, Ident, Index, Lifetime, LifetimeParam, Type,
-    parse_macro_input,
+    ext::IdentExt as _, parse_macro_input,
 };
 
 #[derive(Clone, Copy, Debug, FromMeta)]
@@ -33,6 +33,10 @@ struct Field {
     endian: Option<Endian>,
     #[darling(default)]
     size: Option<Size>,
+    #[darling(default)]
+    since: Option<usize>,
+    #[darling(default)]
+    default: Option<Expr>,
 }
 
 #[derive(Debug, FromVariant)]
@@ -48,12 +52,95 @@ struct Opts {
     ident: Ident,
     generics: Generics,
     data: darling::ast::Data<Variant, Field>,
+    #[darling(default)]
+    version: Option<usize>,
+    #[darling(default)]
+    align: Option<usize>,
+}
+
+impl Opts {
+    /// Checks the attributes that depend on each other, i.e. `since` and `default` on fields
+    /// require a `version` on the struct, and that `align` is a power of two.
+    fn validate(&self) -> darling::Result<()> {
+        let mut errors = darling::Error::accumulator();
+
+        if let Some(align) = self.align
+            && !align.is_power_of_two()
+        {
+            errors.push(
+                darling::Error::custom(format!("`align = {align}` is not a power of two"))
+                    .with_span(&self.ident),
+            );
+        }
+
+        match &self.data {
+            darling::ast::Data::Struct(fields) => {
+                for field in fields.iter() {
+                    if let Some(since) = field.since {
+                        match self.version {
+                            None => errors.push(
+                                darling::Error::custom(
+                                    "`since` requires a `version` on the struct",
+                                )
+                                .with_span(&field.ty),
+                            ),
+                            Some(version) if since > version => errors.push(
+                                darling::Error::custom(format!(
+                                    "`since = {since}` is newer than the struct version {version}"
+                                ))
+                                .with_span(&field.ty),
+                            ),
+                            _ => {}
+                        }
+
+                        if field.skip || field.skip_with.is_some() {
+                            errors.push(
+                                darling::Error::custom("skipped fields cannot have `since`")
+                                    .with_span(&field.ty),
+                            );
+                        }
+                    } else if field.default.is_some() {
+                        errors.push(
+                            darling::Error::custom("`default` requires `since`")
+                                .with_span(&field.ty),
+                        );
+                    }
+                }
+            }
+            darling::ast::Data::Enum(variants) => {
+                if self.version.is_some() {
+                    errors.push(
+                        darling::Error::custom("`version` is only supported on structs")
+                            .with_span(&self.ident),
+                    );
+                }
+
+                for field in variants.iter().flat_map(|variant| variant.fields.iter()) {
+                    if field.since.is_some() || field.default.is_some() {
+                        errors.push(
+                            darling::Error::custom(
+                                "`since` and `default` are only supported on structs",
+                            )
+                            .with_span(&field.ty),
+                        );
+                    }
+                }
+            }
+        }
+
+        errors.finish()
+    }
 }
 
 impl ToTokens for Opts {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let name = &self.ident;
 
+        let align = match self.align {
+            Some(align) => quote! { const ALIGN: usize = #align; },
+            None => quote! {},
+        };
+
         let mut generics = self.generics.clone();
 
         if generics.lifetimes().next().is_none() {
@@ -71,6 +158,8 @@ impl ToTokens for Opts {
             let mut decoder_tokens = Vec::with_capacity(data.fields.len());
             let mut encoder_tokens = Vec::with_capacity(data.fields.len());
             let mut migrate_tokens = Vec::with_capacity(data.fields.len());
+            let mut verify_tokens = Vec::with_capacity(data.fields.len());
+            let mut schema_tokens = Vec::with_capacity(data.fields.len());
 
             for (index, field) in data.fields.iter().enumerate() {
                 let Field {
@@ -80,6 +169,8 @@ impl ToTokens for Opts {
                     skip_with,
                     endian,
                     size,
+                    since,
+                    default,
                 } = field;
                 let access = match ident {
                     Some(ident) => quote! { self.#ident },
@@ -88,6 +179,10 @@ impl ToTokens for Opts {
                         quote! { self.#index }
                     }
                 };
+                let field_name = match ident {
+                    Some(ident) => ident.unraw().to_string(),
+                    _ => index.to_string(),
+                };
                 let ident = ident.clone().unwrap_or(format_ident!("v{index}"));
 
                 names.push(quote! {
@@ -104,12 +199,21 @@ impl ToTokens for Opts {
 
                 if *skip {
                     decoder_tokens.push(quote! {
-                        let #ident = Default::skip_with();
+                        let #ident = Default::default();
                     });
 
                     continue;
                 }
 
+                schema_tokens.push(field_schema(
+                    &field_name,
+                    ty,
+                    lifetime,
+                    *endian,
+                    *size,
+                    *since,
+                ));
+
                 let size = match size {
                     Some(Size::Fixed) => quote! { config.fixed_width(); },
                     Some(Size::Variable) => quote! { config.variable_width(); },
@@ -123,15 +227,38 @@ impl ToTokens for Opts {
                     None => quote! {},
                 };
 
-                decoder_tokens.push(quote! {
-                    let #ident = {
+                let decoder = quote! {
+                    {
                         let mut config = config;
                         #size
                         #endian
-                        let (value, rest) = <#ty>::from_slice(slice, config)?;
+                        let (value, rest) = <#ty as arken::Field<#lifetime>>::from_slice(slice, config)
+                            .map_err(|error| error.with_field(#field_name))?;
                         slice = rest;
                         value
-                    };
+                    }
+                };
+
+                // Fields added in a later version than the one the value was written with are
+                // not encoded, and take their default instead.
+                decoder_tokens.push(match since {
+                    Some(since) => {
+                        let default = match default {
+                            Some(default) => quote! { #default },
+                            None => quote! { Default::default() },
+                        };
+
+                        quote! {
+                            let #ident = if version >= #since {
+                                #decoder
+                            } else {
+                                #default
+                            };
+                        }
+                    }
+                    None => quote! {
+                        let #ident = #decoder;
+                    },
                 });
 
                 encoder_tokens.push(quote! {
@@ -146,6 +273,10 @@ impl ToTokens for Opts {
                 migrate_tokens.push(quote! {
                     #access.migrate(bytes, writer, reader)?;
                 });
+
+                verify_tokens.push(quote! {
+                    #access.verify(verifier);
+                });
             }
 
             let fields = if data.is_struct() {
@@ -164,9 +295,38 @@ impl ToTokens for Opts {
                 quote! {}
             };
 
+            let (version_decoder, version_encoder, kind) = match self.version {
+                Some(version) => (
+                    quote! {
+                        let input = slice;
+                        let (version, rest) = <usize as arken::Field>::from_slice(slice, config)?;
+                        slice = rest;
+
+                        if version > #version {
+                            return Err(arken::Error::unknown_version::<Self>(input, version));
+                        }
+                    },
+                    quote! {
+                        arken::Field::put_bytes(&#version, bytes, config)?;
+                    },
+                    quote! {
+                        |fields| arken::schema::TypeKind::Versioned { version: #version, fields }
+                    },
+                ),
+                None => (
+                    quote! {},
+                    quote! {},
+                    quote! { arken::schema::TypeKind::Struct },
+                ),
+            };
+
             tokens.extend(quote! {
                 impl #impl_generics arken::Field<#lifetime> for #name #ty_generics #where_clause {
+                    #align
+
                     fn from_slice(mut slice: &#lifetime [u8], config: arken::Config) -> Result<(Self, &#lifetime [u8]), arken::Error> {
+                        #version_decoder
+
                         #(
                             #decoder_tokens
                         )*
@@ -175,6 +335,8 @@ impl ToTokens for Opts {
                     }
 
                     fn put_bytes(&self, bytes: &mut bytes::BytesMut, config: arken::Config) -> Result<(), arken::Error> {
+                        #version_encoder
+
                         #(
                             #encoder_tokens
                         )*
@@ -189,20 +351,52 @@ impl ToTokens for Opts {
 
                         Ok(())
                     }
+
+                    fn verify(&self, verifier: &mut arken::verify::Verifier<#lifetime>) {
+                        #(
+                            #verify_tokens
+                        )*
+                    }
+
+                    fn schema(schemas: &mut arken::schema::Schemas<'static>) -> arken::schema::Schema<'static> {
+                        let name = std::any::type_name::<Self>();
+
+                        if !schemas.contains(name) {
+                            // Insert a placeholder first, such that recursive types terminate.
+                            let kind = #kind;
+                            schemas.insert(name, kind(Default::default()));
+
+                            let fields = vec![
+                                #(
+                                    #schema_tokens
+                                )*
+                            ];
+
+                            schemas.insert(name, kind(fields.into()));
+                        }
+
+                        arken::schema::Schema::Named(name.into())
+                    }
                 }
             });
         } else if let Some(variants) = self.data.as_ref().take_enum() {
             let mut decoder_tokens = Vec::with_capacity(variants.len());
             let mut encoder_tokens = Vec::with_capacity(variants.len());
             let mut migrate_tokens = Vec::with_capacity(variants.len());
+            let mut verify_tokens = Vec::with_capacity(variants.len());
+            let mut schema_tokens = Vec::with_capacity(variants.len());
 
             for (index, variant) in variants.iter().enumerate() {
                 let Variant { ident, .. } = variant;
+                let variant_name = ident.unraw().to_string();
 
                 let mut names = Vec::with_capacity(variant.fields.len());
+                let mut patterns = Vec::with_capacity(variant.fields.len());
                 let mut decoder_subtokens = Vec::with_capacity(variant.fields.len());
                 let mut encoder_subtokens = Vec::with_capacity(variant.fields.len());
                 let mut migrate_subtokens = Vec::with_capacity(variant.fields.len());
+                let mut verify_subtokens = Vec::with_capacity(variant.fields.len());
+                let mut schema_subtokens = Vec::with_capacity(variant.fields.len());
 
                 for (index, field) in variant.fields.as_ref().iter().enumerate() {
                     let Field {
@@ -212,15 +406,31 @@ impl ToTokens for Opts {
                         skip_with,
                         endian,
                         size,
+                        ..
                     } = field;
+                    let field_name = match ident {
+                        Some(ident) => ident.unraw().to_string(),
+                        _ => index.to_string(),
+                    };
                     let ident = ident.clone().unwrap_or(format_ident!("v{index}"));
 
                     names.push(quote! {
                         #ident,
                     });
 
+                    if skip_with.is_some() || *skip {
+                        patterns.push(match field.ident {
+                            Some(_) => quote! { #ident: _, },
+                            None => quote! { _, },
+                        });
+                    } else {
+                        patterns.push(quote! {
+                            #ident,
+                        });
+                    }
+
                     if let Some(skip_with) = skip_with {
-                        decoder_tokens.push(quote! {
+                        decoder_subtokens.push(quote! {
                             let #ident = #skip_with;
                         });
 
@@ -228,13 +438,22 @@ impl ToTokens for Opts {
                     }
 
                     if *skip {
-                        decoder_tokens.push(quote! {
+                        decoder_subtokens.push(quote! {
                             let #ident = Default::default();
                         });
 
                         continue;
                     }
 
+                    schema_subtokens.push(field_schema(
+                        &field_name,
+                        ty,
+                        lifetime,
+                        *endian,
+                        *size,
+                        None,
+                    ));
+
                     let size = match size {
                         Some(Size::Fixed) => quote! { config.fixed_width(); },
                         Some(Size::Variable) => quote! { config.variable_width(); },
@@ -257,7 +476,9 @@ impl ToTokens for Opts {
                             let mut config = config;
                             #size
                             #endian
-                            let (value, rest) = <#ty>::from_slice(slice, config)?;
+                            let (value, rest) = <#ty as arken::Field<#lifetime>>::from_slice(slice, config).map_err(|error| {
+                                error.with_field(#field_name).with_field(#variant_name)
+                            })?;
                             slice = rest;
                             value
                         };
@@ -275,22 +496,40 @@ impl ToTokens for Opts {
                     migrate_subtokens.push(quote! {
                         #ident.migrate(bytes, writer, reader)?;
                     });
+
+                    verify_subtokens.push(quote! {
+                        #ident.verify(verifier);
+                    });
                 }
 
-                let fields = if variant.fields.is_struct() {
-                    quote! { {
-                        #(
-                            #names
-                        )*
-                    } }
+                let (fields, pattern) = if variant.fields.is_struct() {
+                    (
+                        quote! { {
+                            #(
+                                #names
+                            )*
+                        } },
+                        quote! { {
+                            #(
+                                #patterns
+                            )*
+                        } },
+                    )
                 } else if variant.fields.is_tuple() {
-                    quote! { (
-                        #(
-                            #names
-                        )*
-                    ) }
+                    (
+                        quote! { (
+                            #(
+                                #names
+                            )*
+                        ) },
+                        quote! { (
+                            #(
+                                #patterns
+                            )*
+                        ) },
+                    )
                 } else {
-                    quote! {}
+                    (quote! {}, quote! {})
                 };
 
                 decoder_tokens.push(quote! {
@@ -304,7 +543,7 @@ impl ToTokens for Opts {
                 });
 
                 encoder_tokens.push(quote! {
-                    Self::#ident #fields => {
+                    Self::#ident #pattern => {
                         #index.put_bytes(bytes, config)?;
 
                         #(
@@ -314,17 +553,39 @@ impl ToTokens for Opts {
                 });
 
                 migrate_tokens.push(quote! {
-                    Self::#ident #fields => {
+                    Self::#ident #pattern => {
                         #(
                             #migrate_subtokens
                         )*
                     }
                 });
+
+                verify_tokens.push(quote! {
+                    Self::#ident #pattern => {
+                        #(
+                            #verify_subtokens
+                        )*
+                    }
+                });
+
+                schema_tokens.push(quote! {
+                    arken::schema::VariantDef {
+                        name: #variant_name.into(),
+                        fields: vec![
+                            #(
+                                #schema_subtokens
+                            )*
+                        ].into(),
+                    },
+                });
             }
 
             tokens.extend(quote! {
                 impl #impl_generics arken::Field<#lifetime> for #name #ty_generics #where_clause {
+                    #align
+
                     fn from_slice(mut slice: &#lifetime [u8], config: arken::Config) -> Result<(Self, &#lifetime [u8]), arken::Error> {
+                        let input = slice;
                         let (tag, rest) = usize::from_slice(slice, config)?;
                         slice = rest;
 
@@ -332,7 +593,7 @@ impl ToTokens for Opts {
                             #(
                                 #decoder_tokens
                             )*
-                            _ => return Err(arken::Error::Incomplete),
+                            tag => return Err(arken::Error::invalid_tag::<Self>(input, tag)),
                         };
 
                         Ok((value, slice))
@@ -357,6 +618,33 @@ impl ToTokens for Opts {
 
                         Ok(())
                     }
+
+                    fn verify(&self, verifier: &mut arken::verify::Verifier<#lifetime>) {
+                        match self {
+                            #(
+                                #verify_tokens
+                            )*
+                        }
+                    }
+
+                    fn schema(schemas: &mut arken::schema::Schemas<'static>) -> arken::schema::Schema<'static> {
+                        let name = std::any::type_name::<Self>();
+
+                        if !schemas.contains(name) {
+                            // Insert a placeholder first, such that recursive types terminate.
+                            schemas.insert(name, arken::schema::TypeKind::Enum(Default::default()));
+
+                            let variants = vec![
+                                #(
+                                    #schema_tokens
+                                )*
+                            ];
+
+                            schemas.insert(name, arken::schema::TypeKind::Enum(variants.into()));
+                        }
+
+                        arken::schema::Schema::Named(name.into())
+                    }
                 }
             });
         } else {
@@ -365,6 +653,44 @@ impl ToTokens for Opts {
     }
 }
 
+/// Describes a field as an `arken::schema::FieldDef`, including the overrides of its encoding.
+fn field_schema(
+    name: &str,
+    ty: &Type,
+    lifetime: &LifetimeParam,
+    endian: Option<Endian>,
+    size: Option<Size>,
+    since: Option<usize>,
+) -> proc_macro2::TokenStream {
+    let endian = match endian {
+        Some(Endian::Big) => quote! { Some(arken::Endian::Big) },
+        Some(Endian::Little) => quote! { Some(arken::Endian::Little) },
+        Some(Endian::Native) => quote! { Some(arken::Endian::Native) },
+        None => quote! { None },
+    };
+
+    let width = match size {
+        Some(Size::Fixed) => quote! { Some(arken::schema::Width::Fixed) },
+        Some(Size::Variable) => quote! { Some(arken::schema::Width::Variable) },
+        None => quote! { None },
+    };
+
+    let since = match since {
+        Some(since) => quote! { Some(#since) },
+        None => quote! { None },
+    };
+
+    quote! {
+        arken::schema::FieldDef {
+            name: #name.into(),
+            schema: <#ty as arken::Field<#lifetime>>::schema(schemas),
+            endian: #endian,
+            width: #width,
+            since: #since,
+        },
+    }
+}
+
 #[proc_macro_derive(Arken, attributes(arken))]
 pub fn derive(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
@@ -374,6 +700,10 @@ pub fn derive(input: TokenStream) -> TokenStream {
         Err(err) => return err.write_errors().into(),
     };
 
+    if let Err(err) = opts.validate() {
+        return err.write_errors().into();
+    }
+
     let mut stream = proc_macro2::TokenStream::new();
     opts.to_tokens(&mut stream);
     stream.into()
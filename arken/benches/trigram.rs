@@ -0,0 +1,106 @@
+use arken::{
+    ByteStr, Config, Error, Field, MappedFile, StringTrigramIter, TrigramRootRef, TrigramSet,
+    Writer,
+};
+use bytes::BytesMut;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    borrow::Cow,
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Runs `f` for the given number of iterations and reports the time and the number of
+/// allocations per iteration.
+fn measure<F: FnMut()>(name: &str, iterations: usize, mut f: F) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+
+    for _ in 0..iterations {
+        f();
+    }
+
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+    println!(
+        "{name:<32} {:>12.2?}/iter {:>10.1} allocations/iter",
+        elapsed / iterations as u32,
+        allocations as f64 / iterations as f64,
+    );
+}
+
+fn main() -> Result<(), Error> {
+    const WORDS: &[&str] = &["apple", "banana", "cherry", "date", "elder", "fig", "grape"];
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("trigram.bin");
+
+    let keys: Vec<String> = (0..2048)
+        .map(|i| {
+            format!(
+                "{}-{}-{i}",
+                WORDS[i % WORDS.len()],
+                WORDS[(i / 7) % WORDS.len()]
+            )
+        })
+        .collect();
+
+    {
+        let mut writer = Writer::tempfile(Default::default())?.persist(&path)?;
+        let mut bytes = BytesMut::new();
+        let mut set: TrigramSet<'_, StringTrigramIter> = TrigramSet::open(Default::default(), None);
+
+        for key in &keys {
+            set.insert(key.as_bytes())?;
+        }
+
+        if let Some(root_reference) = set.commit(&mut bytes, &mut writer)? {
+            writer.append_with_marker(&mut bytes, b"map", &root_reference)?;
+        }
+
+        writer.flush()?;
+    }
+
+    let file = MappedFile::open(&path)?;
+    let reader = file.reader();
+    let root = reader.find::<TrigramRootRef<'_, ()>>(b"map").next();
+    let set: TrigramSet<'_, StringTrigramIter> = TrigramSet::open(reader, root);
+
+    measure("TrigramSet::query", 100, || {
+        black_box(set.query(b"banana-fig").unwrap());
+    });
+
+    let mut bytes = BytesMut::new();
+    let key = ByteStr::from(&[0x42; 64][..]);
+    key.put_bytes(&mut bytes, Config::default())?;
+
+    measure("Cow<[u8]>::from_slice", 100_000, || {
+        black_box(Cow::<[u8]>::from_slice(&bytes, Config::default()).ok());
+    });
+
+    measure("ByteStr::from_slice", 100_000, || {
+        black_box(ByteStr::from_slice(&bytes, Config::default()).ok());
+    });
+
+    Ok(())
+}
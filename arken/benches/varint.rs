@@ -0,0 +1,89 @@
+use arken::{Config, Error, Field};
+use bytes::BytesMut;
+use integer_encoding::VarInt;
+use std::{hint::black_box, time::Instant};
+
+/// Decodes every value in `bytes` with `f` for the given number of iterations, and reports the
+/// time per value and the throughput.
+fn measure<F: FnMut(&[u8]) -> usize>(name: &str, bytes: &[u8], count: usize, mut f: F) {
+    const ITERATIONS: usize = 100;
+
+    let start = Instant::now();
+
+    for _ in 0..ITERATIONS {
+        assert_eq!(black_box(f(black_box(bytes))), count);
+    }
+
+    let elapsed = start.elapsed();
+    let values = (ITERATIONS * count) as f64;
+
+    println!(
+        "{name:<40} {:>8.2} ns/value {:>10.1} MB/s",
+        elapsed.as_nanos() as f64 / values,
+        (ITERATIONS * bytes.len()) as f64 / elapsed.as_secs_f64() / 1e6,
+    );
+}
+
+fn main() -> Result<(), Error> {
+    const COUNT: usize = 100_000;
+
+    let mut config = Config::default();
+    config.variable_width();
+
+    // Lengths of short sequences take one byte, offsets into larger files three to five bytes.
+    let cases: [(&str, u32); 3] = [("1 byte", 7), ("up to 3 bytes", 21), ("up to 10 bytes", 64)];
+
+    for (name, bits) in cases {
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        let mut bytes = BytesMut::new();
+
+        for _ in 0..COUNT {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+
+            let value = if bits == 64 {
+                state
+            } else {
+                state % (1 << bits)
+            };
+            (value as usize).put_bytes(&mut bytes, config)?;
+        }
+
+        measure(
+            &format!("usize::from_slice, {name}"),
+            &bytes,
+            COUNT,
+            |mut slice| {
+                let mut count = 0;
+
+                while let Ok((value, rest)) = usize::from_slice(slice, config) {
+                    black_box(value);
+                    slice = rest;
+                    count += 1;
+                }
+
+                count
+            },
+        );
+
+        measure(
+            &format!("integer-encoding, {name}"),
+            &bytes,
+            COUNT,
+            |mut slice| {
+                let mut count = 0;
+
+                while let Some((value, n)) = usize::decode_var(slice) {
+                    black_box(value);
+                    slice = &slice[n..];
+                    count += 1;
+                }
+
+                count
+            },
+        );
+    }
+
+    Ok(())
+}
@@ -53,7 +53,7 @@ fn main() -> Result<(), Error> {
             let trie: HashMap<'_, Cow<'_, str>, Cow<'_, str>> = HashMap::open(reader, root);
 
             for key in trie.keys() {
-                println!("{key}");
+                println!("{}", key?);
             }
         }
         Command::Query { key } => {
@@ -64,7 +64,7 @@ fn main() -> Result<(), Error> {
                 .next();
             let trie: HashMap<'_, Cow<'_, str>, Cow<'_, str>> = HashMap::open(reader, root);
 
-            match trie.get(&key.into()) {
+            match trie.get(&key.into())? {
                 Some(value) => println!("{key} = {value}"),
                 _ => println!("{key} not found"),
             }
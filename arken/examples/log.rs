@@ -30,7 +30,7 @@ fn main() -> Result<(), Error> {
     match &args.command {
         Command::List => {
             let file = MappedFile::open("log.bin")?;
-            let reader = file.reader()?;
+            let reader = file.reader();
 
             for (index, message) in reader.find::<Message>(b"msg").enumerate() {
                 if index != 0 {
@@ -53,7 +53,7 @@ fn main() -> Result<(), Error> {
             let map: MergeMap<'_, Cow<'_, str>, Cow<'_, str>> = MergeMap::open(reader, root);
 
             for key in map.keys() {
-                println!("{key}");
+                println!("{}", key?);
             }
         }
         Command::Query { key } => {
@@ -64,7 +64,7 @@ fn main() -> Result<(), Error> {
                 .next();
             let map: MergeMap<'_, Cow<'_, str>, Cow<'_, str>> = MergeMap::open(reader, root);
 
-            match map.get(&key.into()) {
+            match map.get(&key.into())? {
                 Some(value) => println!("{key} = {value}"),
                 _ => println!("{key} not found"),
             }
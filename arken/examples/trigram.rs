@@ -37,7 +37,7 @@ fn main() -> Result<(), Error> {
             let root = reader.find::<TrigramRootRef<'_, ()>>(b"map").next();
             let map: TrigramSet<'_, StringTrigramIter> = TrigramSet::open(reader, root);
 
-            let results = map.query(key.as_bytes());
+            let results = map.query(key.as_bytes())?;
 
             for (score, key) in results.iter().rev() {
                 let Ok(key) = std::str::from_utf8(key) else {
@@ -53,7 +53,7 @@ fn main() -> Result<(), Error> {
             let root = reader.find::<TrigramRootRef<'_, ()>>(b"map").next();
             let mut map: TrigramSet<'_, StringTrigramIter> = TrigramSet::open(reader, root);
 
-            map.insert(key.as_bytes());
+            map.insert(key.as_bytes())?;
             let root_reference = map.commit(&mut bytes, &mut writer)?;
 
             if let Some(root_reference) = root_reference {
@@ -66,7 +66,7 @@ fn main() -> Result<(), Error> {
             let root = reader.find::<TrigramRootRef<'_, ()>>(b"map").next();
             let mut map: TrigramSet<'_, StringTrigramIter> = TrigramSet::open(reader, root);
 
-            map.remove(&key.as_bytes());
+            map.remove(key.as_bytes())?;
             let root_reference = map.commit(&mut bytes, &mut writer)?;
 
             if let Some(root_reference) = root_reference {
@@ -0,0 +1,543 @@
+//! Inspects arken files.
+//!
+//! Unless the schemas have been written into the file, the types of the keys and values of a map
+//! have to be given on the command line. Values can be left undecoded with `--value none`, which
+//! works because the value is the last field of every entry.
+
+use arken::{
+    BlockFormat, ByteStr, Error, Field, HashMap, HashRootRef, MappedFile, MergeMap, MergeRootRef,
+    Reader, RecordStatus, StringTrigramIter, TrigramMap, TrigramRootRef,
+    schema::{FieldDef, TypeKind},
+};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::{borrow::Cow, hash::Hash, path::PathBuf};
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum KeyType {
+    #[default]
+    Bytes,
+    Str,
+    U64,
+    I64,
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum ValueType {
+    /// Does not decode the values.
+    #[default]
+    None,
+    Bytes,
+    Str,
+    U64,
+    I64,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Shows the format version, the endianness, the integer width and the checksum from the
+    /// header.
+    Header { file: PathBuf },
+    /// Lists the records with the given markers, along with their offsets, sizes and checksums.
+    Records {
+        file: PathBuf,
+        #[arg(required = true)]
+        markers: Vec<String>,
+    },
+    /// Prints the schemas written into the file, along with the types they refer to.
+    Schema { file: PathBuf },
+    /// Decodes the records with the given marker through the schema written into the file.
+    Decode {
+        file: PathBuf,
+        marker: String,
+        /// Prints at most this many records, starting from the most recent one.
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Dumps the bytes at an offset in hex.
+    Dump {
+        file: PathBuf,
+        /// The offset, in decimal or in hex with a `0x` prefix.
+        #[arg(value_parser = parse_offset)]
+        offset: usize,
+        #[arg(long, default_value_t = 256)]
+        length: usize,
+    },
+    /// Prints the most recent `MergeMap` committed with the given marker.
+    MergeMap {
+        file: PathBuf,
+        marker: String,
+        #[arg(long, value_enum, default_value_t)]
+        key: KeyType,
+        #[arg(long, value_enum, default_value_t)]
+        value: ValueType,
+        /// Prints at most this many entries.
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Prints the most recent `HashMap` committed with the given marker.
+    HashMap {
+        file: PathBuf,
+        marker: String,
+        #[arg(long, value_enum, default_value_t)]
+        key: KeyType,
+        #[arg(long, value_enum, default_value_t)]
+        value: ValueType,
+        /// Prints at most this many entries.
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Prints the most recent `TrigramMap` committed with the given marker.
+    TrigramMap {
+        file: PathBuf,
+        marker: String,
+        #[arg(long, value_enum, default_value_t)]
+        value: ValueType,
+        /// Prints at most this many trigrams.
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+}
+
+#[derive(Debug, Parser)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+fn parse_offset(s: &str) -> Result<usize, std::num::ParseIntError> {
+    match s.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+}
+
+/// Formats keys and values for printing.
+trait Show {
+    fn show(&self) -> String;
+}
+
+impl Show for () {
+    fn show(&self) -> String {
+        "..".to_string()
+    }
+}
+
+impl Show for ByteStr<'_> {
+    fn show(&self) -> String {
+        format!("b\"{}\"", self.escape_ascii())
+    }
+}
+
+impl Show for Vec<u8> {
+    fn show(&self) -> String {
+        format!("b\"{}\"", self.escape_ascii())
+    }
+}
+
+impl Show for Cow<'_, str> {
+    fn show(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+impl Show for String {
+    fn show(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+impl Show for u64 {
+    fn show(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Show for i64 {
+    fn show(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Calls the function with the type to decode the values of a map as.
+macro_rules! with_value {
+    ($value:expr, $f:ident($($arg:expr),*)) => {
+        match $value {
+            ValueType::None => $f::<()>($($arg),*),
+            ValueType::Bytes => $f::<Vec<u8>>($($arg),*),
+            ValueType::Str => $f::<String>($($arg),*),
+            ValueType::U64 => $f::<u64>($($arg),*),
+            ValueType::I64 => $f::<i64>($($arg),*),
+        }
+    };
+}
+
+fn header(reader: &Reader) {
+    let config = reader.config();
+
+    println!("format: {}", config.format_version());
+    println!("endian: {:?}", config.endian());
+    println!(
+        "width:  {}",
+        if config.is_fixed_width() {
+            "fixed"
+        } else {
+            "variable"
+        }
+    );
+    println!("hash:   {:?}", config.checksum());
+    println!("size:   {} bytes", reader.bytes().len());
+}
+
+fn records(reader: &Reader, markers: &[String]) {
+    println!(
+        "{:<16} {:>12} {:>10} {:>12}  status",
+        "marker", "offset", "size", "trailer"
+    );
+
+    for marker in markers {
+        for record in reader.records(marker.as_bytes()).into_iter().rev() {
+            let status = match record.status {
+                RecordStatus::Ok => "ok".to_string(),
+                RecordStatus::ChecksumMismatch { expected, actual } => {
+                    format!("checksum mismatch (expected {expected:#010x}, got {actual:#010x})")
+                }
+                RecordStatus::Truncated => "truncated".to_string(),
+            };
+
+            println!(
+                "{marker:<16} {:>#12x} {:>10} {:>#12x}  {status}",
+                record.offset, record.size, record.marker_offset
+            );
+        }
+    }
+}
+
+fn print_fields(fields: &[FieldDef], indent: &str) {
+    for field in fields {
+        print!("{indent}{}: {}", field.name, field.schema);
+
+        if let Some(endian) = field.endian {
+            print!(" (endian: {endian:?})");
+        }
+
+        if let Some(width) = field.width {
+            print!(" (width: {width:?})");
+        }
+
+        if let Some(since) = field.since {
+            print!(" (since: {since})");
+        }
+
+        println!();
+    }
+}
+
+fn schema(reader: &Reader) {
+    let records = reader.schemas();
+
+    if records.is_empty() {
+        println!("no schemas have been written into this file");
+        return;
+    }
+
+    for (index, record) in records.iter().enumerate() {
+        if index != 0 {
+            println!();
+        }
+
+        println!("{}: {}", record.marker().escape_ascii(), record.schema);
+
+        for def in record.types.iter() {
+            match &def.kind {
+                TypeKind::Struct(fields) => {
+                    println!("  struct {}", def.name);
+                    print_fields(fields, "    ");
+                }
+                TypeKind::Versioned { version, fields } => {
+                    println!("  struct {} (version: {version})", def.name);
+                    print_fields(fields, "    ");
+                }
+                TypeKind::Enum(variants) => {
+                    println!("  enum {}", def.name);
+
+                    for variant in variants.iter() {
+                        println!("    {}", variant.name);
+                        print_fields(&variant.fields, "      ");
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn decode(reader: &Reader, marker: &str, limit: Option<usize>) {
+    let Some(record) = reader.schema(marker.as_bytes()) else {
+        println!("no schema has been written for this marker");
+        return;
+    };
+
+    let schemas = record.schemas();
+
+    for found in reader
+        .records(marker.as_bytes())
+        .into_iter()
+        .filter(|found| found.status == RecordStatus::Ok)
+        .take(limit.unwrap_or(usize::MAX))
+    {
+        match reader.read_value(found.offset, &record.schema, &schemas) {
+            Ok(value) => println!("{:#x}: {value}", found.offset),
+            Err(error) => println!("{:#x}: {error}", found.offset),
+        }
+    }
+}
+
+fn dump(reader: &Reader, offset: usize, length: usize) {
+    let bytes = reader.bytes();
+    let start = offset.min(bytes.len());
+    let end = offset.saturating_add(length).min(bytes.len());
+
+    for (index, line) in bytes[start..end].chunks(16).enumerate() {
+        let hex: Vec<String> = line.iter().map(|byte| format!("{byte:02x}")).collect();
+        let ascii: String = line
+            .iter()
+            .map(|&byte| {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+
+        println!(
+            "{:08x}  {:<47}  |{ascii}|",
+            start + index * 16,
+            hex.join(" ")
+        );
+    }
+}
+
+fn merge_map<'a, V: 'a + Clone + for<'b> Field<'b> + Show>(
+    reader: Reader<'a>,
+    key: KeyType,
+    marker: &'a [u8],
+    limit: Option<usize>,
+) {
+    match key {
+        KeyType::Bytes => print_merge_map::<Vec<u8>, V>(reader, marker, limit),
+        KeyType::Str => print_merge_map::<String, V>(reader, marker, limit),
+        KeyType::U64 => print_merge_map::<u64, V>(reader, marker, limit),
+        KeyType::I64 => print_merge_map::<i64, V>(reader, marker, limit),
+    }
+}
+
+/// Prints a `MergeMap`, whose keys and values are decoded as owned values, as its tables may be
+/// block tables.
+fn print_merge_map<'a, K, V>(reader: Reader<'a>, marker: &'a [u8], limit: Option<usize>)
+where
+    K: 'a + Clone + for<'b> Field<'b> + Ord + Show,
+    V: 'a + Clone + for<'b> Field<'b> + Show,
+{
+    let Some((offset, root)) = reader.find::<MergeRootRef<K, V>>(marker).next_with_offset() else {
+        println!("no intact record with this marker");
+        return;
+    };
+
+    println!("root:    {:#x} (record at {offset:#x})", root.offset());
+
+    // The format only applies to the tables that are committed, so any format reads the blocks.
+    let mut map = MergeMap::open(reader, Some(root));
+    map.with_blocks(BlockFormat::default());
+    let sizes = map.node_sizes();
+
+    println!("entries: {}", map.len());
+    println!("nodes:   {}", sizes.len());
+
+    for (index, size) in sizes.iter().enumerate() {
+        println!("  node {index}: {size} key-value pairs");
+    }
+
+    println!();
+
+    for entry in map.iter().take(limit.unwrap_or(usize::MAX)) {
+        match entry {
+            Ok((key, value)) => println!("{} => {}", key.show(), value.show()),
+            Err(error) => println!("error: {error}"),
+        }
+    }
+}
+
+fn hash_map<'a, V: 'a + Clone + Field<'a> + Show>(
+    reader: Reader<'a>,
+    key: KeyType,
+    marker: &'a [u8],
+    limit: Option<usize>,
+) {
+    match key {
+        KeyType::Bytes => print_hash_map::<ByteStr, V>(reader, marker, limit),
+        KeyType::Str => print_hash_map::<Cow<str>, V>(reader, marker, limit),
+        KeyType::U64 => print_hash_map::<u64, V>(reader, marker, limit),
+        KeyType::I64 => print_hash_map::<i64, V>(reader, marker, limit),
+    }
+}
+
+fn print_hash_map<'a, K, V>(reader: Reader<'a>, marker: &'a [u8], limit: Option<usize>)
+where
+    K: 'a + Clone + Field<'a> + Hash + PartialEq + Show,
+    V: 'a + Clone + Field<'a> + Show,
+{
+    let Some((offset, root)) = reader.find::<HashRootRef<K, V>>(marker).next_with_offset() else {
+        println!("no intact record with this marker");
+        return;
+    };
+
+    println!("root:    {:#x} (record at {offset:#x})", root.offset());
+
+    let map = HashMap::open(reader, Some(root));
+    let levels = map.depth_stats();
+
+    let nodes: usize = levels.iter().map(|level| level.nodes).sum();
+    let entries: usize = levels.iter().map(|level| level.entries).sum();
+    let depths: usize = levels
+        .iter()
+        .enumerate()
+        .map(|(depth, level)| depth * level.entries)
+        .sum();
+
+    println!("entries: {}", map.len());
+    println!("nodes:   {nodes}");
+    println!("depth:   {}", levels.len().saturating_sub(1));
+
+    if entries != 0 {
+        println!("mean entry depth: {:.2}", depths as f64 / entries as f64);
+    }
+
+    println!("  {:>5} {:>10} {:>10}", "depth", "nodes", "entries");
+
+    for (depth, level) in levels.iter().enumerate() {
+        println!("  {depth:>5} {:>10} {:>10}", level.nodes, level.entries);
+    }
+
+    println!();
+
+    for entry in map.iter().take(limit.unwrap_or(usize::MAX)) {
+        match entry {
+            Ok((key, value)) => println!("{} => {}", key.show(), value.show()),
+            Err(error) => println!("error: {error}"),
+        }
+    }
+}
+
+fn trigram_map<'a, V: 'a + Clone + Field<'a> + Show>(
+    reader: Reader<'a>,
+    marker: &'a [u8],
+    limit: Option<usize>,
+) {
+    let Some((offset, root)) = reader.find::<TrigramRootRef<V>>(marker).next_with_offset() else {
+        println!("no intact record with this marker");
+        return;
+    };
+
+    println!("root:    {:#x} (record at {offset:#x})", root.offset());
+
+    let map: TrigramMap<V, StringTrigramIter> = TrigramMap::open(reader, Some(root));
+    let sizes = map.node_sizes();
+
+    println!("nodes:   {}", sizes.len());
+
+    for (index, size) in sizes.iter().enumerate() {
+        println!("  node {index}: {size} trigrams");
+    }
+
+    println!();
+
+    for entry in map.trigrams().take(limit.unwrap_or(usize::MAX)) {
+        let (trigram, key_values) = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                println!("error: {error}");
+                continue;
+            }
+        };
+
+        println!("{}:", trigram.show());
+
+        for key_value in key_values.iter() {
+            println!(
+                "  {} => {}",
+                ByteStr::from(key_value.key()).show(),
+                key_value.value().show()
+            );
+        }
+    }
+}
+
+fn main() -> Result<(), Error> {
+    let args = Args::parse();
+
+    match &args.command {
+        Command::Header { file } => {
+            let file = MappedFile::open(file)?;
+            header(&file.try_reader()?);
+        }
+        Command::Records { file, markers } => {
+            let file = MappedFile::open(file)?;
+            records(&file.try_reader()?, markers);
+        }
+        Command::Schema { file } => {
+            let file = MappedFile::open(file)?;
+            schema(&file.try_reader()?);
+        }
+        Command::Decode {
+            file,
+            marker,
+            limit,
+        } => {
+            let file = MappedFile::open(file)?;
+            decode(&file.try_reader()?, marker, *limit);
+        }
+        Command::Dump {
+            file,
+            offset,
+            length,
+        } => {
+            let file = MappedFile::open(file)?;
+            dump(&file.try_reader()?, *offset, *length);
+        }
+        Command::MergeMap {
+            file,
+            marker,
+            key,
+            value,
+            limit,
+        } => {
+            let file = MappedFile::open(file)?;
+            let reader = file.try_reader()?;
+            with_value!(value, merge_map(reader, *key, marker.as_bytes(), *limit));
+        }
+        Command::HashMap {
+            file,
+            marker,
+            key,
+            value,
+            limit,
+        } => {
+            let file = MappedFile::open(file)?;
+            let reader = file.try_reader()?;
+            with_value!(value, hash_map(reader, *key, marker.as_bytes(), *limit));
+        }
+        Command::TrigramMap {
+            file,
+            marker,
+            value,
+            limit,
+        } => {
+            let file = MappedFile::open(file)?;
+            let reader = file.try_reader()?;
+            with_value!(value, trigram_map(reader, marker.as_bytes(), *limit));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+//! Checks an arken file for damage.
+//!
+//! As the file does not describe its own types, the roots to check have to be named on the command
+//! line, along with the type of their keys. Values are not decoded beyond their `Option` tag, which
+//! works because the value is the last field of every entry. A `TrigramMap` can be checked as a
+//! `--merge-map` with `bytes` keys.
+
+use arken::{
+    ByteStr, Error, MappedFile,
+    verify::{Problem, Verifier},
+};
+use clap::{Parser, ValueEnum};
+use std::{borrow::Cow, path::PathBuf, process::ExitCode, str::FromStr};
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum KeyType {
+    #[default]
+    Bytes,
+    Str,
+    U64,
+    I64,
+}
+
+/// A marker optionally followed by the type of the keys, e.g. `users:str`.
+#[derive(Clone, Debug)]
+struct Root {
+    marker: String,
+    key: KeyType,
+}
+
+impl FromStr for Root {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (marker, key) = match s.rsplit_once(':') {
+            Some((marker, key)) => (marker, KeyType::from_str(key, true)?),
+            None => (s, KeyType::default()),
+        };
+
+        Ok(Self {
+            marker: marker.to_string(),
+            key,
+        })
+    }
+}
+
+#[derive(Debug, Parser)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    file: PathBuf,
+    /// Checks the checksums of the records with this marker.
+    #[arg(long)]
+    marker: Vec<String>,
+    /// Checks the `MergeMap`s committed with this marker, as `MARKER[:bytes|str|u64|i64]`.
+    #[arg(long)]
+    merge_map: Vec<Root>,
+    /// Checks the `HashMap`s committed with this marker, as `MARKER[:bytes|str|u64|i64]`.
+    #[arg(long)]
+    hash_map: Vec<Root>,
+}
+
+fn main() -> Result<ExitCode, Error> {
+    let args = Args::parse();
+
+    let file = MappedFile::open(&args.file)?;
+
+    let reader = match file.try_reader() {
+        Ok(reader) => reader,
+        Err(error) => {
+            println!("{:#010x}: {}", 0, Problem::InvalidHeader(error));
+            println!("1 problem(s) found");
+
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
+    let mut verifier = Verifier::new(reader.bytes());
+
+    for marker in &args.marker {
+        verifier.check_records(marker.as_bytes());
+    }
+
+    for root in &args.merge_map {
+        let marker = root.marker.as_bytes();
+
+        match root.key {
+            KeyType::Bytes => verifier.check_merge_map::<ByteStr, ()>(marker),
+            KeyType::Str => verifier.check_merge_map::<Cow<str>, ()>(marker),
+            KeyType::U64 => verifier.check_merge_map::<u64, ()>(marker),
+            KeyType::I64 => verifier.check_merge_map::<i64, ()>(marker),
+        }
+    }
+
+    for root in &args.hash_map {
+        let marker = root.marker.as_bytes();
+
+        match root.key {
+            KeyType::Bytes => verifier.check_hash_map::<ByteStr, ()>(marker),
+            KeyType::Str => verifier.check_hash_map::<Cow<str>, ()>(marker),
+            KeyType::U64 => verifier.check_hash_map::<u64, ()>(marker),
+            KeyType::I64 => verifier.check_hash_map::<i64, ()>(marker),
+        }
+    }
+
+    for problem in verifier.problems() {
+        println!("{:#010x}: {problem}", problem.offset());
+    }
+
+    if !verifier.is_ok() {
+        println!("{} problem(s) found", verifier.problems().len());
+
+        return Ok(ExitCode::FAILURE);
+    }
+
+    println!("no problems found");
+
+    Ok(ExitCode::SUCCESS)
+}
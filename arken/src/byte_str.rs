@@ -0,0 +1,81 @@
+use crate::{
+    Config, Error, Field,
+    schema::{Schema, Schemas},
+};
+use bytes::{BufMut as _, BytesMut};
+use std::{borrow::Cow, ops::Deref};
+
+/// A byte string that borrows its bytes straight from the mapped file when decoded.
+///
+/// `Cow<'a, [T]>` has to decode its elements one by one into a `Vec<T>`, even for `Cow<'a, [u8]>`.
+/// `ByteStr` uses the same encoding as `Cow<'a, [u8]>`, i.e. the length followed by the bytes, but
+/// decodes to [`Cow::Borrowed`] without allocating. Both types can therefore be used to read the
+/// same data.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ByteStr<'a>(pub Cow<'a, [u8]>);
+
+impl ByteStr<'_> {
+    /// Returns the bytes, copying them if they are borrowed.
+    pub fn into_owned(self) -> Vec<u8> {
+        self.0.into_owned()
+    }
+}
+
+impl Deref for ByteStr<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for ByteStr<'_> {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<'a> From<&'a [u8]> for ByteStr<'a> {
+    fn from(bytes: &'a [u8]) -> Self {
+        Self(Cow::Borrowed(bytes))
+    }
+}
+
+impl From<Vec<u8>> for ByteStr<'_> {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(Cow::Owned(bytes))
+    }
+}
+
+impl<'a> From<Cow<'a, [u8]>> for ByteStr<'a> {
+    fn from(bytes: Cow<'a, [u8]>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl<'a> Field<'a> for ByteStr<'a> {
+    fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let (n, rest) = usize::from_slice(slice, config)?;
+        slice = rest;
+
+        if slice.len() < n {
+            return Err(Error::incomplete::<Self>(slice));
+        }
+
+        let value = &slice[..n];
+        slice = &slice[n..];
+
+        Ok((Self(Cow::Borrowed(value)), slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        self.0.len().put_bytes(bytes, config)?;
+        bytes.put_slice(&self.0);
+
+        Ok(())
+    }
+
+    fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Bytes
+    }
+}
@@ -0,0 +1,106 @@
+use crate::Error;
+use num_enum::TryFromPrimitive;
+
+/// The largest header in front of a record, see [`Checksum::put_header`].
+pub(crate) const MAX_HEADER_SIZE: usize = 12;
+
+/// The checksum of every record of a file, see [`crate::Config::with_checksum`].
+///
+/// Every record is preceded by a header with its size and its checksum, such that
+/// [`crate::Reader::read`] can verify a record before decoding it. This covers the records that
+/// [`crate::Writer::append`] writes, such as the nodes of the collections, which are not
+/// otherwise checksummed. Note that this adds 8 bytes to every record with CRC32C and 12 bytes
+/// with xxHash3, and requires hashing the whole record on every read, e.g. the whole node on
+/// every lookup in a [`crate::MergeMap`].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, TryFromPrimitive)]
+#[repr(u8)]
+pub enum Checksum {
+    /// Only the records that are written with a marker are checksummed, with CRC32.
+    #[default]
+    None = 0,
+    /// CRC32C (Castagnoli), which is hardware accelerated on most platforms.
+    Crc32c = 1,
+    /// The 64-bit variant of xxHash3.
+    Xxh3 = 2,
+}
+
+impl Checksum {
+    /// Returns the size of the header in front of every record.
+    pub(crate) fn header_size(self) -> usize {
+        match self {
+            Self::None => 0,
+            Self::Crc32c => 8,
+            Self::Xxh3 => 12,
+        }
+    }
+
+    fn hash(self, record: &[u8]) -> u64 {
+        match self {
+            Self::None => 0,
+            Self::Crc32c => crc32c::crc32c(record) as u64,
+            Self::Xxh3 => xxhash_rust::xxh3::xxh3_64(record),
+        }
+    }
+
+    /// Writes the header of `record`, i.e. its size as a little endian `u32` followed by its
+    /// checksum, into `header`, and returns the size of the header.
+    pub(crate) fn put_header(
+        self,
+        record: &[u8],
+        header: &mut [u8; MAX_HEADER_SIZE],
+    ) -> Result<usize, Error> {
+        if self == Self::None {
+            return Ok(0);
+        }
+
+        let Ok(size) = u32::try_from(record.len()) else {
+            return Err(Error::overflow::<u32>(&[]));
+        };
+
+        let checksum = self.hash(record).to_le_bytes();
+        let header_size = self.header_size();
+
+        header[..4].copy_from_slice(&size.to_le_bytes());
+        header[4..header_size].copy_from_slice(&checksum[..header_size - 4]);
+
+        Ok(header_size)
+    }
+
+    /// Verifies the checksum of the record at `offset`. Fails with [`Error::InvalidOffset`] if
+    /// the header or the record lie outside of `bytes`.
+    pub(crate) fn verify(self, bytes: &[u8], offset: usize) -> Result<(), Error> {
+        let header_size = self.header_size();
+
+        if header_size == 0 {
+            return Ok(());
+        }
+
+        let Some(header) = offset
+            .checked_sub(header_size)
+            .and_then(|start| bytes.get(start..offset))
+        else {
+            return Err(Error::InvalidOffset);
+        };
+
+        let size = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+
+        let Some(record) = bytes.get(offset..).and_then(|record| record.get(..size)) else {
+            return Err(Error::InvalidOffset);
+        };
+
+        let mut expected = [0; 8];
+        expected[..header_size - 4].copy_from_slice(&header[4..]);
+        let expected = u64::from_le_bytes(expected);
+        let actual = self.hash(record);
+
+        if expected != actual {
+            return Err(Error::ChecksumMismatch {
+                offset,
+                expected,
+                actual,
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,307 @@
+//! Inline encodings of the collections in `std::collections`.
+//!
+//! Maps and sets are encoded as their length followed by their entries, like `Cow<[(K, V)]>` and
+//! `Cow<[K]>` respectively. The entries are written in a canonical order, such that equal
+//! collections always encode to the same bytes: ordered collections are written in the order of
+//! their keys, and hashed collections in the order of the encoded bytes of their keys. Decoding
+//! accepts the entries in any order, but rejects duplicate keys.
+
+use crate::{
+    Config, Error, Field, Reader, Writer,
+    schema::{Schema, Schemas},
+    verify::Verifier,
+};
+use bytes::{BufMut as _, BytesMut};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    hash::{BuildHasher, Hash},
+    io::{Seek, Write},
+};
+
+/// Writes the length followed by the entries in the order of the encoded bytes of their keys.
+fn put_hashed<'a, 'b, K, V>(
+    entries: impl ExactSizeIterator<Item = (&'b K, &'b V)>,
+    bytes: &mut BytesMut,
+    config: Config,
+) -> Result<(), Error>
+where
+    K: 'b + Field<'a>,
+    V: 'b + Field<'a>,
+{
+    let n = entries.len();
+    let mut encoded = Vec::with_capacity(n);
+
+    for (key, value) in entries {
+        let mut entry = BytesMut::new();
+        key.put_bytes(&mut entry, config)?;
+        let key_len = entry.len();
+        value.put_bytes(&mut entry, config)?;
+
+        encoded.push((entry, key_len));
+    }
+
+    encoded.sort_unstable_by(|(a, a_len), (b, b_len)| a[..*a_len].cmp(&b[..*b_len]));
+
+    n.put_bytes(bytes, config)?;
+
+    for (entry, _) in encoded {
+        bytes.put_slice(&entry[..]);
+    }
+
+    Ok(())
+}
+
+/// Reads the length followed by that many entries, passing each entry to `insert`, which
+/// returns `false` if the key is a duplicate.
+fn read_entries<'a, K: Field<'a>, V: Field<'a>, C: ?Sized>(
+    mut slice: &'a [u8],
+    config: Config,
+    mut insert: impl FnMut(K, V) -> bool,
+) -> Result<&'a [u8], Error> {
+    let (n, rest) = usize::from_slice(slice, config)?;
+    slice = rest;
+
+    for index in 0..n {
+        let input = slice;
+        let (key, rest) = K::from_slice(slice, config).map_err(|error| error.with_index(index))?;
+        slice = rest;
+        let (value, rest) =
+            V::from_slice(slice, config).map_err(|error| error.with_index(index))?;
+        slice = rest;
+
+        if !insert(key, value) {
+            return Err(Error::corrupt::<C>(input).with_index(index));
+        }
+    }
+
+    Ok(slice)
+}
+
+/// Reads the length of a collection, capped such that the length does not have to be trusted for
+/// the allocation.
+fn capacity<T>(slice: &[u8], config: Config) -> usize {
+    usize::from_slice(slice, config)
+        .map(|(n, rest)| crate::capacity::<T>(n, rest))
+        .unwrap_or_default()
+}
+
+impl<'a, K: Field<'a> + Ord, V: Field<'a>> Field<'a> for BTreeMap<K, V> {
+    fn from_slice(slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let mut map = BTreeMap::new();
+        let slice = read_entries::<K, V, Self>(slice, config, |key, value| {
+            map.insert(key, value).is_none()
+        })?;
+
+        Ok((map, slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        self.len().put_bytes(bytes, config)?;
+
+        for (key, value) in self {
+            key.put_bytes(bytes, config)?;
+            value.put_bytes(bytes, config)?;
+        }
+
+        Ok(())
+    }
+
+    fn migrate<W: Seek + Write>(
+        &mut self,
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+        reader: &Reader<'a>,
+    ) -> Result<(), Error> {
+        for (mut key, mut value) in std::mem::take(self) {
+            key.migrate(bytes, writer, reader)?;
+            value.migrate(bytes, writer, reader)?;
+            self.insert(key, value);
+        }
+
+        Ok(())
+    }
+
+    fn verify(&self, verifier: &mut Verifier<'a>) {
+        for (key, value) in self {
+            key.verify(verifier);
+            value.verify(verifier);
+        }
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Seq(Box::new(<(K, V)>::schema(schemas)))
+    }
+}
+
+impl<'a, K: Field<'a> + Ord> Field<'a> for BTreeSet<K> {
+    fn from_slice(slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let mut set = BTreeSet::new();
+        let slice = read_entries::<K, (), Self>(slice, config, |key, ()| set.insert(key))?;
+
+        Ok((set, slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        self.len().put_bytes(bytes, config)?;
+
+        for key in self {
+            key.put_bytes(bytes, config)?;
+        }
+
+        Ok(())
+    }
+
+    fn migrate<W: Seek + Write>(
+        &mut self,
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+        reader: &Reader<'a>,
+    ) -> Result<(), Error> {
+        for mut key in std::mem::take(self) {
+            key.migrate(bytes, writer, reader)?;
+            self.insert(key);
+        }
+
+        Ok(())
+    }
+
+    fn verify(&self, verifier: &mut Verifier<'a>) {
+        for key in self {
+            key.verify(verifier);
+        }
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Seq(Box::new(K::schema(schemas)))
+    }
+}
+
+impl<'a, K, V, S> Field<'a> for HashMap<K, V, S>
+where
+    K: Field<'a> + Eq + Hash,
+    V: Field<'a>,
+    S: BuildHasher + Default,
+{
+    fn from_slice(slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let mut map =
+            HashMap::with_capacity_and_hasher(capacity::<(K, V)>(slice, config), S::default());
+        let slice = read_entries::<K, V, Self>(slice, config, |key, value| {
+            map.insert(key, value).is_none()
+        })?;
+
+        Ok((map, slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        put_hashed(self.iter(), bytes, config)
+    }
+
+    fn migrate<W: Seek + Write>(
+        &mut self,
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+        reader: &Reader<'a>,
+    ) -> Result<(), Error> {
+        for (mut key, mut value) in std::mem::take(self) {
+            key.migrate(bytes, writer, reader)?;
+            value.migrate(bytes, writer, reader)?;
+            self.insert(key, value);
+        }
+
+        Ok(())
+    }
+
+    fn verify(&self, verifier: &mut Verifier<'a>) {
+        for (key, value) in self {
+            key.verify(verifier);
+            value.verify(verifier);
+        }
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Seq(Box::new(<(K, V)>::schema(schemas)))
+    }
+}
+
+impl<'a, K, S> Field<'a> for HashSet<K, S>
+where
+    K: Field<'a> + Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn from_slice(slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let mut set = HashSet::with_capacity_and_hasher(capacity::<K>(slice, config), S::default());
+        let slice = read_entries::<K, (), Self>(slice, config, |key, ()| set.insert(key))?;
+
+        Ok((set, slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        put_hashed(self.iter().map(|key| (key, &())), bytes, config)
+    }
+
+    fn migrate<W: Seek + Write>(
+        &mut self,
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+        reader: &Reader<'a>,
+    ) -> Result<(), Error> {
+        for mut key in std::mem::take(self) {
+            key.migrate(bytes, writer, reader)?;
+            self.insert(key);
+        }
+
+        Ok(())
+    }
+
+    fn verify(&self, verifier: &mut Verifier<'a>) {
+        for key in self {
+            key.verify(verifier);
+        }
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Seq(Box::new(K::schema(schemas)))
+    }
+}
+
+/// Encoded like `Cow<[T]>`, from front to back.
+impl<'a, T: Field<'a>> Field<'a> for VecDeque<T> {
+    fn from_slice(slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let (values, slice) = Vec::<T>::from_slice(slice, config)?;
+
+        Ok((values.into(), slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        self.len().put_bytes(bytes, config)?;
+
+        for value in self {
+            value.put_bytes(bytes, config)?;
+        }
+
+        Ok(())
+    }
+
+    fn migrate<W: Seek + Write>(
+        &mut self,
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+        reader: &Reader<'a>,
+    ) -> Result<(), Error> {
+        for value in self {
+            value.migrate(bytes, writer, reader)?;
+        }
+
+        Ok(())
+    }
+
+    fn verify(&self, verifier: &mut Verifier<'a>) {
+        for value in self {
+            value.verify(verifier);
+        }
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Seq(Box::new(T::schema(schemas)))
+    }
+}
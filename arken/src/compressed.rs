@@ -0,0 +1,285 @@
+//! Per-record compression of large values, behind the `lz4` and `zstd` features.
+//!
+//! A [`Compressed`] value is encoded as a tag that identifies the algorithm, followed by either
+//! the encoding of the value as is, or its size and its compressed encoding. Values smaller than
+//! the threshold, or that do not get any smaller, are stored as is, such that they can still be
+//! borrowed from the file. The tag is stored with every value, so values compressed with any
+//! algorithm can be decoded, as long as the feature for that algorithm is enabled.
+
+use crate::{Config, Error, Field, Reader, Writer, verify::Verifier};
+use bytes::{BufMut as _, BytesMut};
+use std::{
+    borrow::Cow,
+    fmt,
+    io::{Seek, Write},
+    ops::Deref,
+};
+
+/// The algorithm with which [`Compressed`] compresses its value.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Compression {
+    /// Stores the value as is.
+    None,
+    #[cfg(feature = "lz4")]
+    Lz4,
+    /// Zstandard at the given level.
+    #[cfg(feature = "zstd")]
+    Zstd(i32),
+}
+
+impl Compression {
+    pub(crate) fn tag(&self) -> u8 {
+        match self {
+            Self::None => 0,
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => 1,
+            #[cfg(feature = "zstd")]
+            Self::Zstd(_) => 2,
+        }
+    }
+
+    /// Compresses `data`, or returns `None` if this is [`Compression::None`].
+    pub(crate) fn compress(&self, data: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        Ok(match self {
+            Self::None => None,
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => Some(lz4_flex::block::compress(data)),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(level) => Some(zstd::bulk::compress(data, *level)?),
+        })
+    }
+
+    /// Decompresses `slice` with the algorithm identified by `tag` into exactly `size` bytes.
+    /// Errors are reported for a `T` at the start of `input`.
+    pub(crate) fn decompress<T: ?Sized>(
+        input: &[u8],
+        tag: u8,
+        slice: &[u8],
+        size: usize,
+    ) -> Result<Vec<u8>, Error> {
+        let data = match tag {
+            // LZ4 cannot compress data by more than a factor of 255, which bounds the size that
+            // is allocated up front.
+            #[cfg(feature = "lz4")]
+            1 => {
+                if size > slice.len().saturating_mul(255) {
+                    return Err(Error::corrupt::<T>(input));
+                }
+
+                lz4_flex::block::decompress(slice, size).map_err(|_| Error::corrupt::<T>(input))?
+            }
+            #[cfg(feature = "zstd")]
+            2 => {
+                use std::io::Read as _;
+
+                let mut data = vec![];
+
+                zstd::stream::read::Decoder::with_buffer(slice)
+                    .and_then(|decoder| {
+                        decoder
+                            .take((size as u64).saturating_add(1))
+                            .read_to_end(&mut data)
+                    })
+                    .map_err(|_| Error::corrupt::<T>(input))?;
+
+                data
+            }
+            tag => return Err(Error::invalid_discriminant::<T>(input, tag)),
+        };
+
+        if data.len() != size {
+            return Err(Error::corrupt::<T>(input));
+        }
+
+        Ok(data)
+    }
+}
+
+/// LZ4 if the `lz4` feature is enabled, and Zstandard at its default level otherwise.
+impl Default for Compression {
+    fn default() -> Self {
+        #[cfg(feature = "lz4")]
+        return Self::Lz4;
+
+        #[cfg(not(feature = "lz4"))]
+        return Self::Zstd(zstd::DEFAULT_COMPRESSION_LEVEL);
+    }
+}
+
+/// Compresses the encoding of a value that is larger than a threshold.
+///
+/// `T` is the borrowed form of the value, e.g. `str` or `[u8]`, such that values that are stored
+/// as is decode as [`Cow::Borrowed`], whereas values that were compressed decode as
+/// [`Cow::Owned`] from the decompressed data. The owned form of `T` must therefore be encoded
+/// like `Cow<T>`, as is the case for `String` and `Vec`.
+///
+/// ```
+/// use arken::{Compressed, Config, Field};
+/// use bytes::BytesMut;
+/// use std::borrow::Cow;
+///
+/// let json = r#"{"level":"info","message":"ok"}"#.repeat(100);
+/// let config = Config::default();
+/// let mut bytes = BytesMut::new();
+///
+/// Compressed::<str>::new(json.as_str()).put_bytes(&mut bytes, config)?;
+/// assert!(bytes.len() < json.len());
+///
+/// let (value, _) = Compressed::<str>::from_slice(&bytes, config)?;
+/// assert!(matches!(value.into_inner(), Cow::Owned(value) if value == json));
+/// # Ok::<(), arken::Error>(())
+/// ```
+pub struct Compressed<'a, T: ?Sized + ToOwned> {
+    value: Cow<'a, T>,
+    compression: Compression,
+    threshold: usize,
+}
+
+impl<'a, T: ?Sized + ToOwned> Compressed<'a, T> {
+    /// The size in bytes of the encoded value below which it is stored as is by default.
+    pub const DEFAULT_THRESHOLD: usize = 256;
+
+    pub fn new(value: impl Into<Cow<'a, T>>) -> Self {
+        Self {
+            value: value.into(),
+            compression: Compression::default(),
+            threshold: Self::DEFAULT_THRESHOLD,
+        }
+    }
+
+    /// Sets the algorithm with which the value is compressed when it is encoded.
+    pub fn with_compression(&mut self, compression: Compression) -> &mut Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets the size in bytes of the encoded value below which it is stored as is.
+    pub fn with_threshold(&mut self, threshold: usize) -> &mut Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn into_inner(self) -> Cow<'a, T> {
+        self.value
+    }
+}
+
+impl<T: ?Sized + ToOwned> Clone for Compressed<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            compression: self.compression,
+            threshold: self.threshold,
+        }
+    }
+}
+
+impl<T: ?Sized + ToOwned + fmt::Debug> fmt::Debug for Compressed<'_, T>
+where
+    T::Owned: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Compressed")
+            .field("value", &self.value)
+            .field("compression", &self.compression)
+            .field("threshold", &self.threshold)
+            .finish()
+    }
+}
+
+impl<T: ?Sized + ToOwned> Deref for Compressed<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<'a, T: ?Sized + ToOwned> From<&'a T> for Compressed<'a, T> {
+    fn from(value: &'a T) -> Self {
+        Self::new(Cow::Borrowed(value))
+    }
+}
+
+impl<'a, T> Field<'a> for Compressed<'a, T>
+where
+    T: ?Sized + ToOwned,
+    Cow<'a, T>: Field<'a>,
+    T::Owned: for<'b> Field<'b>,
+{
+    fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let input = slice;
+        let (tag, rest) = u8::from_slice(slice, config)?;
+        slice = rest;
+
+        let value = if tag == Compression::None.tag() {
+            let (value, rest) = Cow::<T>::from_slice(slice, config)?;
+            slice = rest;
+
+            value
+        } else {
+            let (size, rest) = usize::from_slice(slice, config)?;
+            slice = rest;
+            let (len, rest) = usize::from_slice(slice, config)?;
+            slice = rest;
+
+            let Some(compressed) = slice.get(..len) else {
+                return Err(Error::incomplete::<Self>(slice));
+            };
+            slice = &slice[len..];
+
+            let data = Compression::decompress::<Self>(input, tag, compressed, size)?;
+
+            // Errors in the decompressed data are reported at the start of the value.
+            let (value, rest) =
+                T::Owned::from_slice(&data, config).map_err(|error| error.relocate(input))?;
+
+            if !rest.is_empty() {
+                return Err(Error::corrupt::<Self>(input));
+            }
+
+            Cow::Owned(value)
+        };
+
+        Ok((Self::new(value), slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        let mut encoded = BytesMut::new();
+        self.value.put_bytes(&mut encoded, config)?;
+
+        let compressed = if encoded.len() < self.threshold {
+            None
+        } else {
+            self.compression.compress(&encoded)?
+        };
+
+        match compressed {
+            Some(compressed) if compressed.len() < encoded.len() => {
+                bytes.put_u8(self.compression.tag());
+                encoded.len().put_bytes(bytes, config)?;
+                compressed.len().put_bytes(bytes, config)?;
+                bytes.put_slice(&compressed);
+            }
+            _ => {
+                bytes.put_u8(Compression::None.tag());
+                bytes.put_slice(&encoded);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn migrate<W: Seek + Write>(
+        &mut self,
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+        reader: &Reader<'a>,
+    ) -> Result<(), Error> {
+        self.value.migrate(bytes, writer, reader)
+    }
+
+    fn verify(&self, verifier: &mut Verifier<'a>) {
+        self.value.verify(verifier);
+    }
+}
@@ -1,16 +1,21 @@
-use crate::{Config, Error, Field};
+use crate::{
+    Config, Error, Field,
+    schema::{Schema, Schemas},
+};
 use bytes::BytesMut;
 use rust_decimal::Decimal;
 
 impl<'a> Field<'a> for Decimal {
     fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let input = slice;
         let (mantissa, rest) = i128::from_slice(slice, config)?;
         slice = rest;
 
         let (scale, rest) = u32::from_slice(slice, config)?;
         slice = rest;
 
-        let value = Decimal::from_i128_with_scale(mantissa, scale);
+        let value = Decimal::try_from_i128_with_scale(mantissa, scale)
+            .map_err(|_| Error::corrupt::<Self>(input))?;
 
         Ok((value, slice))
     }
@@ -21,6 +26,10 @@ impl<'a> Field<'a> for Decimal {
 
         Ok(())
     }
+
+    fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Decimal
+    }
 }
 
 #[derive(Debug, Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -40,10 +49,12 @@ impl<const N: u32> From<FixedDecimal<N>> for Decimal {
 
 impl<'a, const N: u32> Field<'a> for FixedDecimal<N> {
     fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let input = slice;
         let (mantissa, rest) = i128::from_slice(slice, config)?;
         slice = rest;
 
-        let value = Decimal::from_i128_with_scale(mantissa, N);
+        let value = Decimal::try_from_i128_with_scale(mantissa, N)
+            .map_err(|_| Error::corrupt::<Self>(input))?;
 
         Ok((Self(value), slice))
     }
@@ -57,4 +68,8 @@ impl<'a, const N: u32> Field<'a> for FixedDecimal<N> {
 
         Ok(())
     }
+
+    fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::FixedDecimal(N)
+    }
 }
@@ -0,0 +1,453 @@
+//! Encryption at rest, behind the `encryption` feature.
+//!
+//! Every record of an encrypted file is sealed with an AEAD, either AES-256-GCM or
+//! ChaCha20-Poly1305, and stored as a frame. A frame starts with a header that consists of the
+//! number of bytes of padding and the size of the record as little endian `u32`s, the epoch of
+//! the writer as a little endian `u64` and a tag that authenticates them, followed by the padding,
+//! the encrypted record and its tag. The padding aligns the encrypted record, which starts at the
+//! offset that a [`crate::Ref`] to the record points to, or is preceded by its checksum if the file
+//! checksums every record. The header of the frame and the padding are authenticated along with
+//! the record as well.
+//!
+//! The header of the file holds the cipher and a random salt, from which the key of the file is
+//! derived with HKDF-SHA256, such that the same [`Key`] can be used for many files, followed by a
+//! tag that authenticates the header. The whole header is part of the derivation, so a header
+//! that was tampered with derives a different key, and like a wrong key fails to authenticate
+//! before any frame is read or written. The epoch is chosen at random whenever a writer is opened,
+//! and the frames of an epoch are sealed with a key that is derived from the key of the file and
+//! the epoch. The nonce of a record is its offset, which is unique within an epoch. The epoch
+//! keeps nonces from being used twice with the same key when a frame that was only partly written
+//! is truncated and its offset is used again, unless two writers pick the same 64-bit epoch. The
+//! header of the file and the headers of the frames are authenticated with HMAC-SHA256, keyed with
+//! another key that is derived from the key of the file, and with their offset.
+//!
+//! [`crate::MappedFile::open_encrypted`] authenticates and decrypts every frame into memory, at
+//! the same offsets as in the file, such that [`crate::Reader`] reads the plaintext as usual. A
+//! frame that fails to authenticate, e.g. because it was tampered with or because the key is
+//! wrong, fails with [`Error::Unauthenticated`]. As the header of a frame is authenticated on its
+//! own, a frame whose size was changed to extend beyond the end of the file fails as well. Only a
+//! frame at the end of the file that is incomplete, with a header that is incomplete or that
+//! authenticates, is ignored, like a record that was only partly written to a file that is not
+//! encrypted. Note that this means that whole frames that are cut off the end of the file are not
+//! detected.
+//!
+//! [`crate::Writer::open_encrypted`] authenticates every frame as well before it truncates an
+//! incomplete frame at the end of the file or appends to it.
+
+use crate::{Config, ENCRYPTED, Error, Field};
+use aes_gcm::Aes256Gcm;
+use bytes::{BufMut as _, BytesMut};
+use chacha20poly1305::{
+    ChaCha20Poly1305,
+    aead::{AeadInPlace, KeyInit, generic_array::GenericArray},
+};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac as _};
+use num_enum::TryFromPrimitive;
+use sha2::Sha256;
+use std::fmt;
+use zeroize::Zeroize as _;
+
+/// The size of the random salt in the header.
+const SALT_SIZE: usize = 16;
+/// The size of the header at the start of a frame: the padding, the size, the epoch and the tag
+/// that authenticates them.
+const FRAME_HEADER_SIZE: usize = 16 + MAC_SIZE;
+/// The size of the tags that authenticate the header of the file and of every frame, a truncated
+/// HMAC-SHA256.
+const MAC_SIZE: usize = 16;
+const TAG_SIZE: usize = 16;
+/// The info from which the key that authenticates the headers is derived, which
+/// differs from the epochs, from which the keys of the records are derived, in size.
+const MAC_INFO: &[u8] = b"arken frame header";
+
+/// The AEAD with which the records of a file are encrypted.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, TryFromPrimitive)]
+#[repr(u8)]
+pub enum Cipher {
+    #[default]
+    Aes256Gcm = 1,
+    ChaCha20Poly1305 = 2,
+}
+
+/// A 256-bit key from which the keys of encrypted files are derived. The key is zeroed when it is
+/// dropped.
+#[derive(Clone)]
+pub struct Key([u8; 32]);
+
+impl Key {
+    pub fn new(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<[u8; 32]> for Key {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Key(..)")
+    }
+}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+enum Aead {
+    Aes256Gcm(Box<Aes256Gcm>),
+    ChaCha20Poly1305(Box<ChaCha20Poly1305>),
+}
+
+impl Aead {
+    /// Derives the key of `epoch` from the key of the file.
+    fn new(cipher: Cipher, key: &[u8; 32], epoch: u64) -> Result<Self, Error> {
+        let mut derived = [0; 32];
+
+        Hkdf::<Sha256>::from_prk(key)
+            .map_err(|_| Error::InvalidHeader)?
+            .expand(&epoch.to_le_bytes(), &mut derived)
+            .map_err(|_| Error::InvalidHeader)?;
+
+        let aead = match cipher {
+            Cipher::Aes256Gcm => Self::Aes256Gcm(Box::new(Aes256Gcm::new(&derived.into()))),
+            Cipher::ChaCha20Poly1305 => {
+                Self::ChaCha20Poly1305(Box::new(ChaCha20Poly1305::new(&derived.into())))
+            }
+        };
+
+        derived.zeroize();
+
+        Ok(aead)
+    }
+}
+
+/// The state with which the frames of an encrypted file are sealed and opened.
+pub(crate) struct Encryption {
+    cipher: Cipher,
+    /// The key of the file, from which the key of every epoch is derived.
+    key: [u8; 32],
+    /// The key that authenticates the header of the file and the headers of the frames.
+    mac_key: [u8; 32],
+    epoch: u64,
+    /// The AEAD of `epoch`.
+    aead: Aead,
+}
+
+impl Drop for Encryption {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.mac_key.zeroize();
+    }
+}
+
+impl fmt::Debug for Encryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Encryption")
+            .field("cipher", &self.cipher)
+            .field("epoch", &self.epoch)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the nonce of the record at `offset`.
+fn nonce(offset: usize) -> [u8; 12] {
+    let mut nonce = [0; 12];
+    nonce[..8].copy_from_slice(&(offset as u64).to_le_bytes());
+
+    nonce
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> usize {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize
+}
+
+/// A frame whose header has been authenticated.
+struct Frame {
+    start: usize,
+    /// The offset of the record.
+    offset: usize,
+    /// The size of the record.
+    size: usize,
+    epoch: u64,
+}
+
+impl Frame {
+    fn end(&self) -> usize {
+        self.offset + self.size + TAG_SIZE
+    }
+}
+
+impl Encryption {
+    /// Derives the key of the file with `header`, which ends with the salt.
+    fn new(cipher: Cipher, key: &Key, header: &[u8]) -> Result<Self, Error> {
+        let mut derived = [0; 32];
+        let salt = &header[header.len() - SALT_SIZE..];
+
+        Hkdf::<Sha256>::new(Some(salt), &key.0)
+            .expand(header, &mut derived)
+            .map_err(|_| Error::InvalidHeader)?;
+
+        let mut epoch = [0; 8];
+        getrandom::fill(&mut epoch).map_err(std::io::Error::other)?;
+        let epoch = u64::from_le_bytes(epoch);
+
+        let mut mac_key = [0; 32];
+
+        let encryption = Hkdf::<Sha256>::from_prk(&derived)
+            .map_err(|_| Error::InvalidHeader)?
+            .expand(MAC_INFO, &mut mac_key)
+            .map_err(|_| Error::InvalidHeader)
+            .and_then(|()| Aead::new(cipher, &derived, epoch))
+            .map(|aead| Self {
+                cipher,
+                key: derived,
+                mac_key,
+                epoch,
+                aead,
+            });
+
+        derived.zeroize();
+        mac_key.zeroize();
+
+        encryption
+    }
+
+    /// Writes the header of a new encrypted file into `bytes`.
+    pub(crate) fn create(
+        config: Config,
+        cipher: Cipher,
+        key: &Key,
+        bytes: &mut BytesMut,
+    ) -> Result<Self, Error> {
+        // The cipher and the salt follow the format version, so earlier formats cannot be
+        // encrypted.
+        if config.format_version() == 0 {
+            return Err(Error::UnsupportedVersion(0));
+        }
+
+        let mut salt = [0; SALT_SIZE];
+        getrandom::fill(&mut salt).map_err(std::io::Error::other)?;
+
+        let start = bytes.len();
+        config.put_bytes(bytes, Default::default())?;
+        bytes[start + 3] |= ENCRYPTED;
+        bytes.put_u8(cipher as u8);
+        bytes.put_slice(&salt);
+
+        let encryption = Self::new(cipher, key, &bytes[start..])?;
+        let mac = encryption.mac(0, &bytes[start..])?.finalize().into_bytes();
+        bytes.put_slice(&mac[..MAC_SIZE]);
+
+        Ok(encryption)
+    }
+
+    /// Parses the header of an encrypted file, and returns the configuration of the file along
+    /// with the size of the header. Fails with [`Error::Unauthenticated`] if the tag at the end
+    /// of the header does not authenticate it, as the key is wrong or the header was tampered
+    /// with.
+    pub(crate) fn open(bytes: &[u8], key: &Key) -> Result<(Config, Self, usize), Error> {
+        let Some(&flags) = bytes.get(3) else {
+            return Err(Error::InvalidHeader);
+        };
+
+        if flags & ENCRYPTED == 0 {
+            return Err(Error::InvalidHeader);
+        }
+
+        let mut header = bytes[..bytes.len().min(6)].to_vec();
+        header[3] &= !ENCRYPTED;
+
+        let (config, rest) = Config::from_slice(&header, Default::default())?;
+        let start = header.len() - rest.len();
+
+        let Some((&cipher, rest)) = bytes[start..].split_first() else {
+            return Err(Error::InvalidHeader);
+        };
+
+        let cipher = Cipher::try_from(cipher).map_err(|_| Error::InvalidHeader)?;
+
+        let salted = start + 1 + SALT_SIZE;
+
+        if rest.len() < SALT_SIZE + MAC_SIZE {
+            return Err(Error::InvalidHeader);
+        }
+
+        let encryption = Self::new(cipher, key, &bytes[..salted])?;
+
+        encryption
+            .mac(0, &bytes[..salted])?
+            .verify_truncated_left(&bytes[salted..salted + MAC_SIZE])
+            .map_err(|_| Error::Unauthenticated(salted))?;
+
+        Ok((config, encryption, salted + MAC_SIZE))
+    }
+
+    /// Seals `header` followed by `record` into a frame that is appended at offset `end`, such
+    /// that the record is aligned to `align`. Returns the offset of the record and the frame.
+    pub(crate) fn seal(
+        &self,
+        end: usize,
+        align: usize,
+        header: &[u8],
+        record: &[u8],
+    ) -> Result<(usize, Vec<u8>), Error> {
+        let offset = (end + FRAME_HEADER_SIZE + header.len()).next_multiple_of(align);
+        let sealed = offset - header.len();
+        let padding = sealed - end - FRAME_HEADER_SIZE;
+        let size = header.len() + record.len();
+
+        let (Ok(padding32), Ok(size32)) = (u32::try_from(padding), u32::try_from(size)) else {
+            return Err(Error::overflow::<u32>(&[]));
+        };
+
+        let mut frame = Vec::with_capacity(sealed - end + size + TAG_SIZE);
+        frame.put_u32_le(padding32);
+        frame.put_u32_le(size32);
+        frame.put_u64_le(self.epoch);
+
+        let mac = self.mac(end, &frame)?.finalize().into_bytes();
+        frame.put_slice(&mac[..MAC_SIZE]);
+        frame.resize(frame.len() + padding, 0);
+
+        let start = frame.len();
+        frame.put_slice(header);
+        frame.put_slice(record);
+
+        let nonce = nonce(sealed);
+        let nonce = GenericArray::from_slice(&nonce);
+        let (header, buffer) = frame.split_at_mut(start);
+
+        let tag = match &self.aead {
+            Aead::Aes256Gcm(aead) => aead.encrypt_in_place_detached(nonce, header, buffer),
+            Aead::ChaCha20Poly1305(aead) => aead.encrypt_in_place_detached(nonce, header, buffer),
+        }
+        .map_err(|_| Error::Unauthenticated(sealed))?;
+
+        frame.put_slice(&tag);
+
+        Ok((offset, frame))
+    }
+
+    /// Returns the HMAC of `header`, which is at `start`: the header of the file or of a frame.
+    fn mac(&self, start: usize, header: &[u8]) -> Result<Hmac<Sha256>, Error> {
+        let mut mac = <Hmac<Sha256> as KeyInit>::new_from_slice(&self.mac_key)
+            .map_err(|_| Error::InvalidHeader)?;
+        mac.update(&(start as u64).to_le_bytes());
+        mac.update(header);
+
+        Ok(mac)
+    }
+
+    /// Authenticates the header of the frame at `start`, and returns the frame, or `None` if the
+    /// frame is incomplete. A frame whose header is incomplete is not authenticated.
+    fn frame(&self, bytes: &[u8], start: usize) -> Result<Option<Frame>, Error> {
+        let Some(header) = bytes.get(start..start + FRAME_HEADER_SIZE) else {
+            return Ok(None);
+        };
+
+        let (fields, tag) = header.split_at(FRAME_HEADER_SIZE - MAC_SIZE);
+
+        self.mac(start, fields)?
+            .verify_truncated_left(tag)
+            .map_err(|_| Error::Unauthenticated(start))?;
+
+        let padding = read_u32(fields, 0);
+        let size = read_u32(fields, 4);
+        let epoch = u64::from_le_bytes(fields[8..16].try_into().unwrap());
+
+        let frame = Frame {
+            start,
+            offset: start + FRAME_HEADER_SIZE + padding,
+            size,
+            epoch,
+        };
+
+        Ok((frame.end() <= bytes.len()).then_some(frame))
+    }
+
+    /// Authenticates and decrypts the record of `frame` into `buffer`. The AEAD of the last epoch
+    /// other than that of this writer is kept in `cache`, as the frames of an epoch are
+    /// contiguous.
+    fn open_frame(
+        &self,
+        bytes: &[u8],
+        frame: &Frame,
+        cache: &mut Option<(u64, Aead)>,
+        buffer: &mut [u8],
+    ) -> Result<(), Error> {
+        let Frame {
+            start,
+            offset,
+            size,
+            epoch,
+        } = *frame;
+
+        buffer.copy_from_slice(&bytes[offset..offset + size]);
+
+        let aead = match cache {
+            _ if epoch == self.epoch => &self.aead,
+            Some((current, aead)) if *current == epoch => aead,
+            _ => {
+                &cache
+                    .insert((epoch, Aead::new(self.cipher, &self.key, epoch)?))
+                    .1
+            }
+        };
+
+        let nonce = nonce(offset);
+        let nonce = GenericArray::from_slice(&nonce);
+        let tag = GenericArray::from_slice(&bytes[offset + size..frame.end()]);
+        let header = &bytes[start..offset];
+
+        match aead {
+            Aead::Aes256Gcm(aead) => aead.decrypt_in_place_detached(nonce, header, buffer, tag),
+            Aead::ChaCha20Poly1305(aead) => {
+                aead.decrypt_in_place_detached(nonce, header, buffer, tag)
+            }
+        }
+        .map_err(|_| Error::Unauthenticated(offset))
+    }
+
+    /// Authenticates and decrypts the complete frames from the frame at `start` into `plaintext`,
+    /// at the same offsets, and returns the end of the last complete frame.
+    pub(crate) fn decrypt(
+        &self,
+        bytes: &[u8],
+        mut start: usize,
+        plaintext: &mut [u8],
+    ) -> Result<usize, Error> {
+        let mut cache = None;
+
+        while let Some(frame) = self.frame(bytes, start)? {
+            let buffer = &mut plaintext[frame.offset..frame.offset + frame.size];
+            self.open_frame(bytes, &frame, &mut cache, buffer)?;
+
+            start = frame.end();
+        }
+
+        Ok(start)
+    }
+
+    /// Authenticates the complete frames from the frame at `start` without keeping the plaintext,
+    /// and returns the end of the last complete frame.
+    pub(crate) fn authenticate(&self, bytes: &[u8], mut start: usize) -> Result<usize, Error> {
+        let (mut cache, mut buffer) = (None, Vec::new());
+
+        while let Some(frame) = self.frame(bytes, start)? {
+            buffer.resize(frame.size, 0);
+            let result = self.open_frame(bytes, &frame, &mut cache, &mut buffer);
+            buffer.zeroize();
+            result?;
+
+            start = frame.end();
+        }
+
+        Ok(start)
+    }
+}
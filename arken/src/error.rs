@@ -0,0 +1,337 @@
+use std::fmt;
+use thiserror::Error;
+
+/// A segment of the path from the value that was read to the value that failed to decode.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum PathSegment {
+    /// A field of a struct or enum variant.
+    Field(&'static str),
+    /// An element of a sequence.
+    Index(usize),
+}
+
+/// Describes where decoding failed.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Context {
+    /// The offset of the data that failed to decode. [`crate::Field::from_slice`] reports the
+    /// number of bytes that remained in its input at that point, which [`crate::Reader`] rebases
+    /// into an absolute offset into the file.
+    pub offset: usize,
+    /// The name of the type that was being decoded.
+    pub type_name: &'static str,
+    /// The path from the value that was read to the value that failed to decode.
+    pub path: Vec<PathSegment>,
+}
+
+impl Context {
+    /// Returns the context for decoding a `T` from the start of `slice`.
+    pub fn new<T: ?Sized>(slice: &[u8]) -> Self {
+        Self {
+            offset: slice.len(),
+            type_name: std::any::type_name::<T>(),
+            path: vec![],
+        }
+    }
+
+    /// Formats the path as e.g. `nodes[3].key`.
+    pub fn path(&self) -> String {
+        let mut path = String::new();
+
+        for segment in &self.path {
+            match segment {
+                PathSegment::Field(name) if path.is_empty() => path.push_str(name),
+                PathSegment::Field(name) => {
+                    path.push('.');
+                    path.push_str(name);
+                }
+                PathSegment::Index(index) => path.push_str(&format!("[{index}]")),
+            }
+        }
+
+        path
+    }
+}
+
+impl fmt::Display for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` at offset {}", self.type_name, self.offset)?;
+
+        if !self.path.is_empty() {
+            write!(f, " in field `{}`", self.path())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The input ended before the value was fully decoded.
+    #[error("incomplete {0}")]
+    Incomplete(Context),
+    /// A variable width integer does not fit in its type.
+    #[error("overflow decoding {0}")]
+    Overflow(Context),
+    /// A variable width integer that is encoded with more bytes than necessary.
+    #[error("non-canonical encoding of {0}")]
+    NonCanonical(Context),
+    /// An enum tag that does not correspond to any variant.
+    #[error("invalid tag {tag} for {context}")]
+    InvalidTag { tag: usize, context: Context },
+    /// A versioned struct written by a newer version of the type than the one decoding it.
+    #[error("unknown version {version} of {context}")]
+    UnknownVersion { version: usize, context: Context },
+    /// A discriminant byte, e.g. of an `Option`, other than the ones that are allowed.
+    #[error("invalid discriminant {value} for {context}")]
+    InvalidDiscriminant { value: u8, context: Context },
+    #[error("invalid UTF-8 in {context}: {source}")]
+    InvalidUtf8 {
+        source: std::str::Utf8Error,
+        context: Context,
+    },
+    /// The data is well-formed, but does not describe a valid value, e.g. an out of range
+    /// timestamp.
+    #[error("corrupt {0}")]
+    Corrupt(Context),
+    /// A string with a NUL character at the given byte position, which cannot be encoded as
+    /// strings are NUL-terminated.
+    #[error("string contains a NUL character at position {0}")]
+    InteriorNul(usize),
+    #[error("invalid header")]
+    InvalidHeader,
+    /// A file with encrypted records, which must be opened with a key, which requires the
+    /// `encryption` feature.
+    #[error("the file is encrypted")]
+    Encrypted,
+    /// An encrypted record that failed to authenticate, as it was tampered with or as the key is
+    /// wrong.
+    #[error("record at offset {0} failed to authenticate")]
+    Unauthenticated(usize),
+    /// A file written in a newer format version than this version of the crate supports. See
+    /// [`crate::FORMAT_VERSION`].
+    #[error("unsupported format version {0}")]
+    UnsupportedVersion(u8),
+    #[error("invalid offset")]
+    InvalidOffset,
+    /// A record whose checksum does not match, see [`crate::Config::with_checksum`].
+    #[error(
+        "checksum mismatch in record at offset {offset}: expected {expected:#x}, got {actual:#x}"
+    )]
+    ChecksumMismatch {
+        offset: usize,
+        expected: u64,
+        actual: u64,
+    },
+    /// An alignment that is not a power of two.
+    #[error("invalid alignment {0}, which is not a power of two")]
+    InvalidAlignment(usize),
+    /// A record that cannot be borrowed in place, as it is not suitably aligned, or is not
+    /// encoded as its in-memory representation under the configuration of the file.
+    #[error("cannot borrow `{type_name}` at offset {offset} in place")]
+    Unaligned {
+        offset: usize,
+        type_name: &'static str,
+    },
+    /// A table of a [`crate::MergeMap`] that packs its key-value pairs into blocks, which can
+    /// only be read by a map opened with [`crate::MergeMap::with_blocks`].
+    #[error("the table at offset {0} consists of blocks, which requires MergeMap::with_blocks")]
+    BlocksRequired(usize),
+    #[error("unknown file {0}")]
+    UnknownFile(u64),
+    /// A [`crate::Value`] was decoded with a schema that is opaque or refers to a type that is
+    /// not described.
+    #[error("cannot decode `{0}` without a description of the type")]
+    UnknownSchema(String),
+    /// A [`crate::Value`] does not have the shape of the schema it is encoded with.
+    #[error("value does not match the schema `{0}`")]
+    SchemaMismatch(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "jiff")]
+    #[error(transparent)]
+    Jiff(#[from] ::jiff::Error),
+    #[error(transparent)]
+    Mmap(#[from] mmap_rs::Error),
+    #[cfg(feature = "serde")]
+    #[error("{0}")]
+    Serde(String),
+    #[error(transparent)]
+    Persist(#[from] tempfile::PersistError),
+    #[cfg(feature = "uuid")]
+    #[error(transparent)]
+    Uuid(#[from] ::uuid::Error),
+}
+
+impl Error {
+    pub fn incomplete<T: ?Sized>(slice: &[u8]) -> Self {
+        Self::Incomplete(Context::new::<T>(slice))
+    }
+
+    pub fn overflow<T: ?Sized>(slice: &[u8]) -> Self {
+        Self::Overflow(Context::new::<T>(slice))
+    }
+
+    pub fn non_canonical<T: ?Sized>(slice: &[u8]) -> Self {
+        Self::NonCanonical(Context::new::<T>(slice))
+    }
+
+    pub fn invalid_tag<T: ?Sized>(slice: &[u8], tag: usize) -> Self {
+        Self::InvalidTag {
+            tag,
+            context: Context::new::<T>(slice),
+        }
+    }
+
+    pub fn unknown_version<T: ?Sized>(slice: &[u8], version: usize) -> Self {
+        Self::UnknownVersion {
+            version,
+            context: Context::new::<T>(slice),
+        }
+    }
+
+    pub fn invalid_discriminant<T: ?Sized>(slice: &[u8], value: u8) -> Self {
+        Self::InvalidDiscriminant {
+            value,
+            context: Context::new::<T>(slice),
+        }
+    }
+
+    pub fn invalid_utf8<T: ?Sized>(slice: &[u8], source: std::str::Utf8Error) -> Self {
+        Self::InvalidUtf8 {
+            source,
+            context: Context::new::<T>(slice),
+        }
+    }
+
+    pub fn corrupt<T: ?Sized>(slice: &[u8]) -> Self {
+        Self::Corrupt(Context::new::<T>(slice))
+    }
+
+    /// Returns where decoding failed, if this is a decoding error.
+    pub fn context(&self) -> Option<&Context> {
+        match self {
+            Self::Incomplete(context)
+            | Self::Overflow(context)
+            | Self::NonCanonical(context)
+            | Self::InvalidTag { context, .. }
+            | Self::UnknownVersion { context, .. }
+            | Self::InvalidDiscriminant { context, .. }
+            | Self::InvalidUtf8 { context, .. }
+            | Self::Corrupt(context) => Some(context),
+            _ => None,
+        }
+    }
+
+    fn context_mut(&mut self) -> Option<&mut Context> {
+        match self {
+            Self::Incomplete(context)
+            | Self::Overflow(context)
+            | Self::NonCanonical(context)
+            | Self::InvalidTag { context, .. }
+            | Self::UnknownVersion { context, .. }
+            | Self::InvalidDiscriminant { context, .. }
+            | Self::InvalidUtf8 { context, .. }
+            | Self::Corrupt(context) => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Prepends a field to the path of a decoding error. This is used by the `Arken` derive to
+    /// record which field failed to decode.
+    pub fn with_field(mut self, name: &'static str) -> Self {
+        if let Some(context) = self.context_mut() {
+            context.path.insert(0, PathSegment::Field(name));
+        }
+
+        self
+    }
+
+    /// Prepends an element index to the path of a decoding error.
+    pub fn with_index(mut self, index: usize) -> Self {
+        if let Some(context) = self.context_mut() {
+            context.path.insert(0, PathSegment::Index(index));
+        }
+
+        self
+    }
+
+    /// Turns the offset of a decoding error returned by [`crate::Field::from_slice`] into an
+    /// absolute offset, given the number of bytes from the start of the file to the end of the
+    /// input.
+    pub(crate) fn rebase(mut self, len: usize) -> Self {
+        if let Some(context) = self.context_mut() {
+            context.offset = len.saturating_sub(context.offset);
+        }
+
+        self
+    }
+
+    /// Moves the offset of a decoding error to the start of `slice`, for errors in data that is
+    /// not part of the input itself, such as decompressed data.
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    pub(crate) fn relocate(mut self, slice: &[u8]) -> Self {
+        if let Some(context) = self.context_mut() {
+            context.offset = slice.len();
+        }
+
+        self
+    }
+}
+
+/// A decoding error returned by [`crate::Reader::read_verbose`], along with the value that was
+/// being read and the bytes around the location where decoding failed.
+#[derive(Debug)]
+pub struct Diagnostic {
+    /// The name of the type that was being read.
+    pub type_name: &'static str,
+    /// The offset of the value that was being read.
+    pub offset: usize,
+    pub error: Error,
+    /// The offset of the first byte in `bytes`.
+    pub bytes_offset: usize,
+    /// The bytes around the location where decoding failed.
+    pub bytes: Vec<u8>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "failed to read `{}` at offset {}: {}",
+            self.type_name, self.offset, self.error
+        )?;
+
+        let Some(context) = self.error.context() else {
+            return Ok(());
+        };
+
+        if !context.path.is_empty() {
+            writeln!(f, "  field:  {}", context.path())?;
+        }
+
+        writeln!(f, "  type:   {}", context.type_name)?;
+        writeln!(
+            f,
+            "  offset: {} ({} bytes into the value)",
+            context.offset,
+            context.offset.saturating_sub(self.offset)
+        )?;
+        write!(f, "  bytes:  {:#010x}:", self.bytes_offset)?;
+
+        for (index, byte) in self.bytes.iter().enumerate() {
+            if self.bytes_offset + index == context.offset {
+                write!(f, " [{byte:02x}]")?;
+            } else {
+                write!(f, " {byte:02x}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostic {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
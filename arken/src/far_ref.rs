@@ -0,0 +1,113 @@
+use crate::{
+    Config, Error, Field, Reader, Ref,
+    schema::{Schema, Schemas},
+};
+use bytes::BytesMut;
+use std::marker::PhantomData;
+
+/// A reference to a value in another file. Where a [`Ref`] is only an offset into the file it was
+/// read from, a `FarRef` also identifies the file by an application-defined id, such as the
+/// sequence number of a segment written by a [`crate::SegmentedWriter`]. This allows structures to
+/// span multiple files, e.g. to keep older data in archived files or to share a large immutable
+/// base file. A `FarRef` is resolved through a [`MultiReader`].
+///
+/// Unlike a [`Ref`], a `FarRef` is left as is by [`Field::migrate`], as it does not point into
+/// the file that is being migrated.
+///
+/// The collections of this crate only ever refer to their nodes through [`Ref`]s, so a
+/// [`crate::MergeMap`] cannot keep some of its tables in another file. Structures that span files
+/// have to be built by the application, e.g. by committing a map per segment and linking their
+/// roots through `FarRef`s. Nothing keeps track of the files a `FarRef` points into either: see
+/// [`crate::SegmentedWriter::apply_retention_with`] for keeping segments that are still
+/// referenced.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct FarRef<'a, T: Field<'a>> {
+    pub(crate) file: u64,
+    pub(crate) offset: usize,
+    pub(crate) _marker: &'a PhantomData<T>,
+}
+
+impl<'a, T: Field<'a>> FarRef<'a, T> {
+    /// Turns a reference into the file with the given id into a `FarRef`.
+    pub fn new(file: u64, reference: Ref<'a, T>) -> Self {
+        Self {
+            file,
+            offset: reference.offset,
+            _marker: &PhantomData,
+        }
+    }
+
+    /// Returns the id of the file the value is stored in.
+    pub fn file(&self) -> u64 {
+        self.file
+    }
+
+    /// Returns the reference to the value within its own file.
+    pub fn local(&self) -> Ref<'a, T> {
+        Ref {
+            offset: self.offset,
+            _marker: &PhantomData,
+        }
+    }
+}
+
+impl<'a, T: Field<'a>> Field<'a> for FarRef<'a, T> {
+    fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let (file, rest) = u64::from_slice(slice, config)?;
+        slice = rest;
+
+        let (offset, rest) = usize::from_slice(slice, config)?;
+        slice = rest;
+
+        let value = FarRef {
+            file,
+            offset,
+            _marker: &PhantomData,
+        };
+
+        Ok((value, slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        self.file.put_bytes(bytes, config)?;
+        self.offset.put_bytes(bytes, config)?;
+
+        Ok(())
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::FarRef(Box::new(T::schema(schemas)))
+    }
+}
+
+/// Resolves [`FarRef`]s to values by dispatching them to the [`Reader`] of the file they point
+/// into.
+#[derive(Clone, Debug, Default)]
+pub struct MultiReader<'a> {
+    readers: Vec<(u64, Reader<'a>)>,
+}
+
+impl<'a> MultiReader<'a> {
+    /// Adds the reader for the file with the given id, replacing any previous reader for that id.
+    pub fn with_file(mut self, file: u64, reader: Reader<'a>) -> Self {
+        self.readers.retain(|(other, _)| *other != file);
+        self.readers.push((file, reader));
+        self
+    }
+
+    /// Returns the reader of the file with the given id.
+    pub fn reader(&self, file: u64) -> Option<&Reader<'a>> {
+        self.readers
+            .iter()
+            .find(|(other, _)| *other == file)
+            .map(|(_, reader)| reader)
+    }
+
+    pub fn read<T: Field<'a>>(&self, reference: &FarRef<'a, T>) -> Result<T, Error> {
+        let reader = self
+            .reader(reference.file)
+            .ok_or(Error::UnknownFile(reference.file))?;
+
+        reader.read(&reference.local())
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{Config, Endian, Error, Field};
+use crate::{
+    Config, Endian, Error, Field,
+    schema::{Schema, Schemas},
+};
 use bytes::{BufMut as _, BytesMut};
 
 macro_rules! impl_float_primitive {
@@ -9,7 +12,7 @@ macro_rules! impl_float_primitive {
                     const N: usize = std::mem::size_of::<$ty>();
 
                     if slice.len() < N {
-                        return Err(Error::Incomplete);
+                        return Err(Error::incomplete::<Self>(slice));
                     }
 
                     let mut bytes = [0u8; N];
@@ -36,6 +39,10 @@ macro_rules! impl_float_primitive {
 
                     Ok(())
                 }
+
+                fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+                    Schema::[<$ty:camel>]
+                }
             }
         }
     };
@@ -1,6 +1,10 @@
 use crate as arken;
 
-use arken::{Arken, Error, Field, Reader, Ref, Writer};
+use arken::{
+    Arken, Error, Field, Reader, Ref, Writer,
+    layout::LayoutVersion,
+    verify::{Problem, Verifier},
+};
 use bytes::BytesMut;
 use std::{
     borrow::Cow,
@@ -67,14 +71,78 @@ pub struct Node<'a, K: Clone + Field<'a>, V: Clone + Field<'a>> {
 
 pub type NodeRef<'a, K, V> = Ref<'a, Node<'a, K, V>>;
 
+/// Bumped whenever the layout of the trie, i.e. [`Node`] and [`KeyValue`], changes.
+const LAYOUT_VERSION: usize = 0;
+
 #[derive(Arken, Clone, Debug)]
 pub struct HashRoot<'a, K: Clone + Field<'a>, V: Clone + Field<'a>> {
+    layout: LayoutVersion<LAYOUT_VERSION>,
     node: NodeRef<'a, K, V>,
     count: usize,
 }
 
 pub type HashRootRef<'a, K, V> = Ref<'a, HashRoot<'a, K, V>>;
 
+impl<'a, K: 'a + Clone + Field<'a>, V: 'a + Clone + Field<'a>> HashRoot<'a, K, V> {
+    /// Reports a problem if `count` does not match the number of entries in the trie.
+    pub(crate) fn verify_count(&self, offset: usize, verifier: &mut Verifier<'a>) {
+        let actual = Self::count_entries(&self.node, 0, verifier);
+
+        if actual != self.count {
+            verifier.report(Problem::CountMismatch {
+                offset,
+                expected: self.count,
+                actual,
+            });
+        }
+    }
+
+    /// Counts the entries below a node the same way lookups find them: through the masks, except
+    /// for the nodes at the bottom of the trie, which hold a list of colliding entries.
+    fn count_entries(
+        reference: &NodeRef<'a, K, V>,
+        shift: usize,
+        verifier: &mut Verifier<'a>,
+    ) -> usize {
+        if let Some(count) = verifier.count(reference.offset()) {
+            return count;
+        }
+
+        // Problems with reading the node have already been reported while walking the trie.
+        let Some(reader) = verifier.reader() else {
+            return 0;
+        };
+
+        let Ok(node) = reader.read(reference) else {
+            return 0;
+        };
+
+        let mut count = 0;
+
+        if shift >= 64 {
+            count += node.values.len();
+        } else {
+            for index in 0..64 {
+                if let Some(dense_index) = node.value_mask.get_dense_index(index)
+                    && dense_index < node.values.len()
+                {
+                    count += 1;
+                }
+
+                if let Some(dense_index) = node.node_mask.get_dense_index(index)
+                    && let Some(reference) = node.nodes.get(dense_index)
+                {
+                    count += Self::count_entries(reference, shift + 6, verifier);
+                }
+            }
+        }
+
+        verifier.set_count(reference.offset(), count);
+
+        count
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct MemNode<'a, K: Clone + Field<'a>, V: Clone + Field<'a>> {
     value_mask: Mask,
@@ -120,18 +188,26 @@ pub enum AnyNode<'a, 'b, K: Clone + Field<'a>, V: Clone + Field<'a>> {
     Memory(&'b MemNode<'a, K, V>),
 }
 
+/// Iterates over the entries of a [`HashMap`]. A record that cannot be read is yielded as an
+/// error, after which the iterator continues with the next entry.
 #[derive(Debug)]
 pub struct Iter<'a, 'b, K: Clone + Field<'a>, V: Clone + Field<'a>> {
     map: &'b HashMap<'a, K, V>,
     stack: Vec<(AnyNode<'a, 'b, K, V>, usize)>,
+    /// The error that occurred while reading the root.
+    error: Option<Error>,
 }
 
 impl<'a, 'b, K: Clone + Field<'a> + PartialEq, V: Clone + Field<'a>> Iterator
     for Iter<'a, 'b, K, V>
 {
-    type Item = (Cow<'b, K>, Cow<'b, V>);
+    type Item = Result<(Cow<'b, K>, Cow<'b, V>), Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.error.take() {
+            return Some(Err(error));
+        }
+
         'outer: while !self.stack.is_empty() {
             let Some((node, index)) = self.stack.last_mut() else {
                 break;
@@ -140,80 +216,82 @@ impl<'a, 'b, K: Clone + Field<'a> + PartialEq, V: Clone + Field<'a>> Iterator
             match node {
                 AnyNode::Disk(node) => {
                     for i in *index..64 {
-                        if let Some(dense_index) = node.value_mask.get_dense_index(i) {
-                            if let Some(reference) = node.values.get(dense_index)
-                                && let Ok(key_value) =
-                                    self.map.reader.read::<KeyValue<'a, K, V>>(reference)
-                            {
-                                *index = i + 1;
-
-                                let key = Cow::Owned(key_value.key);
-                                let value = Cow::Owned(key_value.value);
-
-                                return Some((key, value));
-                            }
+                        if let Some(dense_index) = node.value_mask.get_dense_index(i)
+                            && let Some(reference) = node.values.get(dense_index)
+                        {
+                            *index = i + 1;
+
+                            let key_value = self.map.reader.read::<KeyValue<'a, K, V>>(reference);
+
+                            return Some(key_value.map(|key_value| {
+                                (Cow::Owned(key_value.key), Cow::Owned(key_value.value))
+                            }));
                         }
 
-                        if let Some(dense_index) = node.node_mask.get_dense_index(i) {
-                            if let Some(reference) = node.nodes.get(dense_index)
-                                && let Ok(node) = self.map.reader.read::<Node<'a, K, V>>(reference)
-                            {
-                                *index = i + 1;
+                        if let Some(dense_index) = node.node_mask.get_dense_index(i)
+                            && let Some(reference) = node.nodes.get(dense_index)
+                        {
+                            *index = i + 1;
 
-                                self.stack.push((AnyNode::Disk(node), 0));
+                            let node = match self.map.reader.read::<Node<'a, K, V>>(reference) {
+                                Ok(node) => node,
+                                Err(error) => return Some(Err(error)),
+                            };
 
-                                continue 'outer;
-                            }
+                            self.stack.push((AnyNode::Disk(node), 0));
+
+                            continue 'outer;
                         }
                     }
                 }
                 AnyNode::Memory(node) => {
                     for i in *index..64 {
-                        if let Some(dense_index) = node.mem_value_mask.get_dense_index(i) {
-                            if let Some(key_value) = node.mem_values.get(dense_index) {
-                                *index = i + 1;
+                        if let Some(dense_index) = node.mem_value_mask.get_dense_index(i)
+                            && let Some(key_value) = node.mem_values.get(dense_index)
+                        {
+                            *index = i + 1;
 
-                                let key = Cow::Borrowed(&key_value.key);
-                                let value = Cow::Borrowed(&key_value.value);
+                            let key = Cow::Borrowed(&key_value.key);
+                            let value = Cow::Borrowed(&key_value.value);
 
-                                return Some((key, value));
-                            }
+                            return Some(Ok((key, value)));
                         }
 
-                        if let Some(dense_index) = node.value_mask.get_dense_index(i) {
-                            if let Some(reference) = node.values.get(dense_index)
-                                && let Ok(key_value) =
-                                    self.map.reader.read::<KeyValue<'a, K, V>>(reference)
-                            {
-                                *index = i + 1;
+                        if let Some(dense_index) = node.value_mask.get_dense_index(i)
+                            && let Some(reference) = node.values.get(dense_index)
+                        {
+                            *index = i + 1;
 
-                                let key = Cow::Owned(key_value.key);
-                                let value = Cow::Owned(key_value.value);
+                            let key_value = self.map.reader.read::<KeyValue<'a, K, V>>(reference);
 
-                                return Some((key, value));
-                            }
+                            return Some(key_value.map(|key_value| {
+                                (Cow::Owned(key_value.key), Cow::Owned(key_value.value))
+                            }));
                         }
 
-                        if let Some(dense_index) = node.mem_node_mask.get_dense_index(i) {
-                            if let Some(node) = node.mem_nodes.get(dense_index) {
-                                *index = i + 1;
+                        if let Some(dense_index) = node.mem_node_mask.get_dense_index(i)
+                            && let Some(node) = node.mem_nodes.get(dense_index)
+                        {
+                            *index = i + 1;
 
-                                self.stack.push((AnyNode::Memory(node), 0));
+                            self.stack.push((AnyNode::Memory(node), 0));
 
-                                continue 'outer;
-                            }
+                            continue 'outer;
                         }
 
-                        if let Some(dense_index) = node.node_mask.get_dense_index(i) {
-                            if let Some(reference) = node.nodes.get(dense_index)
-                                && let Ok(node) = self.map.reader.read::<Node<'a, K, V>>(reference)
-                            {
-                                *index = i + 1;
+                        if let Some(dense_index) = node.node_mask.get_dense_index(i)
+                            && let Some(reference) = node.nodes.get(dense_index)
+                        {
+                            *index = i + 1;
+
+                            let node = match self.map.reader.read::<Node<'a, K, V>>(reference) {
+                                Ok(node) => node,
+                                Err(error) => return Some(Err(error)),
+                            };
 
-                                self.stack.push((AnyNode::Disk(node), 0));
+                            self.stack.push((AnyNode::Disk(node), 0));
 
-                                continue 'outer;
-                            }
+                            continue 'outer;
                         }
                     }
                 }
@@ -234,11 +312,11 @@ pub struct Keys<'a, 'b, K: Clone + Field<'a> + PartialEq, V: Clone + Field<'a>>
 impl<'a, 'b, K: Clone + Field<'a> + PartialEq, V: Clone + Field<'a>> Iterator
     for Keys<'a, 'b, K, V>
 {
-    type Item = Cow<'b, K>;
+    type Item = Result<Cow<'b, K>, Error>;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next().map(|(k, _)| k)
+        self.iter.next().map(|entry| entry.map(|(k, _)| k))
     }
 }
 
@@ -250,14 +328,21 @@ pub struct Values<'a, 'b, K: Clone + Field<'a> + PartialEq, V: Clone + Field<'a>
 impl<'a, 'b, K: Clone + Field<'a> + PartialEq, V: Clone + Field<'a>> Iterator
     for Values<'a, 'b, K, V>
 {
-    type Item = Cow<'b, K>;
+    type Item = Result<Cow<'b, V>, Error>;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next().map(|(k, _)| k)
+        self.iter.next().map(|entry| entry.map(|(_, v)| v))
     }
 }
 
+/// The number of nodes and entries at one depth of a [`HashMap`]'s trie.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct TrieLevel {
+    pub nodes: usize,
+    pub entries: usize,
+}
+
 #[derive(Debug)]
 pub struct HashMap<'a, K: Clone + Field<'a>, V: Clone + Field<'a>> {
     reader: Reader<'a>,
@@ -299,32 +384,69 @@ impl<'a, K: 'a + Clone + Field<'a> + Hash + PartialEq, V: 'a + Clone + Field<'a>
         self.len() == 0
     }
 
-    pub fn iter<'b>(&'b self) -> Iter<'a, 'b, K, V> {
-        if let Some(node) = self.root.as_ref() {
-            let node = AnyNode::Memory(node);
+    /// Returns the number of nodes and entries at every depth of the committed trie, starting
+    /// with the root node at depth 0. Changes that have not been committed are not included.
+    pub fn depth_stats(&self) -> Vec<TrieLevel> {
+        let mut levels: Vec<TrieLevel> = vec![];
 
-            return Iter {
-                map: self,
-                stack: vec![(node, 0)],
-            };
-        }
+        let Some(root_reference) = self.root_reference.as_ref() else {
+            return levels;
+        };
 
-        if let Some(root_reference) = self.root_reference.as_ref()
-            && let Ok(root) = self.reader.read::<HashRoot<K, V>>(root_reference)
-            && let Ok(node) = self.reader.read::<Node<'a, K, V>>(&root.node)
-        {
-            let node = AnyNode::Disk(node);
+        let Ok(root) = self.reader.read::<HashRoot<K, V>>(root_reference) else {
+            return levels;
+        };
+
+        let mut stack = vec![(root.node, 0)];
 
-            return Iter {
-                map: self,
-                stack: vec![(node, 0)],
+        while let Some((reference, depth)) = stack.pop() {
+            let Ok(node) = self.reader.read::<Node<K, V>>(&reference) else {
+                continue;
             };
+
+            if levels.len() <= depth {
+                levels.resize(depth + 1, TrieLevel::default());
+            }
+
+            levels[depth].nodes += 1;
+            levels[depth].entries += node.values.len();
+
+            for reference in node.nodes.iter() {
+                stack.push((reference.clone(), depth + 1));
+            }
         }
 
-        return Iter {
+        levels
+    }
+
+    pub fn iter<'b>(&'b self) -> Iter<'a, 'b, K, V> {
+        let mut iter = Iter {
             map: self,
             stack: vec![],
+            error: None,
+        };
+
+        if let Some(node) = self.root.as_ref() {
+            iter.stack.push((AnyNode::Memory(node), 0));
+
+            return iter;
+        }
+
+        let Some(root_reference) = self.root_reference.as_ref() else {
+            return iter;
         };
+
+        let node = self
+            .reader
+            .read::<HashRoot<K, V>>(root_reference)
+            .and_then(|root| self.reader.read::<Node<'a, K, V>>(&root.node));
+
+        match node {
+            Ok(node) => iter.stack.push((AnyNode::Disk(node), 0)),
+            Err(error) => iter.error = Some(error),
+        }
+
+        iter
     }
 
     pub fn keys<'b>(&'b self) -> Keys<'a, 'b, K, V> {
@@ -408,7 +530,7 @@ impl<'a, K: 'a + Clone + Field<'a> + Hash + PartialEq, V: 'a + Clone + Field<'a>
 
         if let Some(dense_index) = mem_node.node_mask.get_dense_index(index) {
             let node = reader
-                .read::<Node<K, V>>(&mem_node.nodes.as_ref()[dense_index])
+                .read::<Node<K, V>>(mem_node.nodes.get(dense_index)?)
                 .ok()?;
 
             mem_node.mem_node_mask.set(index);
@@ -568,7 +690,7 @@ impl<'a, K: 'a + Clone + Field<'a> + Hash + PartialEq, V: 'a + Clone + Field<'a>
             if let Some(dense_index) = mem_node.node_mask.get_dense_index(index) {
                 let node = self
                     .reader
-                    .read::<Node<K, V>>(&mem_node.nodes.as_ref()[dense_index])
+                    .read::<Node<K, V>>(mem_node.nodes.get(dense_index)?)
                     .ok()?;
 
                 mem_node.mem_node_mask.set(index);
@@ -655,55 +777,64 @@ impl<'a, K: 'a + Clone + Field<'a> + Hash + PartialEq, V: 'a + Clone + Field<'a>
         hash: u64,
         mut shift: usize,
         key: &K,
-    ) -> Option<Cow<'_, V>> {
+    ) -> Result<Option<Cow<'_, V>>, Error> {
         while shift < 64 {
             let index = ((hash >> shift) & 0b111111) as usize;
             shift += 6;
 
             if let Some(dense_index) = node.value_mask.get_dense_index(index) {
-                let reference = node.values.get(dense_index)?;
-                let key_value = self.reader.read::<KeyValue<K, V>>(reference).ok()?;
+                let Some(reference) = node.values.get(dense_index) else {
+                    return Ok(None);
+                };
+                let key_value = self.reader.read::<KeyValue<K, V>>(reference)?;
 
                 if key_value.key != *key {
-                    return None;
+                    return Ok(None);
                 }
 
-                return Some(Cow::Owned(key_value.value));
+                return Ok(Some(Cow::Owned(key_value.value)));
             }
 
             if let Some(dense_index) = node.node_mask.get_dense_index(index) {
-                let reference = node.nodes.get(dense_index)?;
-                node = self.reader.read::<Node<K, V>>(reference).ok()?;
+                let Some(reference) = node.nodes.get(dense_index) else {
+                    return Ok(None);
+                };
+                node = self.reader.read::<Node<K, V>>(reference)?;
 
                 continue;
             }
 
-            return None;
+            return Ok(None);
         }
 
         for reference in node.values.as_ref() {
-            let key_value = self.reader.read::<KeyValue<K, V>>(reference).ok()?;
+            let key_value = self.reader.read::<KeyValue<K, V>>(reference)?;
 
             if key_value.key == *key {
-                return Some(Cow::Owned(key_value.value));
+                return Ok(Some(Cow::Owned(key_value.value)));
             }
         }
 
-        None
+        Ok(None)
     }
 
-    pub fn contains_key(&self, key: &K) -> bool {
-        self.get(key).is_some()
+    pub fn contains_key(&self, key: &K) -> Result<bool, Error> {
+        Ok(self.get(key)?.is_some())
     }
 
-    pub fn get(&self, key: &K) -> Option<Cow<'_, V>> {
+    /// Returns the value of `key`. This fails if a record that the lookup reads cannot be
+    /// decoded or does not match its checksum, see [`crate::Config::with_checksum`], rather than
+    /// treating the key as missing.
+    pub fn get(&self, key: &K) -> Result<Option<Cow<'_, V>>, Error> {
         let hash = Self::hash(key);
         let mut shift = 0;
 
         let Some(mut mem_node) = self.root.as_ref() else {
-            let root_reference = self.root_reference.as_ref()?;
-            let root = self.reader.read::<HashRoot<K, V>>(root_reference).ok()?;
-            let node = self.reader.read::<Node<K, V>>(&root.node).ok()?;
+            let Some(root_reference) = self.root_reference.as_ref() else {
+                return Ok(None);
+            };
+            let root = self.reader.read::<HashRoot<K, V>>(root_reference)?;
+            let node = self.reader.read::<Node<K, V>>(&root.node)?;
 
             return self.get_from_reader(node, hash, shift, key);
         };
@@ -713,56 +844,65 @@ impl<'a, K: 'a + Clone + Field<'a> + Hash + PartialEq, V: 'a + Clone + Field<'a>
             shift += 6;
 
             if let Some(dense_index) = mem_node.mem_value_mask.get_dense_index(index) {
-                let key_value = mem_node.mem_values.get(dense_index)?;
+                let Some(key_value) = mem_node.mem_values.get(dense_index) else {
+                    return Ok(None);
+                };
 
                 if key_value.key != *key {
-                    return None;
+                    return Ok(None);
                 }
 
-                return Some(Cow::Borrowed(&key_value.value));
+                return Ok(Some(Cow::Borrowed(&key_value.value)));
             }
 
             if let Some(dense_index) = mem_node.mem_node_mask.get_dense_index(index) {
-                mem_node = mem_node.mem_nodes.get(dense_index)?;
+                let Some(next) = mem_node.mem_nodes.get(dense_index) else {
+                    return Ok(None);
+                };
+                mem_node = next;
                 continue;
             }
 
             if let Some(dense_index) = mem_node.value_mask.get_dense_index(index) {
-                let reference = mem_node.values.get(dense_index)?;
-                let key_value = self.reader.read::<KeyValue<K, V>>(reference).ok()?;
+                let Some(reference) = mem_node.values.get(dense_index) else {
+                    return Ok(None);
+                };
+                let key_value = self.reader.read::<KeyValue<K, V>>(reference)?;
 
                 if key_value.key != *key {
-                    return None;
+                    return Ok(None);
                 }
 
-                return Some(Cow::Owned(key_value.value));
+                return Ok(Some(Cow::Owned(key_value.value)));
             }
 
             if let Some(dense_index) = mem_node.node_mask.get_dense_index(index) {
-                let reference = mem_node.nodes.get(dense_index)?;
-                let node = self.reader.read::<Node<K, V>>(reference).ok()?;
+                let Some(reference) = mem_node.nodes.get(dense_index) else {
+                    return Ok(None);
+                };
+                let node = self.reader.read::<Node<K, V>>(reference)?;
 
                 return self.get_from_reader(node, hash, shift, key);
             }
 
-            return None;
+            return Ok(None);
         }
 
         for key_value in &mem_node.mem_values {
             if key_value.key == *key {
-                return Some(Cow::Borrowed(&key_value.value));
+                return Ok(Some(Cow::Borrowed(&key_value.value)));
             }
         }
 
         for reference in mem_node.values.as_ref() {
-            let key_value = self.reader.read::<KeyValue<K, V>>(reference).ok()?;
+            let key_value = self.reader.read::<KeyValue<K, V>>(reference)?;
 
             if key_value.key == *key {
-                return Some(Cow::Owned(key_value.value));
+                return Ok(Some(Cow::Owned(key_value.value)));
             }
         }
 
-        None
+        Ok(None)
     }
 
     fn commit_node<W: Seek + Write>(
@@ -860,6 +1000,7 @@ impl<'a, K: 'a + Clone + Field<'a> + Hash + PartialEq, V: 'a + Clone + Field<'a>
         let node = self.commit_node(bytes, writer, node, 0)?;
 
         let root = HashRoot {
+            layout: LayoutVersion::default(),
             node,
             count: self.count,
         };
@@ -897,8 +1038,8 @@ impl<'a, K: Clone + Field<'a> + Hash + PartialEq> HashSet<'a, K> {
         self.0.insert(key, ()).is_some()
     }
 
-    pub fn contains(&self, key: &K) -> bool {
-        self.0.get(key).is_some()
+    pub fn contains(&self, key: &K) -> Result<bool, Error> {
+        self.0.contains_key(key)
     }
 
     pub fn commit<W: Seek + Write>(
@@ -0,0 +1,280 @@
+use crate as arken;
+
+use arken::{Arken, Config, Error, Field, MappedFile, Reader, Ref, Writer};
+use bytes::BytesMut;
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{Seek, Write},
+    path::Path,
+};
+use tempfile::NamedTempFile;
+
+/// The size of a single [`IndexEntry`] in an index file, which is always written using fixed
+/// width encoding.
+const ENTRY_SIZE: usize = 16;
+
+/// The number of bytes at the start of the indexed file that the [`Fingerprint`] covers. Files
+/// are only ever appended to, such that these bytes do not change once they have been written.
+const FINGERPRINT_SIZE: usize = 4096;
+
+/// Identifies the file that an index was built for, such that an index of another file, or of an
+/// earlier file at the same path, is not used.
+#[derive(Arken, Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct Fingerprint {
+    /// The number of bytes at the start of the indexed file that the checksum covers.
+    size: u64,
+    checksum: u32,
+}
+
+impl Fingerprint {
+    fn new(bytes: &[u8]) -> Self {
+        let bytes = &bytes[..bytes.len().min(FINGERPRINT_SIZE)];
+
+        Self {
+            size: bytes.len() as u64,
+            checksum: crc32fast::hash(bytes),
+        }
+    }
+
+    fn matches(&self, bytes: &[u8]) -> bool {
+        bytes
+            .get(..self.size as usize)
+            .is_some_and(|bytes| crc32fast::hash(bytes) == self.checksum)
+    }
+}
+
+/// Locates a single record written with [`Writer::append_with_marker`].
+#[derive(Arken, Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct IndexEntry {
+    /// The offset of the record in the indexed file.
+    pub offset: u64,
+    /// The timestamp of the record, in a unit chosen by the application.
+    pub timestamp: i64,
+}
+
+/// A sidecar index that maps the sequence numbers and timestamps of the records of a single
+/// marker to their offsets in the indexed file.
+///
+/// The index file starts with a fixed width [`Config`] header, followed by the marker, a
+/// fingerprint of the start of the indexed file and the entries in the order the records were
+/// written. As every entry has the same size, the `n`-th entry can be found in O(1) and the
+/// entries can be binary searched by timestamp, provided that the timestamps are monotonic. The
+/// index only contains derived data, and can be rebuilt from the indexed file at any time using
+/// [`IndexWriter::rebuild`].
+#[derive(Clone, Debug, Default)]
+pub struct MarkerIndex<'a> {
+    marker: Cow<'a, [u8]>,
+    fingerprint: Fingerprint,
+    entries: &'a [u8],
+    config: Config,
+}
+
+impl<'a> TryFrom<&'a [u8]> for MarkerIndex<'a> {
+    type Error = Error;
+
+    fn try_from(bytes: &'a [u8]) -> Result<Self, Error> {
+        let (config, rest) = Config::from_slice(bytes, Default::default())?;
+
+        if !config.fixed {
+            return Err(Error::InvalidHeader);
+        }
+
+        let (marker, rest) = Cow::<[u8]>::from_slice(rest, config)?;
+        let (fingerprint, entries) = Fingerprint::from_slice(rest, config)?;
+
+        Ok(Self {
+            marker,
+            fingerprint,
+            entries,
+            config,
+        })
+    }
+}
+
+impl MarkerIndex<'_> {
+    /// Returns the marker of the records in this index.
+    pub fn marker(&self) -> &[u8] {
+        &self.marker
+    }
+
+    /// Returns the number of records in the index. An entry that was only partially written is
+    /// not counted.
+    pub fn len(&self) -> usize {
+        self.entries.len() / ENTRY_SIZE
+    }
+
+    /// Returns `true` if the index contains no records.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the index was built for the file of `reader`, i.e. if the start of the
+    /// file matches the fingerprint in the index, and the first and the last entry of the index
+    /// point to records with the marker. [`Reader::with_index`] ignores an index that does not
+    /// match, see [`IndexWriter::open_or_rebuild`] for rebuilding it instead. An index that lags
+    /// behind the file, as records were written without being pushed to it, still matches.
+    pub fn matches(&self, reader: &Reader<'_>) -> bool {
+        if !self.fingerprint.matches(reader.bytes()) {
+            return false;
+        }
+
+        let Some(last) = self.len().checked_sub(1) else {
+            return true;
+        };
+
+        [0, last].into_iter().all(|n| {
+            self.get(n)
+                .is_some_and(|entry| reader.is_record(&self.marker, entry.offset as usize))
+        })
+    }
+
+    /// Returns the entry of the `n`-th record, counting from the start of the indexed file.
+    pub fn get(&self, n: usize) -> Option<IndexEntry> {
+        if n >= self.len() {
+            return None;
+        }
+
+        let (entry, _) =
+            IndexEntry::from_slice(&self.entries[n * ENTRY_SIZE..], self.config).ok()?;
+
+        Some(entry)
+    }
+
+    /// Returns the sequence number of the first record with a timestamp at or after `timestamp`,
+    /// or the number of records if there is no such record.
+    pub fn search(&self, timestamp: i64) -> usize {
+        let mut low = 0;
+        let mut high = self.len();
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+
+            match self.get(mid) {
+                Some(entry) if entry.timestamp < timestamp => low = mid + 1,
+                _ => high = mid,
+            }
+        }
+
+        low
+    }
+}
+
+/// Appends entries to a [`MarkerIndex`] file.
+#[derive(Debug)]
+pub struct IndexWriter<W: Seek + Write> {
+    writer: Writer<W>,
+}
+
+impl IndexWriter<NamedTempFile> {
+    /// Creates an empty index for the records written with `marker` to the file of `reader`.
+    pub fn tempfile(
+        bytes: &mut BytesMut,
+        reader: &Reader<'_>,
+        marker: &[u8],
+    ) -> Result<Self, Error> {
+        let mut config = Config::default();
+        config.fixed_width();
+
+        let mut writer = Writer::tempfile(config)?;
+        writer.append(bytes, &Cow::Borrowed(marker))?;
+        writer.append(bytes, &Fingerprint::new(reader.bytes()))?;
+
+        Ok(Self { writer })
+    }
+
+    /// Builds a new index from the records written with `marker`, using `timestamp` to extract
+    /// the timestamp of each record.
+    pub fn rebuild<'a, T: Field<'a>, F: FnMut(&T) -> i64>(
+        bytes: &mut BytesMut,
+        reader: &Reader<'a>,
+        marker: &'a [u8],
+        mut timestamp: F,
+    ) -> Result<Self, Error> {
+        let mut iter = reader.find::<T>(marker);
+        let mut entries = vec![];
+
+        while let Some((offset, value)) = iter.next_with_offset() {
+            entries.push(IndexEntry {
+                offset: offset as u64,
+                timestamp: timestamp(&value),
+            });
+        }
+
+        let mut writer = Self::tempfile(bytes, reader, marker)?;
+
+        for entry in entries.iter().rev() {
+            writer.writer.append(bytes, entry)?;
+        }
+
+        Ok(writer)
+    }
+
+    pub fn persist<P: AsRef<Path>>(self, new_path: P) -> Result<IndexWriter<File>, Error> {
+        let writer = self.writer.persist(new_path)?;
+
+        Ok(IndexWriter { writer })
+    }
+}
+
+impl IndexWriter<File> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let writer = Writer::open(path)?;
+
+        if !writer.config().fixed {
+            return Err(Error::InvalidHeader);
+        }
+
+        Ok(Self { writer })
+    }
+
+    /// Opens the index at `path` to append to it, or rebuilds it with [`IndexWriter::rebuild`]
+    /// if there is no index at `path`, or if the index does not match the file of `reader`, see
+    /// [`MarkerIndex::matches`].
+    pub fn open_or_rebuild<'a, P: AsRef<Path>, T: Field<'a>, F: FnMut(&T) -> i64>(
+        path: P,
+        bytes: &mut BytesMut,
+        reader: &Reader<'a>,
+        marker: &'a [u8],
+        timestamp: F,
+    ) -> Result<Self, Error> {
+        let path = path.as_ref();
+
+        let matches = match MappedFile::open(path) {
+            Ok(file) => file
+                .index()
+                .is_ok_and(|index| index.marker() == marker && index.matches(reader)),
+            Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::NotFound => false,
+            Err(error) => return Err(error),
+        };
+
+        if matches {
+            return Self::open(path);
+        }
+
+        IndexWriter::rebuild(bytes, reader, marker, timestamp)?.persist(path)
+    }
+}
+
+impl<W: Seek + Write> IndexWriter<W> {
+    /// Appends an entry for a record that was just written with [`Writer::append_with_marker`].
+    pub fn push<'a, T: Field<'a>>(
+        &mut self,
+        bytes: &mut BytesMut,
+        reference: &Ref<'a, T>,
+        timestamp: i64,
+    ) -> Result<(), Error> {
+        let entry = IndexEntry {
+            offset: reference.offset as u64,
+            timestamp,
+        };
+
+        self.writer.append(bytes, &entry)?;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()
+    }
+}
@@ -1,12 +1,22 @@
-use crate::{Config, Error, Field};
+use crate::{
+    Config, Error, Field,
+    schema::{Schema, Schemas},
+};
 use ::jiff::Timestamp;
 use bytes::BytesMut;
 
 impl<'a> Field<'a> for ::jiff::Timestamp {
     fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let input = slice;
         let (value, rest) = i128::from_slice(slice, config)?;
         slice = rest;
 
+        // Check the range up front, as jiff may trip a debug assertion on values that are far out
+        // of range instead of returning an error.
+        if !(Timestamp::MIN.as_nanosecond()..=Timestamp::MAX.as_nanosecond()).contains(&value) {
+            return Err(Error::corrupt::<Self>(input));
+        }
+
         let value = Timestamp::from_nanosecond(value)?;
 
         Ok((value, slice))
@@ -17,4 +27,8 @@ impl<'a> Field<'a> for ::jiff::Timestamp {
 
         Ok(())
     }
+
+    fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Timestamp
+    }
 }
@@ -0,0 +1,48 @@
+use crate::{
+    Config, Error, Field,
+    schema::{Schema, Schemas},
+};
+use bytes::BytesMut;
+
+/// The version of the node layout of a collection, stored at the start of its root record. See
+/// [`crate::FORMAT_VERSION`].
+///
+/// Decoding rejects layouts newer than `VERSION`, and encoding always writes `VERSION`, as the
+/// nodes are written in the current layout. Files of format version 0 do not store the layout
+/// version, and are read as layout version 0.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct LayoutVersion<const VERSION: usize>(pub(crate) usize);
+
+impl<const VERSION: usize> Default for LayoutVersion<VERSION> {
+    fn default() -> Self {
+        Self(VERSION)
+    }
+}
+
+impl<'a, const VERSION: usize> Field<'a> for LayoutVersion<VERSION> {
+    fn from_slice(slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        if config.format_version() == 0 {
+            return Ok((Self(0), slice));
+        }
+
+        let (version, rest) = usize::from_slice(slice, config)?;
+
+        if version > VERSION {
+            return Err(Error::unknown_version::<Self>(slice, version));
+        }
+
+        Ok((Self(version), rest))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        if config.format_version() == 0 {
+            return Ok(());
+        }
+
+        VERSION.put_bytes(bytes, config)
+    }
+
+    fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Usize
+    }
+}
@@ -1,19 +1,47 @@
+mod byte_str;
+mod checksum;
+mod collections;
+#[cfg(any(feature = "lz4", feature = "zstd"))]
+mod compressed;
 #[cfg(feature = "rust_decimal")]
 mod decimal;
+#[cfg(feature = "encryption")]
+mod encryption;
+mod error;
+mod far_ref;
 mod float;
 mod hash_trie;
+mod index;
 #[cfg(feature = "jiff")]
 mod jiff;
+mod layout;
 mod lsm;
 mod migrate;
+mod net;
+mod owned;
+mod pod;
 mod reader;
+mod scalar;
+pub mod schema;
+mod segment;
+#[cfg(feature = "serde")]
+mod serde;
 mod signed;
+mod time;
 mod trigram;
+mod tuple;
 mod unsigned;
 #[cfg(feature = "uuid")]
 mod uuid;
+mod value;
+mod varint;
+pub mod verify;
 mod writer;
 
+use crate::{
+    schema::{FieldDef, Schema, Schemas, VariantDef},
+    verify::Verifier,
+};
 use bytes::{BufMut as _, BytesMut};
 use num_enum::TryFromPrimitive;
 use std::{
@@ -21,44 +49,133 @@ use std::{
     io::{Seek, Write},
     marker::PhantomData,
 };
-use thiserror::Error;
 
+pub use crate::byte_str::ByteStr;
+pub use crate::checksum::Checksum;
+#[cfg(any(feature = "lz4", feature = "zstd"))]
+pub use crate::compressed::{Compressed, Compression};
 #[cfg(feature = "rust_decimal")]
 pub use crate::decimal::FixedDecimal;
-pub use crate::hash_trie::{HashMap, HashRootRef, HashSet};
-pub use crate::lsm::{MergeMap, MergeRootRef, MergeSet};
-pub use crate::migrate::{MigrationStrategy, migrate, migrate_to};
-pub use crate::reader::{MappedFile, Reader};
+#[cfg(feature = "encryption")]
+pub use crate::encryption::{Cipher, Key};
+pub use crate::error::{Context, Diagnostic, Error, PathSegment};
+pub use crate::far_ref::{FarRef, MultiReader};
+pub use crate::hash_trie::{HashMap, HashRootRef, HashSet, TrieLevel};
+pub use crate::index::{IndexEntry, IndexWriter, MarkerIndex};
+pub use crate::lsm::{BlockFormat, MergeMap, MergeRootRef, MergeSet};
+pub use crate::migrate::{
+    ConvertHashMap, ConvertMergeMap, MapConversion, MigrationStrategy, migrate, migrate_to,
+};
+pub use crate::pod::{Pod, PodSlice};
+pub use crate::reader::{MappedFile, MarkerIter, Reader, Record, RecordStatus};
+pub use crate::segment::{RetentionPolicy, SegmentedMarkerIter, SegmentedReader, SegmentedWriter};
+#[cfg(feature = "serde")]
+pub use crate::serde::{Serde, SerdeMap, SerdeSeq};
 pub use crate::trigram::{
-    ByteTrigramIter, StringTrigramIter, TrigramIter, TrigramMap, TrigramRootRef, TrigramSet,
+    ByteTrigramIter, KeyValue as TrigramKeyValue, QueryResults, StringTrigramIter, TrigramIter,
+    TrigramMap, TrigramRootRef, TrigramSet,
 };
+pub use crate::value::Value;
 pub use crate::writer::Writer;
+/// Derives [`Field`] for structs and enums, encoding the fields in the order they are declared.
+///
+/// The following attributes are supported on fields:
+///
+/// - `#[arken(skip)]` does not encode the field, and decodes it as `Default::default()`.
+/// - `#[arken(skip_with = expr)]` does not encode the field, and decodes it as `expr`.
+/// - `#[arken(endian = "big" | "little" | "native")]` overrides the endianness from the header.
+/// - `#[arken(size = "fixed" | "variable")]` overrides the integer width from the header.
+///
+/// On the type itself, `#[arken(align = N)]` sets [`Field::ALIGN`], such that records of the type
+/// are written at a multiple of `N` bytes. Along with a `#[repr(C)]` layout of fixed-width fields
+/// and an implementation of [`Pod`], this allows [`Reader::read_aligned`] to borrow records
+/// straight from the mapped file.
+///
+/// # Versioning
+///
+/// Adding a field to a struct changes its encoding, such that existing records can no longer be
+/// decoded. A struct with `#[arken(version = N)]` is encoded with its version, and fields with
+/// `#[arken(since = M)]` are only decoded from records of version `M` or later. Older records
+/// take `#[arken(default = expr)]` for those fields instead, or `Default::default()` if there is
+/// no default. Records of a version newer than `N` fail with [`Error::UnknownVersion`].
+///
+/// Adding a field along with bumping the version is therefore a compatible change, as long as
+/// the fields of earlier versions are left as they are. Adding `version` to a struct that did not
+/// have one is not, as the records written before do not start with a version.
+///
+/// ```
+/// use arken::{Config, Field};
+/// use bytes::BytesMut;
+///
+/// mod v1 {
+///     use arken::Arken;
+///     use std::borrow::Cow;
+///
+///     #[derive(Arken)]
+///     #[arken(version = 1)]
+///     pub struct User<'a> {
+///         pub name: Cow<'a, str>,
+///     }
+/// }
+///
+/// mod v2 {
+///     use arken::Arken;
+///     use std::borrow::Cow;
+///
+///     #[derive(Arken, Debug, PartialEq)]
+///     #[arken(version = 2)]
+///     pub struct User<'a> {
+///         pub name: Cow<'a, str>,
+///         #[arken(since = 2, default = 18)]
+///         pub age: u8,
+///     }
+/// }
+///
+/// let config = Config::default();
+/// let mut bytes = BytesMut::new();
+///
+/// // Records written by the first version decode with the default.
+/// v1::User { name: "alice".into() }.put_bytes(&mut bytes, config)?;
+/// let (user, _) = v2::User::from_slice(&bytes, config)?;
+/// assert_eq!(user, v2::User { name: "alice".into(), age: 18 });
+///
+/// // Records written by the second version round-trip.
+/// bytes.clear();
+/// v2::User { name: "bob".into(), age: 42 }.put_bytes(&mut bytes, config)?;
+/// let (user, _) = v2::User::from_slice(&bytes, config)?;
+/// assert_eq!(user, v2::User { name: "bob".into(), age: 42 });
+///
+/// // The first version cannot decode records of the second.
+/// assert!(matches!(
+///     v1::User::from_slice(&bytes, config),
+///     Err(arken::Error::UnknownVersion { version: 2, .. }),
+/// ));
+/// # Ok::<(), arken::Error>(())
+/// ```
+///
+/// A field cannot be newer than its struct, as no record of that version can have been written:
+///
+/// ```compile_fail
+/// #[derive(arken::Arken)]
+/// #[arken(version = 1)]
+/// struct User {
+///     #[arken(since = 2)]
+///     age: u8,
+/// }
+/// ```
+///
+/// Enums cannot be versioned themselves. Instead, add new variants at the end, or add a field of
+/// the enum to a versioned struct.
+///
+/// ```compile_fail
+/// #[derive(arken::Arken)]
+/// #[arken(version = 1)]
+/// enum Status {
+///     Active,
+/// }
+/// ```
 pub use arken_impl::Arken;
 
-#[derive(Debug, Error)]
-pub enum Error {
-    #[error("incomplete")]
-    Incomplete,
-    #[error("invalid header")]
-    InvalidHeader,
-    #[error("invalid offset")]
-    InvalidOffset,
-    #[error("overflow")]
-    Overflow,
-    #[error(transparent)]
-    Io(#[from] std::io::Error),
-    #[cfg(feature = "jiff")]
-    #[error(transparent)]
-    Jiff(#[from] ::jiff::Error),
-    #[error(transparent)]
-    Mmap(#[from] mmap_rs::Error),
-    #[error(transparent)]
-    Persist(#[from] tempfile::PersistError),
-    #[cfg(feature = "uuid")]
-    #[error(transparent)]
-    Uuid(#[from] ::uuid::Error),
-}
-
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, TryFromPrimitive)]
 #[repr(u8)]
 pub enum Endian {
@@ -68,23 +185,104 @@ pub enum Endian {
     Native,
 }
 
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+/// The version of the file format written by this version of the crate.
+///
+/// # Compatibility
+///
+/// The format version is stored in the header of every file, and is bumped whenever files
+/// written by this crate can no longer be read correctly by earlier versions of it, e.g. when the
+/// encoding of a primitive or the layout of the header changes. Readers accept files of every
+/// format version up to and including their own, and reject files of newer versions with
+/// [`Error::UnsupportedVersion`] rather than misreading them. Appending to an existing file keeps
+/// writing the format version of that file.
+///
+/// The collections, i.e. [`MergeMap`], [`HashMap`] and the types built on them, store the version
+/// of their node layout in their root records, such that their layouts can evolve independently
+/// of the file format. Roots of a layout that is newer than the reader supports fail with
+/// [`Error::UnknownVersion`].
+///
+/// Changes that earlier readers can safely ignore, such as records with new markers or padding
+/// between records, do not bump either version. The encoding of your own types is up to you,
+/// see the `version` attribute of [`Arken`].
+///
+/// Format version 0 covers the files written before the format version was stored in the header.
+/// Their collections do not store a layout version, and are read as layout version 0.
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Set in the header byte if a format version byte follows.
+const VERSIONED: u8 = 1 << 6;
+
+/// Set in the header byte if the records of the file are encrypted, in which case the header is
+/// followed by the cipher, a salt and a tag that authenticates the header. Readers that do not
+/// support encryption take this bit to be part of the endianness, and reject the header.
+const ENCRYPTED: u8 = 1 << 5;
+
+/// Set in the header byte if every record is checksummed, in which case the format version is
+/// followed by the [`Checksum`]. Earlier readers reject this as an invalid endianness.
+const CHECKSUMMED: u8 = 1 << 4;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Config {
     fixed: bool,
     endian: Endian,
+    version: u8,
+    checksum: Checksum,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            fixed: false,
+            endian: Endian::default(),
+            version: FORMAT_VERSION,
+            checksum: Checksum::None,
+        }
+    }
 }
 
 impl Config {
+    /// Encodes integers other than `u8` and `i8` as LEB128 varints. Signed integers are ZigZag
+    /// encoded first, such that values close to zero take few bytes regardless of their sign,
+    /// e.g. `-1i64` takes a single byte.
     pub fn variable_width(&mut self) -> &mut Self {
         self.fixed = false;
         self
     }
 
+    /// Encodes integers as their bytes in the endianness of this configuration.
     pub fn fixed_width(&mut self) -> &mut Self {
         self.fixed = true;
         self
     }
 
+    /// Returns `true` if integers are encoded with a fixed size rather than as varints.
+    pub fn is_fixed_width(&self) -> bool {
+        self.fixed
+    }
+
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    /// Returns the format version of the file, see [`FORMAT_VERSION`].
+    pub fn format_version(&self) -> u8 {
+        self.version
+    }
+
+    /// Returns the checksum of every record, see [`Config::with_checksum`].
+    pub fn checksum(&self) -> Checksum {
+        self.checksum
+    }
+
+    /// Checksums every record with `checksum`, such that [`Reader::read`] fails with
+    /// [`Error::ChecksumMismatch`] rather than decoding a record that is corrupt. By default,
+    /// only the records written with a marker are checksummed. This is stored in the header, and
+    /// thus applies to the whole file. Files of format version 0 cannot be checksummed.
+    pub fn with_checksum(&mut self, checksum: Checksum) -> &mut Self {
+        self.checksum = checksum;
+        self
+    }
+
     pub fn with_endian(&mut self, mut endian: Endian) -> &mut Self {
         if endian == Endian::Native {
             if cfg!(target_endian = "big") {
@@ -112,23 +310,87 @@ impl<'a> Field<'a> for Config {
         let value = slice[3];
         slice = &slice[4..];
 
-        let endian = Endian::try_from(value).map_err(|_| Error::InvalidHeader)?;
+        if value & ENCRYPTED != 0 {
+            return Err(Error::Encrypted);
+        }
+
+        let endian = Endian::try_from(value & 0x0f).map_err(|_| Error::InvalidHeader)?;
         let fixed = (value >> 7) & 1 == 1;
 
-        Ok((Self { fixed, endian }, slice))
+        // Files written before the format version was introduced do not have a version byte.
+        let version = if value & VERSIONED != 0 {
+            let Some((&version, rest)) = slice.split_first() else {
+                return Err(Error::InvalidHeader);
+            };
+            slice = rest;
+
+            match version {
+                0 => return Err(Error::InvalidHeader),
+                version if version > FORMAT_VERSION => {
+                    return Err(Error::UnsupportedVersion(version));
+                }
+                version => version,
+            }
+        } else {
+            0
+        };
+
+        let checksum = if value & CHECKSUMMED != 0 {
+            let Some((&checksum, rest)) = slice.split_first() else {
+                return Err(Error::InvalidHeader);
+            };
+            slice = rest;
+
+            match Checksum::try_from(checksum) {
+                Ok(Checksum::None) | Err(_) => return Err(Error::InvalidHeader),
+                Ok(checksum) => checksum,
+            }
+        } else {
+            Checksum::None
+        };
+
+        Ok((
+            Self {
+                fixed,
+                endian,
+                version,
+                checksum,
+            },
+            slice,
+        ))
     }
 
     fn put_bytes(&self, bytes: &mut BytesMut, _: Config) -> Result<(), Error> {
         bytes.put_slice(b"ARK");
 
         let value = self.endian as u8 | (self.fixed as u8) << 7;
-        bytes.put_u8(value);
+
+        if self.version == 0 {
+            // The checksum follows the format version, so earlier formats cannot store it.
+            if self.checksum != Checksum::None {
+                return Err(Error::UnsupportedVersion(0));
+            }
+
+            bytes.put_u8(value);
+        } else if self.checksum == Checksum::None {
+            bytes.put_u8(value | VERSIONED);
+            bytes.put_u8(self.version);
+        } else {
+            bytes.put_u8(value | VERSIONED | CHECKSUMMED);
+            bytes.put_u8(self.version);
+            bytes.put_u8(self.checksum as u8);
+        }
 
         Ok(())
     }
 }
 
 pub trait Field<'a> {
+    /// The alignment of the records of this type in the file. [`Writer::append`] pads the file
+    /// such that records start at a multiple of this many bytes, which must be a power of two.
+    /// Values that are nested in other values are not aligned.
+    const ALIGN: usize = 1;
+
     fn from_slice(slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error>
     where
         Self: Sized;
@@ -143,6 +405,18 @@ pub trait Field<'a> {
     ) -> Result<(), Error> {
         Ok(())
     }
+
+    /// Checks the values referenced by this value, reporting any problems to the [`Verifier`].
+    fn verify(&self, _verifier: &mut Verifier<'a>) {}
+
+    /// Describes the encoding of this type, adding the structs and enums it refers to to
+    /// `schemas`. Types that do not override this are described as [`Schema::Opaque`].
+    fn schema(_schemas: &mut Schemas<'static>) -> Schema<'static>
+    where
+        Self: Sized,
+    {
+        Schema::Opaque(std::any::type_name::<Self>().into())
+    }
 }
 
 impl<'a> Field<'a> for () {
@@ -153,10 +427,15 @@ impl<'a> Field<'a> for () {
     fn put_bytes(&self, _: &mut BytesMut, _: Config) -> Result<(), Error> {
         Ok(())
     }
+
+    fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Unit
+    }
 }
 
 impl<'a, T: Field<'a>> Field<'a> for Option<T> {
     fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let input = slice;
         let (value, rest) = u8::from_slice(slice, config)?;
         slice = rest;
 
@@ -168,7 +447,7 @@ impl<'a, T: Field<'a>> Field<'a> for Option<T> {
 
                 Ok((Some(value), slice))
             }
-            _ => Err(Error::Incomplete),
+            value => Err(Error::invalid_discriminant::<Self>(input, value)),
         }
     }
 
@@ -200,6 +479,95 @@ impl<'a, T: Field<'a>> Field<'a> for Option<T> {
 
         Ok(())
     }
+
+    fn verify(&self, verifier: &mut Verifier<'a>) {
+        if let Some(value) = self {
+            value.verify(verifier);
+        }
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Option(Box::new(T::schema(schemas)))
+    }
+}
+
+/// Encoded as a `usize` tag, 0 for `Ok` and 1 for `Err`, followed by the value, like a derived
+/// enum.
+impl<'a, T: Field<'a>, E: Field<'a>> Field<'a> for Result<T, E> {
+    fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let input = slice;
+        let (tag, rest) = usize::from_slice(slice, config)?;
+        slice = rest;
+
+        let value = match tag {
+            0 => {
+                let (value, rest) =
+                    T::from_slice(slice, config).map_err(|error| error.with_field("Ok"))?;
+                slice = rest;
+
+                Ok(value)
+            }
+            1 => {
+                let (value, rest) =
+                    E::from_slice(slice, config).map_err(|error| error.with_field("Err"))?;
+                slice = rest;
+
+                Err(value)
+            }
+            tag => return Err(Error::invalid_tag::<Self>(input, tag)),
+        };
+
+        Ok((value, slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        match self {
+            Ok(value) => {
+                0usize.put_bytes(bytes, config)?;
+                value.put_bytes(bytes, config)?;
+            }
+            Err(value) => {
+                1usize.put_bytes(bytes, config)?;
+                value.put_bytes(bytes, config)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn migrate<W: Seek + Write>(
+        &mut self,
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+        reader: &Reader<'a>,
+    ) -> Result<(), Error> {
+        match self {
+            Ok(value) => value.migrate(bytes, writer, reader),
+            Err(value) => value.migrate(bytes, writer, reader),
+        }
+    }
+
+    fn verify(&self, verifier: &mut Verifier<'a>) {
+        match self {
+            Ok(value) => value.verify(verifier),
+            Err(value) => value.verify(verifier),
+        }
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        schemas.describe_enum::<Self>(|schemas| {
+            vec![
+                VariantDef {
+                    name: "Ok".into(),
+                    fields: vec![FieldDef::new("0", T::schema(schemas))].into(),
+                },
+                VariantDef {
+                    name: "Err".into(),
+                    fields: vec![FieldDef::new("0", E::schema(schemas))].into(),
+                },
+            ]
+        })
+    }
 }
 
 impl<'a> Field<'a> for Cow<'a, str> {
@@ -207,19 +575,28 @@ impl<'a> Field<'a> for Cow<'a, str> {
         let n = slice
             .iter()
             .position(|&b| b == 0)
-            .ok_or(Error::Incomplete)?;
-        let value = std::str::from_utf8(&slice[..n]).unwrap_or_default();
+            .ok_or_else(|| Error::incomplete::<Self>(slice))?;
+        let value = std::str::from_utf8(&slice[..n])
+            .map_err(|error| Error::invalid_utf8::<Self>(slice, error))?;
         slice = &slice[n + 1..];
 
         Ok((value.into(), slice))
     }
 
     fn put_bytes(&self, bytes: &mut BytesMut, _: Config) -> Result<(), Error> {
+        if let Some(position) = self.bytes().position(|b| b == 0) {
+            return Err(Error::InteriorNul(position));
+        }
+
         bytes.put_slice(self.as_bytes());
         bytes.put_u8(0);
 
         Ok(())
     }
+
+    fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Str
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -242,6 +619,10 @@ impl<'a, T: Field<'a>> Field<'a> for Array<'a, T> {
         let (n, rest) = usize::from_slice(slice, config)?;
         slice = rest;
 
+        if slice.len() < n {
+            return Err(Error::incomplete::<Self>(slice));
+        }
+
         let value = &slice[..n];
         slice = &slice[n..];
 
@@ -268,6 +649,10 @@ impl<'a, T: Field<'a>> Field<'a> for Array<'a, T> {
 
         Ok(())
     }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Packed(Box::new(T::schema(schemas)))
+    }
 }
 
 pub enum Iter<'a, T> {
@@ -297,6 +682,22 @@ pub struct Ref<'a, T: Field<'a>> {
     pub(crate) _marker: &'a PhantomData<T>,
 }
 
+impl<'a, T: Field<'a>> Ref<'a, T> {
+    /// Creates a reference to the value at the given offset. Reading a value through a reference
+    /// that does not point to a value of type `T` returns an error or garbage, but never panics.
+    pub fn new(offset: usize) -> Self {
+        Self {
+            offset,
+            _marker: &PhantomData,
+        }
+    }
+
+    /// Returns the offset of the value in the file.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
 impl<'a, T: Field<'a>> Field<'a> for Ref<'a, T> {
     fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
         let (offset, rest) = usize::from_slice(slice, config)?;
@@ -332,15 +733,32 @@ impl<'a, T: Field<'a>> Field<'a> for Ref<'a, T> {
 
         Ok(())
     }
+
+    fn verify(&self, verifier: &mut Verifier<'a>) {
+        if let Some(value) = verifier.visit(self) {
+            value.verify(verifier);
+        }
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Ref(Box::new(T::schema(schemas)))
+    }
 }
 
 impl<'a, T: Clone + Field<'a>, const N: usize> Field<'a> for Cow<'a, [T; N]> {
     fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
-        let values = std::array::from_fn(|_| {
-            let (value, rest) = T::from_slice(slice, config).unwrap();
+        let mut values = Vec::with_capacity(N);
+
+        for index in 0..N {
+            let (value, rest) =
+                T::from_slice(slice, config).map_err(|error| error.with_index(index))?;
             slice = rest;
-            value
-        });
+            values.push(value);
+        }
+
+        let Ok(values) = values.try_into() else {
+            return Err(Error::corrupt::<Self>(slice));
+        };
 
         Ok((Cow::Owned(values), slice))
     }
@@ -378,22 +796,62 @@ impl<'a, T: Clone + Field<'a>, const N: usize> Field<'a> for Cow<'a, [T; N]> {
 
         Ok(())
     }
+
+    fn verify(&self, verifier: &mut Verifier<'a>) {
+        for value in self.as_ref() {
+            value.verify(verifier);
+        }
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Array(Box::new(T::schema(schemas)), N)
+    }
 }
 
-impl<'a, T: Clone + Field<'a>> Field<'a> for Cow<'a, [T]> {
-    fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
-        let (n, rest) = usize::from_slice(slice, config)?;
-        slice = rest;
+/// The most memory that is allocated up front for the values of a sequence.
+const MAX_PREALLOCATION: usize = 1 << 20;
 
-        let mut values = Vec::with_capacity(n);
+/// Returns the number of values of `T` to allocate up front for a sequence of `n` values that
+/// are encoded in `slice`. The length has not been validated yet, so it is not trusted for the
+/// allocation.
+pub(crate) fn capacity<T>(n: usize, slice: &[u8]) -> usize {
+    n.min(slice.len())
+        .min(MAX_PREALLOCATION / std::mem::size_of::<T>().max(1))
+}
 
-        for _ in 0..n {
-            let (value, rest) = T::from_slice(slice, config).unwrap();
-            slice = rest;
-            values.push(value);
+/// The number of values that take up no bytes that a sequence may hold regardless of its size.
+pub(crate) const MAX_EMPTY_VALUES: usize = 1 << 16;
+
+/// Decodes a sequence of `n` values with `decode`. Values that take up no bytes would let a
+/// corrupt length keep the decoder busy for a very long time, so such values fail as a corrupt
+/// `S` if there are more of them than there are bytes left, and more than [`MAX_EMPTY_VALUES`].
+pub(crate) fn decode_seq<'a, T, S: ?Sized>(
+    mut slice: &'a [u8],
+    n: usize,
+    mut decode: impl FnMut(&'a [u8]) -> Result<(T, &'a [u8]), Error>,
+) -> Result<(Vec<T>, &'a [u8]), Error> {
+    let mut values = Vec::with_capacity(capacity::<T>(n, slice));
+
+    for index in 0..n {
+        let (value, rest) = decode(slice).map_err(|error| error.with_index(index))?;
+
+        if rest.len() == slice.len() && n > slice.len().max(MAX_EMPTY_VALUES) {
+            return Err(Error::corrupt::<S>(slice).with_index(index));
         }
 
-        Ok((Cow::Owned(values), slice))
+        slice = rest;
+        values.push(value);
+    }
+
+    Ok((values, slice))
+}
+
+impl<'a, T: Clone + Field<'a>> Field<'a> for Cow<'a, [T]> {
+    fn from_slice(slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let (n, rest) = usize::from_slice(slice, config)?;
+        let (values, rest) = decode_seq::<_, Self>(rest, n, |slice| T::from_slice(slice, config))?;
+
+        Ok((Cow::Owned(values), rest))
     }
 
     fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
@@ -422,4 +880,14 @@ impl<'a, T: Clone + Field<'a>> Field<'a> for Cow<'a, [T]> {
 
         Ok(())
     }
+
+    fn verify(&self, verifier: &mut Verifier<'a>) {
+        for value in self.as_ref() {
+            value.verify(verifier);
+        }
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Seq(Box::new(T::schema(schemas)))
+    }
 }
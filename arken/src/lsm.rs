@@ -1,15 +1,25 @@
+mod block;
+
 use crate as arken;
 
-use arken::{Arken, Error, Field, Reader, Ref, Writer};
+use self::block::{BlockCodec, BlockTable, BlockTableRef, BlockWriter};
+use arken::{
+    Arken, Config, Error, Field, Reader, Ref, Writer,
+    layout::LayoutVersion,
+    schema::{FieldDef, Schema, Schemas},
+    verify::{Problem, Verifier},
+};
 use bytes::BytesMut;
 use std::{
     borrow::Cow,
     cmp::Ordering,
-    collections::{BTreeMap, BinaryHeap},
+    collections::{BTreeMap, BinaryHeap, btree_map::Entry},
     io::{Seek, Write},
     marker::PhantomData,
 };
 
+pub use self::block::BlockFormat;
+
 #[derive(Arken, Clone, Debug)]
 pub struct KeyValue<'a, K: Field<'a>, V: Field<'a>> {
     key: K,
@@ -29,14 +39,163 @@ pub struct Node<'a, K: Clone + Field<'a>, V: Clone + Field<'a>> {
 
 pub type NodeRef<'a, K, V> = Ref<'a, Node<'a, K, V>>;
 
+/// A committed sorted table.
 #[derive(Arken, Clone, Debug)]
+pub enum Table<'a, K: Clone + Field<'a>, V: Clone + Field<'a>> {
+    Node(NodeRef<'a, K, V>),
+    Blocks(BlockTableRef<'a, K, V>),
+}
+
+/// The key-value pairs of a committed table, where `None` marks a removed key.
+type Entries<K, V> = Vec<(K, Option<V>)>;
+
+/// The version of the layout of the tables, stored in the root. Layout 0 only has [`Node`]s,
+/// whereas layout 1 stores a [`Table`] for every table, which may also be a [`BlockTable`].
+const LAYOUT_VERSION: usize = 1;
+
+#[derive(Clone, Debug)]
 pub struct MergeRoot<'a, K: Clone + Field<'a>, V: Clone + Field<'a>> {
-    nodes: Cow<'a, [NodeRef<'a, K, V>]>,
+    nodes: Cow<'a, [Table<'a, K, V>]>,
     count: usize,
 }
 
 pub type MergeRootRef<'a, K, V> = Ref<'a, MergeRoot<'a, K, V>>;
 
+impl<'a, K: Clone + Field<'a>, V: Clone + Field<'a>> Field<'a> for MergeRoot<'a, K, V> {
+    fn from_slice(slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let (layout, slice) = LayoutVersion::<LAYOUT_VERSION>::from_slice(slice, config)
+            .map_err(|error| error.with_field("layout"))?;
+
+        let (nodes, slice) = if layout.0 == 0 {
+            let (nodes, slice) = Cow::<[NodeRef<'a, K, V>]>::from_slice(slice, config)
+                .map_err(|error| error.with_field("nodes"))?;
+
+            (nodes.iter().cloned().map(Table::Node).collect(), slice)
+        } else {
+            Cow::<[Table<'a, K, V>]>::from_slice(slice, config)
+                .map_err(|error| error.with_field("nodes"))?
+        };
+
+        let (count, slice) =
+            usize::from_slice(slice, config).map_err(|error| error.with_field("count"))?;
+
+        Ok((Self { nodes, count }, slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        LayoutVersion::<LAYOUT_VERSION>::default().put_bytes(bytes, config)?;
+
+        // Files of format version 0 do not store the layout, and are therefore written in layout
+        // 0, which cannot refer to block tables.
+        if config.format_version() == 0 {
+            let nodes = self
+                .nodes
+                .iter()
+                .map(|table| match table {
+                    Table::Node(reference) => Ok(reference.clone()),
+                    Table::Blocks(_) => Err(Error::UnsupportedVersion(0)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Cow::<[NodeRef<'a, K, V>]>::Owned(nodes).put_bytes(bytes, config)?;
+        } else {
+            self.nodes.put_bytes(bytes, config)?;
+        }
+
+        self.count.put_bytes(bytes, config)
+    }
+
+    fn migrate<W: Seek + Write>(
+        &mut self,
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+        reader: &Reader<'a>,
+    ) -> Result<(), Error> {
+        self.nodes.migrate(bytes, writer, reader)
+    }
+
+    fn verify(&self, verifier: &mut Verifier<'a>) {
+        self.nodes.verify(verifier);
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        schemas.describe_struct::<Self>(|schemas| {
+            vec![
+                FieldDef::new("layout", Schema::Usize),
+                FieldDef::new("nodes", Cow::<[Table<'a, K, V>]>::schema(schemas)),
+                FieldDef::new("count", Schema::Usize),
+            ]
+        })
+    }
+}
+
+impl<'a, K: 'a + Clone + Field<'a> + Ord, V: 'a + Clone + Field<'a>> MergeRoot<'a, K, V> {
+    /// Reports a problem for every node whose keys are not in strictly ascending order. For block
+    /// tables, this checks the first keys of the blocks, as the blocks can only be decoded by a
+    /// [`MergeMap`] opened with [`MergeMap::with_blocks`].
+    pub(crate) fn verify_sorted(&self, verifier: &mut Verifier<'a>) {
+        for table in self.nodes.iter() {
+            let offset = match table {
+                Table::Node(reference) => reference.offset(),
+                Table::Blocks(reference) => reference.offset(),
+            };
+
+            if !verifier.mark_sorted(offset) {
+                continue;
+            }
+
+            // Problems with reading the node and its keys have already been reported while
+            // walking the map.
+            let Some(reader) = verifier.reader().cloned() else {
+                return;
+            };
+
+            let keys: Vec<Option<K>> = match table {
+                Table::Node(reference) => {
+                    let Ok(node) = reader.read(reference) else {
+                        continue;
+                    };
+
+                    node.values
+                        .iter()
+                        .map(|key_value| reader.read(key_value).ok().map(|key_value| key_value.key))
+                        .collect()
+                }
+                Table::Blocks(reference) => {
+                    let Ok(table) = reader.read(reference) else {
+                        continue;
+                    };
+
+                    table
+                        .blocks
+                        .iter()
+                        .map(|handle| handle.first_key(reader.config()).ok().flatten())
+                        .collect()
+                }
+            };
+
+            let mut previous: Option<K> = None;
+
+            for (index, key) in keys.into_iter().enumerate() {
+                let Some(key) = key else {
+                    previous = None;
+                    continue;
+                };
+
+                if previous.is_some_and(|previous| previous >= key) {
+                    verifier.report(Problem::Unsorted { offset, index });
+                }
+
+                previous = Some(key);
+            }
+        }
+    }
+}
+
+/// A key-value pair of a table that is being merged by an [`Iter`]. Elements are ordered such
+/// that the heap yields the smallest key first, and for equal keys, the element from the most
+/// recent table, i.e. the memory table followed by the committed tables from the newest to the
+/// oldest.
 #[derive(Debug)]
 struct Element<'a, K: Clone + Ord, V: Clone> {
     key: Cow<'a, K>,
@@ -47,7 +206,7 @@ struct Element<'a, K: Clone + Ord, V: Clone> {
 
 impl<K: Clone + Ord, V: Clone> PartialEq for Element<'_, K, V> {
     fn eq(&self, other: &Self) -> bool {
-        self.key.eq(&other.key)
+        self.cmp(other) == Ordering::Equal
     }
 }
 
@@ -61,57 +220,84 @@ impl<K: Clone + Ord, V: Clone> PartialOrd for Element<'_, K, V> {
 
 impl<K: Clone + Ord, V: Clone> Ord for Element<'_, K, V> {
     fn cmp(&self, other: &Self) -> Ordering {
-        other.key.cmp(&self.key)
+        other.key.cmp(&self.key).then(self.table.cmp(&other.table))
     }
 }
 
+/// The position of an [`Iter`] in a [`BlockTable`], which is decoded one block at a time.
+#[derive(Debug)]
+struct BlockCursor<'a, K: Field<'a>, V: Field<'a>> {
+    table: BlockTable<'a, K, V>,
+    /// The index of the next block to decode.
+    block: usize,
+    entries: std::vec::IntoIter<(K, Option<V>)>,
+}
+
+/// Iterates over the entries of a [`MergeMap`]. A record that cannot be read is yielded as an
+/// error, after which the iterator continues without the rest of the table it belongs to.
 #[derive(Debug)]
 pub struct Iter<'a, 'b, K: Clone + Field<'a> + Ord, V: Clone + Field<'a>> {
     map: &'b MergeMap<'a, K, V>,
     heap: BinaryHeap<Element<'b, K, V>>,
     iter: std::collections::btree_map::Iter<'b, K, Option<V>>,
+    cursors: BTreeMap<usize, BlockCursor<'a, K, V>>,
+    /// The errors that have not been yielded yet.
+    errors: Vec<Error>,
 }
 
-impl<'a, 'b, K: Clone + Field<'a> + Ord, V: Clone + Field<'a>> Iterator for Iter<'a, 'b, K, V> {
-    type Item = (Cow<'b, K>, Cow<'b, V>);
+impl<'a, 'b, K: 'a + Clone + Field<'a> + Ord, V: 'a + Clone + Field<'a>> Iter<'a, 'b, K, V> {
+    /// Pushes the element that follows `element` in its table onto the heap.
+    fn advance(&mut self, element: &Element<'b, K, V>) {
+        let next = element.next + 1;
+
+        if element.table == usize::MAX {
+            let Some((key, value)) = self.iter.next() else {
+                return;
+            };
+
+            self.heap.push(Element {
+                key: Cow::Borrowed(key),
+                value: value.as_ref().map(Cow::Borrowed),
+                table: element.table,
+                next,
+            });
+
+            return;
+        }
+
+        match self.map.entry(element.table, next, &mut self.cursors) {
+            Ok(Some((key, value))) => self.heap.push(Element {
+                key: Cow::Owned(key),
+                value: value.map(Cow::Owned),
+                table: element.table,
+                next,
+            }),
+            Ok(None) => {}
+            Err(error) => self.errors.push(error),
+        }
+    }
+}
+
+impl<'a, 'b, K: 'a + Clone + Field<'a> + Ord, V: 'a + Clone + Field<'a>> Iterator
+    for Iter<'a, 'b, K, V>
+{
+    type Item = Result<(Cow<'b, K>, Cow<'b, V>), Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut key = None;
-        let mut value = None;
+        loop {
+            if let Some(error) = self.errors.pop() {
+                return Some(Err(error));
+            }
 
-        while value.is_none() {
             let element = self.heap.pop()?;
-            key = Some(element.key);
-            value = element.value;
-
-            if element.table == usize::MAX {
-                if let Some((key, value)) = self.iter.next() {
-                    self.heap.push(Element {
-                        key: Cow::Borrowed(key),
-                        value: value.as_ref().map(Cow::Borrowed),
-                        table: element.table,
-                        next: element.next + 1,
-                    });
-                }
-            }
+            self.advance(&element);
 
-            if let Some(root_reference) = self.map.root_reference.as_ref()
-                && let Ok(root) = self.map.reader.read::<MergeRoot<K, V>>(root_reference)
-                && let Some(reference) = root.nodes.get(element.table)
-                && let Ok(node) = self.map.reader.read::<Node<'a, K, V>>(reference)
-                && let Some(reference) = node.values.get(element.next + 1)
-                && let Ok(key_value) = self.map.reader.read::<KeyValue<'a, K, V>>(reference)
-            {
-                self.heap.push(Element {
-                    key: Cow::Owned(key_value.key),
-                    value: key_value.value.map(Cow::Owned),
-                    table: element.table,
-                    next: element.next + 1,
-                });
-            }
+            let key = element.key;
+            let value = element.value;
 
+            // Skip the older versions of the key.
             while let Some(element) = self.heap.peek() {
-                if key.as_ref().map(|key| *key != element.key).unwrap_or(false) {
+                if key != element.key {
                     break;
                 }
 
@@ -119,40 +305,13 @@ impl<'a, 'b, K: Clone + Field<'a> + Ord, V: Clone + Field<'a>> Iterator for Iter
                     break;
                 };
 
-                value = element.value;
-
-                if element.table == usize::MAX {
-                    if let Some((key, value)) = self.iter.next() {
-                        self.heap.push(Element {
-                            key: Cow::Borrowed(key),
-                            value: value.as_ref().map(Cow::Borrowed),
-                            table: element.table,
-                            next: element.next + 1,
-                        });
-                    }
-                }
+                self.advance(&element);
+            }
 
-                if let Some(root_reference) = self.map.root_reference.as_ref()
-                    && let Ok(root) = self.map.reader.read::<MergeRoot<K, V>>(root_reference)
-                    && let Some(reference) = root.nodes.get(element.table)
-                    && let Ok(node) = self.map.reader.read::<Node<'a, K, V>>(reference)
-                    && let Some(reference) = node.values.get(element.next + 1)
-                    && let Ok(key_value) = self.map.reader.read::<KeyValue<'a, K, V>>(reference)
-                {
-                    self.heap.push(Element {
-                        key: Cow::Owned(key_value.key),
-                        value: key_value.value.map(Cow::Owned),
-                        table: element.table,
-                        next: element.next + 1,
-                    });
-                }
+            if let Some(value) = value {
+                return Some(Ok((key, value)));
             }
         }
-
-        let key = key?;
-        let value = value?;
-
-        Some((key, value))
     }
 }
 
@@ -162,11 +321,11 @@ pub struct Keys<'a, 'b, K: Clone + Field<'a> + Ord, V: Clone + Field<'a>> {
 }
 
 impl<'a, 'b, K: Clone + Field<'a> + Ord, V: Clone + Field<'a>> Iterator for Keys<'a, 'b, K, V> {
-    type Item = Cow<'b, K>;
+    type Item = Result<Cow<'b, K>, Error>;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next().map(|(k, _)| k)
+        self.iter.next().map(|entry| entry.map(|(k, _)| k))
     }
 }
 
@@ -176,11 +335,11 @@ pub struct Values<'a, 'b, K: Clone + Field<'a> + Ord, V: Clone + Field<'a>> {
 }
 
 impl<'a, 'b, K: Clone + Field<'a> + Ord, V: Clone + Field<'a>> Iterator for Values<'a, 'b, K, V> {
-    type Item = Cow<'b, K>;
+    type Item = Result<Cow<'b, V>, Error>;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next().map(|(k, _)| k)
+        self.iter.next().map(|entry| entry.map(|(_, v)| v))
     }
 }
 
@@ -203,20 +362,26 @@ impl<'a, 'b, K: Clone + Field<'a> + Ord, V: Clone + Field<'a>> Iterator for Valu
 /// that keys must be of a type that implements the [std::cmp::Ord] trait, such that two keys can
 /// always be compared to determing their [std::cmp::Ordering]. Examples of keys with a total order
 /// are strings with lexicographical order, and numbers with their natural order.
+///
+/// By default, every committed table is a node that refers to a record for every key-value pair.
+/// [`MergeMap::with_blocks`] instead packs the key-value pairs into blocks with prefix-compressed
+/// keys, which can optionally be compressed as a whole, see [`BlockFormat`].
 #[derive(Debug)]
 pub struct MergeMap<'a, K: Clone + Field<'a>, V: Clone + Field<'a>> {
     reader: Reader<'a>,
     mem_table: BTreeMap<K, Option<V>>,
     root_reference: Option<MergeRootRef<'a, K, V>>,
     root: Option<MergeRoot<'a, K, V>>,
+    blocks: Option<BlockCodec<K, V>>,
 }
 
 impl<'a, K: 'a + Clone + Field<'a> + Ord, V: 'a + Clone + Field<'a>> MergeMap<'a, K, V> {
-    fn read_root(&self) -> Option<MergeRoot<'a, K, V>> {
-        let root_reference = self.root_reference.as_ref()?;
-        let root = self.reader.read(root_reference).ok()?;
+    fn read_root(&self) -> Result<Option<MergeRoot<'a, K, V>>, Error> {
+        let Some(root_reference) = self.root_reference.as_ref() else {
+            return Ok(None);
+        };
 
-        Some(root)
+        Ok(Some(self.reader.read(root_reference)?))
     }
 
     fn prepare_root(&mut self) {
@@ -225,8 +390,13 @@ impl<'a, K: 'a + Clone + Field<'a> + Ord, V: 'a + Clone + Field<'a>> MergeMap<'a
             return;
         }
 
-        // Try reading the root from disk. If there is no root, prepare an empty root.
-        let Some(mut root) = self.read_root() else {
+        // Try reading the root from disk. If there is no root, prepare an empty root. If the root
+        // cannot be read, it is left uncached, such that committing reports the error.
+        let Ok(root) = self.read_root() else {
+            return;
+        };
+
+        let Some(mut root) = root else {
             self.root = Some(MergeRoot {
                 nodes: Cow::Borrowed(&[]),
                 count: 0,
@@ -239,24 +409,18 @@ impl<'a, K: 'a + Clone + Field<'a> + Ord, V: 'a + Clone + Field<'a>> MergeMap<'a
         // elements. Read their key-value pairs into the memory table, such that we can coalesce
         // these nodes with the newly written node to keep the number of small tables reasonable.
         while self.mem_table.len() < 4096 {
-            let Some(reference) = root.nodes.last() else {
+            let Some(table) = root.nodes.last() else {
                 break;
             };
 
-            let Ok(node) = self.reader.read::<Node<'a, K, V>>(reference) else {
+            let Ok(Some(entries)) = self.small_table_entries(table) else {
                 break;
             };
 
-            if node.values.len() >= 4096 {
-                break;
-            }
-
-            for reference in node.values.as_ref() {
-                let Ok(key_value) = self.reader.read::<KeyValue<'a, K, V>>(reference) else {
-                    continue;
-                };
-
-                self.mem_table.insert(key_value.key, key_value.value);
+            // The tables are merged from the newest to the oldest, so keys that are already in the
+            // memory table are more recent.
+            for (key, value) in entries {
+                self.mem_table.entry(key).or_insert(value);
             }
 
             let mut nodes = root.nodes.into_owned();
@@ -273,15 +437,132 @@ impl<'a, K: 'a + Clone + Field<'a> + Ord, V: 'a + Clone + Field<'a>> MergeMap<'a
             mem_table: BTreeMap::new(),
             root_reference,
             root: None,
+            blocks: None,
         }
     }
 
+    /// Returns the key-value pairs of a committed table with fewer than 4096 key-value pairs, or
+    /// `None` if the table is larger.
+    fn small_table_entries(&self, table: &Table<'a, K, V>) -> Result<Option<Entries<K, V>>, Error> {
+        match table {
+            Table::Node(reference) => {
+                let node = self.reader.read::<Node<'a, K, V>>(reference)?;
+
+                if node.values.len() >= 4096 {
+                    return Ok(None);
+                }
+
+                let entries = node
+                    .values
+                    .iter()
+                    .map(|reference| {
+                        let key_value = self.reader.read::<KeyValue<'a, K, V>>(reference)?;
+
+                        Ok((key_value.key, key_value.value))
+                    })
+                    .collect::<Result<_, Error>>()?;
+
+                Ok(Some(entries))
+            }
+            Table::Blocks(reference) => {
+                let codec = self.codec(reference)?;
+                let table = self.reader.read(reference)?;
+
+                if table.count >= 4096 {
+                    return Ok(None);
+                }
+
+                let mut entries = Vec::with_capacity(table.count);
+
+                for handle in table.blocks.iter() {
+                    let block = self.reader.read(&handle.block)?;
+                    entries.extend(codec.entries(&block, self.reader.config())?);
+                }
+
+                Ok(Some(entries))
+            }
+        }
+    }
+
+    /// Returns the `index`-th key-value pair of the `table`-th committed table. The key-value
+    /// pairs of a block table are decoded one block at a time into `cursors`, and must therefore
+    /// be requested in order.
+    fn entry(
+        &self,
+        table: usize,
+        index: usize,
+        cursors: &mut BTreeMap<usize, BlockCursor<'a, K, V>>,
+    ) -> Result<Option<(K, Option<V>)>, Error> {
+        let codec = self.blocks.as_ref();
+
+        let cursor = match cursors.entry(table) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let Some(root) = self.read_root()? else {
+                    return Ok(None);
+                };
+
+                match root.nodes.get(table) {
+                    None => return Ok(None),
+                    Some(Table::Node(reference)) => {
+                        let node = self.reader.read::<Node<'a, K, V>>(reference)?;
+
+                        let Some(reference) = node.values.get(index) else {
+                            return Ok(None);
+                        };
+
+                        let key_value = self.reader.read::<KeyValue<'a, K, V>>(reference)?;
+
+                        return Ok(Some((key_value.key, key_value.value)));
+                    }
+                    Some(Table::Blocks(reference)) => {
+                        self.codec(reference)?;
+
+                        entry.insert(BlockCursor {
+                            table: self.reader.read(reference)?,
+                            block: 0,
+                            entries: vec![].into_iter(),
+                        })
+                    }
+                }
+            }
+        };
+
+        // A cursor is only created for a block table if there is a codec.
+        let Some(codec) = codec else {
+            return Ok(None);
+        };
+
+        loop {
+            if let Some(entry) = cursor.entries.next() {
+                return Ok(Some(entry));
+            }
+
+            let Some(handle) = cursor.table.blocks.get(cursor.block) else {
+                return Ok(None);
+            };
+
+            cursor.block += 1;
+
+            let block = self.reader.read(&handle.block)?;
+            cursor.entries = codec.entries(&block, self.reader.config())?.into_iter();
+        }
+    }
+
+    /// Returns the codec to decode the block table at `reference` with, which requires the map
+    /// to be opened with [`MergeMap::with_blocks`].
+    fn codec(&self, reference: &BlockTableRef<'a, K, V>) -> Result<&BlockCodec<K, V>, Error> {
+        self.blocks
+            .as_ref()
+            .ok_or(Error::BlocksRequired(reference.offset()))
+    }
+
     /// Returns the number of elements in the map.
     pub fn len(&self) -> usize {
         self.root
             .as_ref()
             .map(|root| root.count)
-            .or(self.read_root().map(|root| root.count))
+            .or_else(|| self.read_root().ok().flatten().map(|root| root.count))
             .unwrap_or(0)
     }
 
@@ -290,47 +571,133 @@ impl<'a, K: 'a + Clone + Field<'a> + Ord, V: 'a + Clone + Field<'a>> MergeMap<'a
         self.len() == 0
     }
 
+    /// Returns the number of key-value pairs in each of the committed sorted tables, from the
+    /// oldest to the most recent table.
+    pub fn node_sizes(&self) -> Vec<usize> {
+        let Ok(Some(root)) = self.read_root() else {
+            return vec![];
+        };
+
+        root.nodes
+            .iter()
+            .map(|table| match table {
+                Table::Node(reference) => self
+                    .reader
+                    .read::<Node<'a, K, V>>(reference)
+                    .map(|node| node.values.len())
+                    .unwrap_or(0),
+                Table::Blocks(reference) => self
+                    .reader
+                    .read(reference)
+                    .map(|table| table.count)
+                    .unwrap_or(0),
+            })
+            .collect()
+    }
+
     /// Returns `true` if the map contains a value for the specified key.
-    pub fn contains_key(&self, key: &K) -> bool {
-        self.get(key).is_some()
+    pub fn contains_key(&self, key: &K) -> Result<bool, Error> {
+        Ok(self.get(key)?.is_some())
     }
 
-    pub fn get(&self, key: &K) -> Option<Cow<'_, V>> {
+    /// Returns the value of `key`. This fails if a record that the lookup reads cannot be
+    /// decoded or does not match its checksum, see [`crate::Config::with_checksum`], rather than
+    /// looking past it.
+    pub fn get(&self, key: &K) -> Result<Option<Cow<'_, V>>, Error> {
         if let Some(value) = self.mem_table.get(key) {
-            return value.as_ref().map(|value| Cow::Borrowed(value));
+            return Ok(value.as_ref().map(|value| Cow::Borrowed(value)));
         }
 
-        let root = self.read_root()?;
+        let Some(root_reference) = self.root_reference.as_ref() else {
+            return Ok(None);
+        };
 
-        for reference in root.nodes.iter().rev() {
-            let Ok(node) = self.reader.read::<Node<'a, K, V>>(reference) else {
-                continue;
+        let root = self.reader.read(root_reference)?;
+
+        for table in root.nodes.iter().rev() {
+            let value = match table {
+                Table::Node(reference) => self.get_in_node(reference, key)?,
+                Table::Blocks(reference) => self.get_in_blocks(reference, key)?,
             };
 
-            let result = node.values.binary_search_by(|reference| {
-                let Ok(key_value) = self.reader.read::<KeyValue<'a, K, V>>(reference) else {
-                    return Ordering::Less;
-                };
+            if let Some(value) = value {
+                return Ok(value.map(Cow::Owned));
+            }
+        }
 
-                key_value.key.cmp(key)
-            });
+        Ok(None)
+    }
 
-            let Ok(index) = result else {
-                continue;
-            };
+    /// Looks up `key` in a committed node. This returns `None` if the node does not contain the
+    /// key, and `Some(None)` if the key was removed.
+    fn get_in_node(
+        &self,
+        reference: &NodeRef<'a, K, V>,
+        key: &K,
+    ) -> Result<Option<Option<V>>, Error> {
+        let node = self.reader.read::<Node<'a, K, V>>(reference)?;
+        let mut failure = None;
+
+        let result = node.values.binary_search_by(|reference| {
+            match self.reader.read::<KeyValue<'a, K, V>>(reference) {
+                Ok(key_value) => key_value.key.cmp(key),
+                Err(error) => {
+                    failure.get_or_insert(error);
+                    Ordering::Less
+                }
+            }
+        });
 
-            let reference = &node.values[index];
+        if let Some(error) = failure {
+            return Err(error);
+        }
 
-            let Ok(key_value) = self.reader.read::<KeyValue<'a, K, V>>(reference) else {
-                continue;
-            };
+        let Ok(index) = result else {
+            return Ok(None);
+        };
+
+        let key_value = self
+            .reader
+            .read::<KeyValue<'a, K, V>>(&node.values[index])?;
 
-            let value = key_value.value?;
+        Ok(Some(key_value.value))
+    }
+
+    /// Looks up `key` in a committed block table, like [`MergeMap::get_in_node`]. Only the block
+    /// that may contain the key is decoded.
+    fn get_in_blocks(
+        &self,
+        reference: &BlockTableRef<'a, K, V>,
+        key: &K,
+    ) -> Result<Option<Option<V>>, Error> {
+        let codec = self.codec(reference)?;
+        let table = self.reader.read(reference)?;
+        let config = self.reader.config();
+
+        let mut failed = None;
+
+        let index = table
+            .blocks
+            .partition_point(|handle| match handle.first_key::<K>(config) {
+                Ok(first_key) => first_key.is_some_and(|first_key| first_key <= *key),
+                Err(error) => {
+                    failed.get_or_insert(error);
+                    false
+                }
+            });
 
-            return Some(Cow::Owned(value));
+        // A first key that fails to decode would otherwise send the lookup to the wrong block.
+        if let Some(error) = failed {
+            return Err(error);
         }
 
-        None
+        let Some(index) = index.checked_sub(1) else {
+            return Ok(None);
+        };
+
+        let block = self.reader.read(&table.blocks[index].block)?;
+
+        codec.get(&block, key, config)
     }
 
     /// Gets an iterator over the entries of the map, sorted by key.
@@ -348,35 +715,39 @@ impl<'a, K: 'a + Clone + Field<'a> + Ord, V: 'a + Clone + Field<'a>> MergeMap<'a
             });
         }
 
-        if let Some(root_reference) = self.root_reference.as_ref()
-            && let Ok(root) = self.reader.read::<MergeRoot<K, V>>(root_reference)
-        {
-            for (index, reference) in root.nodes.iter().enumerate() {
-                let Ok(node) = self.reader.read::<Node<'a, K, V>>(reference) else {
-                    continue;
-                };
-
-                let Some(reference) = node.values.first() else {
-                    continue;
-                };
-
-                let Ok(key_value) = self.reader.read::<KeyValue<'a, K, V>>(reference) else {
-                    continue;
-                };
-
-                heap.push(Element {
-                    key: Cow::Owned(key_value.key),
-                    value: key_value.value.map(Cow::Owned),
-                    table: index,
-                    next: 0,
-                });
+        let mut cursors = BTreeMap::new();
+        let mut errors = vec![];
+
+        match self.read_root() {
+            Ok(Some(root)) => {
+                for index in 0..root.nodes.len() {
+                    let (key, value) = match self.entry(index, 0, &mut cursors) {
+                        Ok(Some(entry)) => entry,
+                        Ok(None) => continue,
+                        Err(error) => {
+                            errors.push(error);
+                            continue;
+                        }
+                    };
+
+                    heap.push(Element {
+                        key: Cow::Owned(key),
+                        value: value.map(Cow::Owned),
+                        table: index,
+                        next: 0,
+                    });
+                }
             }
+            Ok(None) => {}
+            Err(error) => errors.push(error),
         }
 
         Iter {
             map: self,
             heap,
             iter,
+            cursors,
+            errors,
         }
     }
 
@@ -400,7 +771,7 @@ impl<'a, K: 'a + Clone + Field<'a> + Ord, V: 'a + Clone + Field<'a>> MergeMap<'a
     pub fn insert(&mut self, key: K, value: V) -> Option<V> {
         self.prepare_root();
 
-        let has_key = self.contains_key(&key);
+        let has_key = self.has_key(&key);
         self.mem_table.insert(key, Some(value));
 
         if !has_key && let Some(root) = &mut self.root {
@@ -413,7 +784,7 @@ impl<'a, K: 'a + Clone + Field<'a> + Ord, V: 'a + Clone + Field<'a>> MergeMap<'a
     pub fn remove(&mut self, key: &K) -> bool {
         self.prepare_root();
 
-        if !self.contains_key(key) {
+        if !self.has_key(key) {
             return false;
         }
 
@@ -426,6 +797,12 @@ impl<'a, K: 'a + Clone + Field<'a> + Ord, V: 'a + Clone + Field<'a>> MergeMap<'a
         true
     }
 
+    /// Returns `true` if the map contains the key, or if the records that would tell cannot be
+    /// read, such that a removal still masks the key.
+    fn has_key(&self, key: &K) -> bool {
+        !matches!(self.get(key), Ok(None))
+    }
+
     pub fn commit<W: Seek + Write>(
         &mut self,
         bytes: &mut BytesMut,
@@ -435,32 +812,59 @@ impl<'a, K: 'a + Clone + Field<'a> + Ord, V: 'a + Clone + Field<'a>> MergeMap<'a
             return Ok(self.root_reference.clone());
         }
 
+        // The root is only missing if it could not be read.
         let Some(mut root) = self.root.clone() else {
+            self.read_root()?;
+
             return Ok(self.root_reference.clone());
         };
 
-        let mut values = Vec::with_capacity(self.mem_table.len());
+        let config = writer.config();
 
-        for (key, value) in std::mem::take(&mut self.mem_table) {
-            let key_value = KeyValue {
-                key,
-                value,
-                _key_lifetime: &PhantomData,
-                _value_lifetime: &PhantomData,
-            };
+        let table = match &self.blocks {
+            Some(codec) if config.format_version() > 0 => {
+                let mut blocks = BlockWriter::new(codec.format);
+                let mut key_bytes = BytesMut::new();
+                let mut value_bytes = BytesMut::new();
 
-            let reference = writer.append(bytes, &key_value)?;
-            values.push(reference);
-        }
+                for (key, value) in std::mem::take(&mut self.mem_table) {
+                    key_bytes.clear();
+                    value_bytes.clear();
+                    key.put_bytes(&mut key_bytes, config)?;
+                    value.put_bytes(&mut value_bytes, config)?;
 
-        let node = Node {
-            values: Cow::Owned(values),
-        };
+                    blocks.push(&key_bytes, &value_bytes, bytes, writer)?;
+                }
+
+                let table: BlockTable<'a, K, V> = blocks.finish(bytes, writer)?;
+
+                Table::Blocks(writer.append(bytes, &table)?)
+            }
+            _ => {
+                let mut values = Vec::with_capacity(self.mem_table.len());
+
+                for (key, value) in std::mem::take(&mut self.mem_table) {
+                    let key_value = KeyValue {
+                        key,
+                        value,
+                        _key_lifetime: &PhantomData,
+                        _value_lifetime: &PhantomData,
+                    };
+
+                    let reference = writer.append(bytes, &key_value)?;
+                    values.push(reference);
+                }
 
-        let reference = writer.append(bytes, &node)?;
+                let node = Node {
+                    values: Cow::Owned(values),
+                };
+
+                Table::Node(writer.append(bytes, &node)?)
+            }
+        };
 
         let mut nodes = std::mem::take(&mut root.nodes).into_owned();
-        nodes.push(reference);
+        nodes.push(table);
         root.nodes = Cow::Owned(nodes);
 
         let reference = writer.append(bytes, &root)?;
@@ -469,6 +873,28 @@ impl<'a, K: 'a + Clone + Field<'a> + Ord, V: 'a + Clone + Field<'a>> MergeMap<'a
     }
 }
 
+impl<'a, K, V> MergeMap<'a, K, V>
+where
+    K: 'a + Clone + for<'b> Field<'b> + Ord,
+    V: 'a + Clone + for<'b> Field<'b>,
+{
+    /// Commits tables as block tables in the given format. The keys and values are decoded from
+    /// blocks as owned values, which requires them to decode for any lifetime, e.g. `String`
+    /// rather than `Cow<'a, str>`.
+    ///
+    /// Block tables can only be read by maps on which this has been called. Other maps fail to
+    /// look up or iterate over their keys with [`Error::BlocksRequired`], so every map that opens
+    /// the same root should enable them. Files of format version 0 cannot store block tables, so
+    /// nodes are committed to such files instead.
+    ///
+    /// Maps with borrowed keys, such as the `ByteStr` keys of [`crate::TrigramMap`], cannot use
+    /// block tables, as they do not meet the `for<'b> Field<'b>` bound.
+    pub fn with_blocks(&mut self, format: BlockFormat) -> &mut Self {
+        self.blocks = Some(BlockCodec::new(format));
+        self
+    }
+}
+
 pub struct MergeSet<'a, K: Clone + Field<'a>>(MergeMap<'a, K, ()>);
 
 impl<'a, K: Clone + Field<'a> + Ord> MergeSet<'a, K> {
@@ -496,8 +922,8 @@ impl<'a, K: Clone + Field<'a> + Ord> MergeSet<'a, K> {
         self.0.insert(key, ()).is_some()
     }
 
-    pub fn contains(&self, key: &K) -> bool {
-        self.0.get(key).is_some()
+    pub fn contains(&self, key: &K) -> Result<bool, Error> {
+        self.0.contains_key(key)
     }
 
     pub fn commit<W: Seek + Write>(
@@ -508,3 +934,11 @@ impl<'a, K: Clone + Field<'a> + Ord> MergeSet<'a, K> {
         self.0.commit(bytes, writer)
     }
 }
+
+impl<'a, K: 'a + Clone + for<'b> Field<'b> + Ord> MergeSet<'a, K> {
+    /// Commits tables as block tables in the given format. See [`MergeMap::with_blocks`].
+    pub fn with_blocks(&mut self, format: BlockFormat) -> &mut Self {
+        self.0.with_blocks(format);
+        self
+    }
+}
@@ -0,0 +1,470 @@
+//! Block tables, which pack the key-value pairs of a committed table into blocks, rather than
+//! writing a record for every pair.
+//!
+//! Within a block, every key only stores the suffix that differs from the key before it, except
+//! for every `restart_interval`-th key, which is stored in full. Such a key is called a restart
+//! point. A lookup binary searches the restart points of a block, and then scans at most
+//! `restart_interval` pairs. The table itself holds the first key of every block, such that a
+//! lookup only decodes a single block.
+//!
+//! The data of a block is the sequence of its entries, followed by the offsets of the restart
+//! points and the number of restart points, as little endian `u32`s. Every entry consists of the
+//! length of the prefix shared with the previous key, the length of the suffix and the length of
+//! the value as varints, regardless of the configuration of the file, followed by the suffix and
+//! the value. The keys and values are the encodings of `K` and `Option<V>`, where `None` marks a
+//! removed key.
+
+use crate as arken;
+
+#[cfg(any(feature = "lz4", feature = "zstd"))]
+use arken::Compression;
+use arken::{Arken, ByteStr, Config, Error, Field, Ref, Writer};
+use bytes::{BufMut as _, BytesMut};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    io::{Seek, Write},
+    marker::PhantomData,
+};
+
+/// The format in which [`crate::MergeMap::with_blocks`] writes its tables.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BlockFormat {
+    block_size: usize,
+    restart_interval: usize,
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    compression: Compression,
+}
+
+impl Default for BlockFormat {
+    fn default() -> Self {
+        Self {
+            block_size: 4096,
+            restart_interval: 16,
+            #[cfg(any(feature = "lz4", feature = "zstd"))]
+            compression: Compression::None,
+        }
+    }
+}
+
+impl BlockFormat {
+    /// Sets the size in bytes of the entries after which a block is finished, before
+    /// compression.
+    pub fn with_block_size(&mut self, block_size: usize) -> &mut Self {
+        self.block_size = block_size;
+        self
+    }
+
+    /// Sets the number of key-value pairs from one key that is stored in full to the next.
+    /// Smaller intervals speed up lookups, whereas larger intervals compress keys better.
+    pub fn with_restart_interval(&mut self, restart_interval: usize) -> &mut Self {
+        self.restart_interval = restart_interval.max(1);
+        self
+    }
+
+    /// Sets the algorithm with which every block is compressed. Blocks that do not get any smaller
+    /// are stored as is.
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    pub fn with_compression(&mut self, compression: Compression) -> &mut Self {
+        self.compression = compression;
+        self
+    }
+}
+
+/// A block of key-value pairs, see the module documentation.
+#[derive(Arken, Clone, Debug)]
+pub struct Block<'a> {
+    /// The tag of the [`Compression`] of the data, where 0 means that it is not compressed.
+    compression: u8,
+    /// The size of the data before compression.
+    size: usize,
+    data: ByteStr<'a>,
+}
+
+pub type BlockRef<'a> = Ref<'a, Block<'a>>;
+
+impl<'a> Block<'a> {
+    /// Returns the data of the block, decompressing it if needed.
+    fn data(&self) -> Result<Cow<'a, [u8]>, Error> {
+        if self.compression == 0 {
+            return Ok(self.data.0.clone());
+        }
+
+        #[cfg(any(feature = "lz4", feature = "zstd"))]
+        return Compression::decompress::<Self>(
+            &self.data,
+            self.compression,
+            &self.data,
+            self.size,
+        )
+        .map(Cow::Owned);
+
+        #[cfg(not(any(feature = "lz4", feature = "zstd")))]
+        Err(Error::invalid_discriminant::<Self>(
+            &self.data,
+            self.compression,
+        ))
+    }
+}
+
+#[derive(Arken, Clone, Debug)]
+pub struct BlockHandle<'a> {
+    /// The encoding of the first key in the block.
+    first_key: ByteStr<'a>,
+    pub(crate) block: BlockRef<'a>,
+}
+
+impl<'a> BlockHandle<'a> {
+    /// Decodes the first key of the block. This borrows from the file, and therefore returns
+    /// `None` for handles that have not been read from a file.
+    pub(crate) fn first_key<K: Field<'a>>(&self, config: Config) -> Result<Option<K>, Error> {
+        let Cow::Borrowed(bytes) = self.first_key.0 else {
+            return Ok(None);
+        };
+
+        let (key, _) = K::from_slice(bytes, config)?;
+
+        Ok(Some(key))
+    }
+}
+
+/// A committed table that packs its key-value pairs into blocks.
+#[derive(Arken, Clone, Debug)]
+pub struct BlockTable<'a, K: Field<'a>, V: Field<'a>> {
+    pub(crate) blocks: Cow<'a, [BlockHandle<'a>]>,
+    /// The number of key-value pairs in the table.
+    pub(crate) count: usize,
+    #[arken(skip_with = &PhantomData)]
+    _key_lifetime: &'a PhantomData<K>,
+    #[arken(skip_with = &PhantomData)]
+    _value_lifetime: &'a PhantomData<V>,
+}
+
+pub type BlockTableRef<'a, K, V> = Ref<'a, BlockTable<'a, K, V>>;
+
+/// Decodes the keys and values of blocks. Keys are rebuilt from their prefixes, and values are
+/// decompressed, so they cannot be borrowed from the file. The codec therefore decodes them as
+/// owned values, which requires `K` and `V` to decode for any lifetime.
+#[derive(Debug)]
+pub(crate) struct BlockCodec<K, V> {
+    pub(crate) format: BlockFormat,
+    key: fn(&[u8], Config) -> Result<K, Error>,
+    value: fn(&[u8], Config) -> Result<Option<V>, Error>,
+}
+
+/// The configuration with which the lengths in the entries are encoded.
+fn lengths() -> Config {
+    *Config::default().variable_width()
+}
+
+fn decode<T: for<'b> Field<'b>>(slice: &[u8], config: Config) -> Result<T, Error> {
+    T::from_slice(slice, config).map(|(value, _)| value)
+}
+
+impl<K, V> BlockCodec<K, V> {
+    pub(crate) fn new(format: BlockFormat) -> Self
+    where
+        K: for<'b> Field<'b>,
+        V: for<'b> Field<'b>,
+    {
+        Self {
+            format,
+            key: decode::<K>,
+            value: decode::<Option<V>>,
+        }
+    }
+
+    /// Looks up `key` in `block`. This returns `None` if the block does not contain the key, and
+    /// `Some(None)` if the key was removed.
+    pub(crate) fn get<'a>(
+        &self,
+        block: &Block<'a>,
+        key: &K,
+        config: Config,
+    ) -> Result<Option<Option<V>>, Error>
+    where
+        K: Ord,
+    {
+        let data = block.data()?;
+        let entries = Entries::new(&data)?;
+
+        // Find the last restart point with a key that is less than or equal to the key.
+        let (mut low, mut high) = (0, entries.restarts());
+
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let mut iter = entries.at_restart(middle)?;
+
+            let Some(_) = iter.next_entry()? else {
+                return Err(Error::corrupt::<Block>(&data));
+            };
+
+            if (self.key)(&iter.key, config)? <= *key {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+
+        let Some(restart) = low.checked_sub(1) else {
+            return Ok(None);
+        };
+
+        let mut iter = entries.at_restart(restart)?;
+
+        while let Some(value) = iter.next_entry()? {
+            match (self.key)(&iter.key, config)?.cmp(key) {
+                Ordering::Less => continue,
+                Ordering::Equal => return Ok(Some((self.value)(value, config)?)),
+                Ordering::Greater => break,
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Decodes every key-value pair in `block`.
+    pub(crate) fn entries(
+        &self,
+        block: &Block<'_>,
+        config: Config,
+    ) -> Result<Vec<(K, Option<V>)>, Error> {
+        let data = block.data()?;
+        let entries = Entries::new(&data)?;
+        let mut iter = entries.at_offset(0);
+        let mut pairs = vec![];
+
+        while let Some(value) = iter.next_entry()? {
+            pairs.push(((self.key)(&iter.key, config)?, (self.value)(value, config)?));
+        }
+
+        Ok(pairs)
+    }
+}
+
+/// The entries and restart points of the data of a block.
+struct Entries<'d> {
+    entries: &'d [u8],
+    restarts: &'d [u8],
+}
+
+impl<'d> Entries<'d> {
+    fn new(data: &'d [u8]) -> Result<Self, Error> {
+        let Some((rest, count)) = data.split_last_chunk::<4>() else {
+            return Err(Error::incomplete::<Block>(data));
+        };
+
+        let count = u32::from_le_bytes(*count) as usize;
+
+        let Some(split) = count
+            .checked_mul(4)
+            .and_then(|len| rest.len().checked_sub(len))
+        else {
+            return Err(Error::incomplete::<Block>(data));
+        };
+
+        let (entries, restarts) = rest.split_at(split);
+
+        Ok(Self { entries, restarts })
+    }
+
+    fn restarts(&self) -> usize {
+        self.restarts.len() / 4
+    }
+
+    fn at_restart(&self, index: usize) -> Result<Iter<'d>, Error> {
+        let offset = &self.restarts[index * 4..][..4];
+        let offset = u32::from_le_bytes(offset.try_into().unwrap()) as usize;
+
+        if offset > self.entries.len() {
+            return Err(Error::corrupt::<Block>(self.entries));
+        }
+
+        Ok(self.at_offset(offset))
+    }
+
+    fn at_offset(&self, offset: usize) -> Iter<'d> {
+        Iter {
+            slice: &self.entries[offset..],
+            key: vec![],
+        }
+    }
+}
+
+/// Iterates over the entries of a block, rebuilding the keys from their prefixes.
+struct Iter<'d> {
+    slice: &'d [u8],
+    /// The encoding of the key of the current entry.
+    key: Vec<u8>,
+}
+
+impl<'d> Iter<'d> {
+    /// Advances to the next entry, and returns the encoding of its value.
+    fn next_entry(&mut self) -> Result<Option<&'d [u8]>, Error> {
+        if self.slice.is_empty() {
+            return Ok(None);
+        }
+
+        let input = self.slice;
+        let (shared, rest) = usize::from_slice(self.slice, lengths())?;
+        let (unshared, rest) = usize::from_slice(rest, lengths())?;
+        let (len, rest) = usize::from_slice(rest, lengths())?;
+
+        if shared > self.key.len() {
+            return Err(Error::corrupt::<Block>(input));
+        }
+
+        let Some(suffix) = rest.get(..unshared) else {
+            return Err(Error::incomplete::<Block>(rest));
+        };
+
+        let rest = &rest[unshared..];
+
+        let Some(value) = rest.get(..len) else {
+            return Err(Error::incomplete::<Block>(rest));
+        };
+
+        self.key.truncate(shared);
+        self.key.extend_from_slice(suffix);
+        self.slice = &rest[len..];
+
+        Ok(Some(value))
+    }
+}
+
+/// Writes the key-value pairs of a table, in order, into blocks.
+pub(crate) struct BlockWriter<'a> {
+    format: BlockFormat,
+    data: BytesMut,
+    restarts: Vec<u32>,
+    pairs: usize,
+    first_key: Vec<u8>,
+    previous: Vec<u8>,
+    handles: Vec<BlockHandle<'a>>,
+    count: usize,
+}
+
+impl<'a> BlockWriter<'a> {
+    pub(crate) fn new(format: BlockFormat) -> Self {
+        Self {
+            format,
+            data: BytesMut::new(),
+            restarts: vec![],
+            pairs: 0,
+            first_key: vec![],
+            previous: vec![],
+            handles: vec![],
+            count: 0,
+        }
+    }
+
+    /// Adds the encodings of a key and a value to the current block, and writes the block once it
+    /// is full.
+    pub(crate) fn push<W: Seek + Write>(
+        &mut self,
+        key: &[u8],
+        value: &[u8],
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+    ) -> Result<(), Error> {
+        let shared = if self.pairs.is_multiple_of(self.format.restart_interval) {
+            let offset =
+                u32::try_from(self.data.len()).map_err(|_| Error::overflow::<Block>(&self.data))?;
+            self.restarts.push(offset);
+
+            0
+        } else {
+            self.previous
+                .iter()
+                .zip(key)
+                .take_while(|(a, b)| a == b)
+                .count()
+        };
+
+        if self.pairs == 0 {
+            self.first_key = key.to_vec();
+        }
+
+        shared.put_bytes(&mut self.data, lengths())?;
+        (key.len() - shared).put_bytes(&mut self.data, lengths())?;
+        value.len().put_bytes(&mut self.data, lengths())?;
+        self.data.put_slice(&key[shared..]);
+        self.data.put_slice(value);
+
+        self.previous.clear();
+        self.previous.extend_from_slice(key);
+        self.pairs += 1;
+        self.count += 1;
+
+        if self.data.len() >= self.format.block_size {
+            self.flush(bytes, writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the current block, if it holds any key-value pairs.
+    fn flush<W: Seek + Write>(
+        &mut self,
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+    ) -> Result<(), Error> {
+        if self.pairs == 0 {
+            return Ok(());
+        }
+
+        let restarts = std::mem::take(&mut self.restarts);
+
+        for offset in &restarts {
+            self.data.put_u32_le(*offset);
+        }
+
+        self.data.put_u32_le(restarts.len() as u32);
+
+        let data = std::mem::take(&mut self.data);
+        let size = data.len();
+
+        #[cfg(any(feature = "lz4", feature = "zstd"))]
+        let (compression, data) = match self.format.compression.compress(&data)? {
+            Some(compressed) if compressed.len() < size => {
+                (self.format.compression.tag(), compressed)
+            }
+            _ => (Compression::None.tag(), data.to_vec()),
+        };
+
+        #[cfg(not(any(feature = "lz4", feature = "zstd")))]
+        let (compression, data) = (0, data.to_vec());
+
+        let block = Block {
+            compression,
+            size,
+            data: ByteStr(Cow::Owned(data)),
+        };
+
+        let block = writer.append(bytes, &block)?;
+
+        self.handles.push(BlockHandle {
+            first_key: ByteStr(Cow::Owned(std::mem::take(&mut self.first_key))),
+            block,
+        });
+        self.pairs = 0;
+
+        Ok(())
+    }
+
+    /// Writes the last block and returns the table.
+    pub(crate) fn finish<W: Seek + Write, K: Field<'a>, V: Field<'a>>(
+        mut self,
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+    ) -> Result<BlockTable<'a, K, V>, Error> {
+        self.flush(bytes, writer)?;
+
+        Ok(BlockTable {
+            blocks: Cow::Owned(self.handles),
+            count: self.count,
+            _key_lifetime: &PhantomData,
+            _value_lifetime: &PhantomData,
+        })
+    }
+}
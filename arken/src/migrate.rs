@@ -1,7 +1,11 @@
-use crate::{Error, MappedFile, Reader, Writer};
+use crate::{
+    Error, Field, HashMap, HashRootRef, MappedFile, MergeMap, MergeRootRef, Reader, Writer,
+};
 use bytes::BytesMut;
 use std::{
+    hash::Hash,
     io::{Seek, Write},
+    marker::PhantomData,
     path::Path,
 };
 
@@ -13,6 +17,139 @@ pub trait MigrationStrategy {
     ) -> Result<(), Error>;
 }
 
+/// Describes a map whose values are converted from [`MapConversion::Old`] to
+/// [`MapConversion::New`] through [`From`], for use with [`ConvertMergeMap`] and
+/// [`ConvertHashMap`].
+///
+/// ```no_run
+/// use arken::{ConvertMergeMap, MapConversion, migrate_to};
+/// use bytes::BytesMut;
+/// use std::borrow::Cow;
+///
+/// # #[derive(Clone, arken::Arken)]
+/// # struct UserV1<'a> { name: Cow<'a, str> }
+/// # #[derive(Clone, arken::Arken)]
+/// # struct UserV2<'a> { name: Cow<'a, str>, age: u8 }
+/// impl<'a> From<UserV1<'a>> for UserV2<'a> {
+///     fn from(user: UserV1<'a>) -> Self {
+///         Self { name: user.name, age: 0 }
+///     }
+/// }
+///
+/// struct Users;
+///
+/// impl MapConversion for Users {
+///     const MARKER: &'static [u8] = b"users";
+///
+///     type Key<'a> = u64;
+///     type Old<'a> = UserV1<'a>;
+///     type New<'a> = UserV2<'a>;
+/// }
+///
+/// let mut bytes = BytesMut::new();
+/// migrate_to::<_, _, ConvertMergeMap<Users>>(&mut bytes, "users-v2.bin", "users-v1.bin")?;
+/// # Ok::<(), arken::Error>(())
+/// ```
+pub trait MapConversion {
+    /// The marker of the records holding the root reference of the map. The converted map is
+    /// written with the same marker.
+    const MARKER: &'static [u8];
+
+    type Key<'a>: 'a + Clone + Field<'a>;
+    type Old<'a>: 'a + Clone + Field<'a>;
+    type New<'a>: 'a + Clone + Field<'a> + From<Self::Old<'a>>;
+}
+
+/// A [`MigrationStrategy`] that reads the latest [`MergeMap`] described by `C`, converts its
+/// values and writes the converted map along with its root reference into the new file. Maps
+/// with block tables, see [`MergeMap::with_blocks`], fail to convert with
+/// [`Error::BlocksRequired`].
+pub struct ConvertMergeMap<C: MapConversion>(PhantomData<C>);
+
+impl<C: MapConversion> MigrationStrategy for ConvertMergeMap<C>
+where
+    for<'a> C::Key<'a>: Ord,
+{
+    fn migrate<'a, W: Seek + Write>(
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+        reader: &Reader<'a>,
+    ) -> Result<(), Error> {
+        let Some(root_reference) = reader
+            .find::<MergeRootRef<'a, C::Key<'a>, C::Old<'a>>>(C::MARKER)
+            .next()
+        else {
+            return Ok(());
+        };
+
+        let old = MergeMap::open(reader.clone(), Some(root_reference));
+
+        // The new map starts out empty, so it never reads from the reader it is opened with.
+        let mut new = MergeMap::<C::Key<'a>, C::New<'a>>::open(reader.clone(), None);
+
+        for entry in old.iter() {
+            let (key, value) = entry?;
+            let mut key = key.into_owned();
+            let mut value = C::New::from(value.into_owned());
+
+            key.migrate(bytes, writer, reader)?;
+            value.migrate(bytes, writer, reader)?;
+
+            new.insert(key, value);
+        }
+
+        if let Some(root_reference) = new.commit(bytes, writer)? {
+            writer.append_with_marker(bytes, C::MARKER, &root_reference)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A [`MigrationStrategy`] that reads the latest [`HashMap`] described by `C`, converts its
+/// values and writes the converted map along with its root reference into the new file.
+pub struct ConvertHashMap<C: MapConversion>(PhantomData<C>);
+
+impl<C: MapConversion> MigrationStrategy for ConvertHashMap<C>
+where
+    for<'a> C::Key<'a>: Hash + PartialEq,
+{
+    fn migrate<'a, W: Seek + Write>(
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+        reader: &Reader<'a>,
+    ) -> Result<(), Error> {
+        let Some(root_reference) = reader
+            .find::<HashRootRef<'a, C::Key<'a>, C::Old<'a>>>(C::MARKER)
+            .next()
+        else {
+            return Ok(());
+        };
+
+        let old = HashMap::open(reader.clone(), Some(root_reference));
+
+        // The new map starts out empty, so it never reads from the reader it is opened with.
+        let mut new = HashMap::<C::Key<'a>, C::New<'a>>::open(reader.clone(), None);
+
+        for entry in old.iter() {
+            let (key, value) = entry?;
+            let mut key = key.into_owned();
+            let mut value = C::New::from(value.into_owned());
+
+            key.migrate(bytes, writer, reader)?;
+            value.migrate(bytes, writer, reader)?;
+
+            new.insert(key, value);
+        }
+
+        if let Some(root_reference) = new.commit(bytes, writer)? {
+            writer.append_with_marker(bytes, C::MARKER, &root_reference)?;
+        }
+
+        Ok(())
+    }
+}
+
 pub fn migrate<P: AsRef<Path>, S: MigrationStrategy>(
     bytes: &mut BytesMut,
     path: P,
@@ -20,15 +157,18 @@ pub fn migrate<P: AsRef<Path>, S: MigrationStrategy>(
     migrate_to::<_, _, S>(bytes, &path, &path)
 }
 
+/// Migrates the file at `path` into a new file at `dst_path` with the [`MigrationStrategy`] `S`.
+/// The new file keeps the [`crate::Config`] of the file at `path`, including its format version.
 pub fn migrate_to<D: AsRef<Path>, P: AsRef<Path>, S: MigrationStrategy>(
     bytes: &mut BytesMut,
     dst_path: D,
     path: P,
 ) -> Result<(), Error> {
     let file = MappedFile::open(&path)?;
-    let reader = file.reader();
+    let reader = file.try_reader()?;
 
-    let mut writer = Writer::tempfile(Default::default())?;
+    // The migrated file is written the same way as the file it was migrated from.
+    let mut writer = Writer::tempfile(reader.config())?;
 
     S::migrate(bytes, &mut writer, &reader)?;
 
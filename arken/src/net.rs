@@ -0,0 +1,242 @@
+use crate::{
+    Config, Error, Field,
+    schema::{FieldDef, Schema, Schemas, VariantDef},
+};
+use bytes::{BufMut as _, BytesMut};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+/// Reads the octets of an address, which are in network byte order regardless of the endianness
+/// of the file.
+fn octets<T: ?Sized, const N: usize>(slice: &[u8]) -> Result<([u8; N], &[u8]), Error> {
+    let Some(bytes) = slice.get(..N) else {
+        return Err(Error::incomplete::<T>(slice));
+    };
+
+    let mut octets = [0u8; N];
+    octets.copy_from_slice(bytes);
+
+    Ok((octets, &slice[N..]))
+}
+
+/// Encoded as its 4 octets.
+impl<'a> Field<'a> for Ipv4Addr {
+    fn from_slice(slice: &'a [u8], _: Config) -> Result<(Self, &'a [u8]), Error> {
+        let (octets, slice) = octets::<Self, 4>(slice)?;
+
+        Ok((Ipv4Addr::from(octets), slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, _: Config) -> Result<(), Error> {
+        bytes.put_slice(&self.octets());
+
+        Ok(())
+    }
+
+    fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Array(Box::new(Schema::U8), 4)
+    }
+}
+
+/// Encoded as its 16 octets.
+impl<'a> Field<'a> for Ipv6Addr {
+    fn from_slice(slice: &'a [u8], _: Config) -> Result<(Self, &'a [u8]), Error> {
+        let (octets, slice) = octets::<Self, 16>(slice)?;
+
+        Ok((Ipv6Addr::from(octets), slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, _: Config) -> Result<(), Error> {
+        bytes.put_slice(&self.octets());
+
+        Ok(())
+    }
+
+    fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Array(Box::new(Schema::U8), 16)
+    }
+}
+
+/// Encoded as a `usize` tag, 0 for `V4` and 1 for `V6`, followed by the address, like a derived
+/// enum.
+impl<'a> Field<'a> for IpAddr {
+    fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let input = slice;
+        let (tag, rest) = usize::from_slice(slice, config)?;
+        slice = rest;
+
+        let value = match tag {
+            0 => {
+                let (value, rest) = Ipv4Addr::from_slice(slice, config)?;
+                slice = rest;
+
+                IpAddr::V4(value)
+            }
+            1 => {
+                let (value, rest) = Ipv6Addr::from_slice(slice, config)?;
+                slice = rest;
+
+                IpAddr::V6(value)
+            }
+            tag => return Err(Error::invalid_tag::<Self>(input, tag)),
+        };
+
+        Ok((value, slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        match self {
+            IpAddr::V4(value) => {
+                0usize.put_bytes(bytes, config)?;
+                value.put_bytes(bytes, config)?;
+            }
+            IpAddr::V6(value) => {
+                1usize.put_bytes(bytes, config)?;
+                value.put_bytes(bytes, config)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        schemas.describe_enum::<Self>(|schemas| {
+            vec![
+                VariantDef {
+                    name: "V4".into(),
+                    fields: vec![FieldDef::new("0", Ipv4Addr::schema(schemas))].into(),
+                },
+                VariantDef {
+                    name: "V6".into(),
+                    fields: vec![FieldDef::new("0", Ipv6Addr::schema(schemas))].into(),
+                },
+            ]
+        })
+    }
+}
+
+/// Encoded as the address followed by the port as a `u16`.
+impl<'a> Field<'a> for SocketAddrV4 {
+    fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let (ip, rest) = Ipv4Addr::from_slice(slice, config)?;
+        slice = rest;
+        let (port, rest) =
+            u16::from_slice(slice, config).map_err(|error| error.with_field("port"))?;
+        slice = rest;
+
+        Ok((SocketAddrV4::new(ip, port), slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        self.ip().put_bytes(bytes, config)?;
+        self.port().put_bytes(bytes, config)?;
+
+        Ok(())
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        schemas.describe_struct::<Self>(|schemas| {
+            vec![
+                FieldDef::new("ip", Ipv4Addr::schema(schemas)),
+                FieldDef::new("port", Schema::U16),
+            ]
+        })
+    }
+}
+
+/// Encoded as the address, the port as a `u16`, the flow information as a `u32` and the scope id
+/// as a `u32`.
+impl<'a> Field<'a> for SocketAddrV6 {
+    fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let (ip, rest) = Ipv6Addr::from_slice(slice, config)?;
+        slice = rest;
+        let (port, rest) =
+            u16::from_slice(slice, config).map_err(|error| error.with_field("port"))?;
+        slice = rest;
+        let (flowinfo, rest) =
+            u32::from_slice(slice, config).map_err(|error| error.with_field("flowinfo"))?;
+        slice = rest;
+        let (scope_id, rest) =
+            u32::from_slice(slice, config).map_err(|error| error.with_field("scope_id"))?;
+        slice = rest;
+
+        Ok((SocketAddrV6::new(ip, port, flowinfo, scope_id), slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        self.ip().put_bytes(bytes, config)?;
+        self.port().put_bytes(bytes, config)?;
+        self.flowinfo().put_bytes(bytes, config)?;
+        self.scope_id().put_bytes(bytes, config)?;
+
+        Ok(())
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        schemas.describe_struct::<Self>(|schemas| {
+            vec![
+                FieldDef::new("ip", Ipv6Addr::schema(schemas)),
+                FieldDef::new("port", Schema::U16),
+                FieldDef::new("flowinfo", Schema::U32),
+                FieldDef::new("scope_id", Schema::U32),
+            ]
+        })
+    }
+}
+
+/// Encoded as a `usize` tag, 0 for `V4` and 1 for `V6`, followed by the socket address, like a
+/// derived enum.
+impl<'a> Field<'a> for SocketAddr {
+    fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let input = slice;
+        let (tag, rest) = usize::from_slice(slice, config)?;
+        slice = rest;
+
+        let value = match tag {
+            0 => {
+                let (value, rest) = SocketAddrV4::from_slice(slice, config)?;
+                slice = rest;
+
+                SocketAddr::V4(value)
+            }
+            1 => {
+                let (value, rest) = SocketAddrV6::from_slice(slice, config)?;
+                slice = rest;
+
+                SocketAddr::V6(value)
+            }
+            tag => return Err(Error::invalid_tag::<Self>(input, tag)),
+        };
+
+        Ok((value, slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        match self {
+            SocketAddr::V4(value) => {
+                0usize.put_bytes(bytes, config)?;
+                value.put_bytes(bytes, config)?;
+            }
+            SocketAddr::V6(value) => {
+                1usize.put_bytes(bytes, config)?;
+                value.put_bytes(bytes, config)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        schemas.describe_enum::<Self>(|schemas| {
+            vec![
+                VariantDef {
+                    name: "V4".into(),
+                    fields: vec![FieldDef::new("0", SocketAddrV4::schema(schemas))].into(),
+                },
+                VariantDef {
+                    name: "V6".into(),
+                    fields: vec![FieldDef::new("0", SocketAddrV6::schema(schemas))].into(),
+                },
+            ]
+        })
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    ByteStr, Config, Error, Field, Reader, Writer, decode_seq,
+    schema::{Schema, Schemas},
+    verify::Verifier,
+};
+use bytes::BytesMut;
+use std::{
+    borrow::Cow,
+    io::{Seek, Write},
+    path::PathBuf,
+};
+
+/// Encoded like `Cow<str>`.
+impl<'a> Field<'a> for String {
+    fn from_slice(slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let (value, slice) = Cow::<str>::from_slice(slice, config)?;
+
+        Ok((value.into_owned(), slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        Cow::Borrowed(self.as_str()).put_bytes(bytes, config)
+    }
+
+    fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Str
+    }
+}
+
+/// Encoded like `Cow<[T]>`.
+impl<'a, T: Field<'a>> Field<'a> for Vec<T> {
+    fn from_slice(slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let (n, rest) = usize::from_slice(slice, config)?;
+
+        decode_seq::<_, Self>(rest, n, |slice| T::from_slice(slice, config))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        self.len().put_bytes(bytes, config)?;
+
+        for value in self {
+            value.put_bytes(bytes, config)?;
+        }
+
+        Ok(())
+    }
+
+    fn migrate<W: Seek + Write>(
+        &mut self,
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+        reader: &Reader<'a>,
+    ) -> Result<(), Error> {
+        for value in self {
+            value.migrate(bytes, writer, reader)?;
+        }
+
+        Ok(())
+    }
+
+    fn verify(&self, verifier: &mut Verifier<'a>) {
+        for value in self {
+            value.verify(verifier);
+        }
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Seq(Box::new(T::schema(schemas)))
+    }
+}
+
+/// Encoded like `T`.
+impl<'a, T: Field<'a>> Field<'a> for Box<T> {
+    const ALIGN: usize = T::ALIGN;
+
+    fn from_slice(slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let (value, slice) = T::from_slice(slice, config)?;
+
+        Ok((Box::new(value), slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        self.as_ref().put_bytes(bytes, config)
+    }
+
+    fn migrate<W: Seek + Write>(
+        &mut self,
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+        reader: &Reader<'a>,
+    ) -> Result<(), Error> {
+        self.as_mut().migrate(bytes, writer, reader)
+    }
+
+    fn verify(&self, verifier: &mut Verifier<'a>) {
+        self.as_ref().verify(verifier);
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        T::schema(schemas)
+    }
+}
+
+/// Encoded like [`ByteStr`]. On Unix, paths are stored as their raw bytes. Elsewhere, paths are
+/// stored in the platform's encoding of the `OsStr`, and only paths that are valid UTF-8 can be
+/// decoded, such that files remain portable for all paths that can be represented everywhere.
+impl<'a> Field<'a> for PathBuf {
+    fn from_slice(slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let (value, rest) = ByteStr::from_slice(slice, config)?;
+
+        Ok((path_from_bytes(slice, &value)?, rest))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        ByteStr::from(self.as_os_str().as_encoded_bytes()).put_bytes(bytes, config)
+    }
+
+    fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Bytes
+    }
+}
+
+#[cfg(unix)]
+fn path_from_bytes(_: &[u8], bytes: &[u8]) -> Result<PathBuf, Error> {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    Ok(PathBuf::from(std::ffi::OsStr::from_bytes(bytes)))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(input: &[u8], bytes: &[u8]) -> Result<PathBuf, Error> {
+    let path =
+        std::str::from_utf8(bytes).map_err(|error| Error::invalid_utf8::<PathBuf>(input, error))?;
+
+    Ok(PathBuf::from(path))
+}
@@ -0,0 +1,151 @@
+use crate::{
+    Config, Endian, Error, Field, decode_seq,
+    schema::{Schema, Schemas},
+};
+use bytes::BytesMut;
+use std::{borrow::Cow, ops::Deref};
+
+/// Primitive types of which every bit pattern is valid, and whose encoding is the same as their
+/// in-memory representation when written with the host's endianness.
+///
+/// # Safety
+///
+/// Implementors must be plain old data, i.e. have no padding and no invalid bit patterns, and
+/// [`Pod::is_contiguous`] must only return `true` for a [`Config`] under which the encoding of the
+/// type is exactly `size_of::<Self>()` bytes in the endianness of the [`Config`].
+pub unsafe trait Pod: Copy + for<'a> Field<'a> + 'static {
+    /// Returns `true` if a value of this type is encoded with a fixed size under `config`.
+    fn is_contiguous(config: Config) -> bool {
+        config.fixed
+    }
+}
+
+unsafe impl Pod for u8 {
+    fn is_contiguous(_: Config) -> bool {
+        true
+    }
+}
+
+unsafe impl Pod for i8 {
+    fn is_contiguous(_: Config) -> bool {
+        true
+    }
+}
+
+unsafe impl Pod for u16 {}
+unsafe impl Pod for u32 {}
+unsafe impl Pod for u64 {}
+unsafe impl Pod for u128 {}
+unsafe impl Pod for usize {}
+unsafe impl Pod for i16 {}
+unsafe impl Pod for i32 {}
+unsafe impl Pod for i64 {}
+unsafe impl Pod for i128 {}
+unsafe impl Pod for isize {}
+
+unsafe impl Pod for f32 {
+    fn is_contiguous(_: Config) -> bool {
+        true
+    }
+}
+
+unsafe impl Pod for f64 {
+    fn is_contiguous(_: Config) -> bool {
+        true
+    }
+}
+
+/// Returns `true` if data encoded with `config` is in the endianness of the host.
+pub(crate) fn is_native_endian(config: Config) -> bool {
+    match config.endian {
+        Endian::Native => true,
+        Endian::Big => cfg!(target_endian = "big"),
+        Endian::Little => cfg!(target_endian = "little"),
+    }
+}
+
+/// A slice of primitives that borrows `&'a [T]` straight from the mapped file where possible.
+///
+/// `PodSlice` uses the same encoding as `Cow<'a, [T]>`. When the elements are encoded with a fixed
+/// size (see [`Config::fixed_width`]) in the endianness of the host, and the data happens to be
+/// suitably aligned, decoding returns [`Cow::Borrowed`] without copying. Otherwise, the elements
+/// are decoded one by one into [`Cow::Owned`].
+#[derive(Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct PodSlice<'a, T: Pod>(pub Cow<'a, [T]>);
+
+impl<T: Pod> PodSlice<'_, T> {
+    /// Returns `true` if the elements are borrowed from the mapped file.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.0, Cow::Borrowed(_))
+    }
+
+    /// Returns the elements, copying them if they are borrowed.
+    pub fn into_owned(self) -> Vec<T> {
+        self.0.into_owned()
+    }
+}
+
+impl<T: Pod> Deref for PodSlice<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<'a, T: Pod> From<&'a [T]> for PodSlice<'a, T> {
+    fn from(values: &'a [T]) -> Self {
+        Self(Cow::Borrowed(values))
+    }
+}
+
+impl<T: Pod> From<Vec<T>> for PodSlice<'_, T> {
+    fn from(values: Vec<T>) -> Self {
+        Self(Cow::Owned(values))
+    }
+}
+
+impl<'a, T: Pod> Field<'a> for PodSlice<'a, T> {
+    fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let (n, rest) = usize::from_slice(slice, config)?;
+        slice = rest;
+
+        if T::is_contiguous(config) && is_native_endian(config) {
+            let size = n
+                .checked_mul(std::mem::size_of::<T>())
+                .ok_or_else(|| Error::overflow::<Self>(slice))?;
+
+            if slice.len() < size {
+                return Err(Error::incomplete::<Self>(slice));
+            }
+
+            let (values, rest) = slice.split_at(size);
+
+            if values.as_ptr().align_offset(std::mem::align_of::<T>()) == 0 {
+                // SAFETY: the bytes are in bounds, suitably aligned, live for `'a`, and hold `n`
+                // values of `T` in the host's representation, of which every bit pattern is valid.
+                let values = unsafe { std::slice::from_raw_parts(values.as_ptr().cast::<T>(), n) };
+
+                return Ok((Self(Cow::Borrowed(values)), rest));
+            }
+        }
+
+        let (values, rest) = decode_seq::<_, Self>(slice, n, |slice| T::from_slice(slice, config))?;
+
+        Ok((Self(Cow::Owned(values)), rest))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        self.0.len().put_bytes(bytes, config)?;
+
+        for value in self.0.iter() {
+            value.put_bytes(bytes, config)?;
+        }
+
+        Ok(())
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Seq(Box::new(T::schema(schemas)))
+    }
+}
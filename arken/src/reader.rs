@@ -1,4 +1,10 @@
-use crate::{Config, Error, Field, Ref};
+use crate::{
+    Config, Diagnostic, Error, Field, MarkerIndex, Pod, Ref, Value,
+    pod::is_native_endian,
+    schema::{SCHEMA_MARKER, Schema, SchemaRecord, Schemas},
+};
+#[cfg(feature = "encryption")]
+use crate::{Key, encryption::Encryption};
 use memchr::memmem::FinderRev;
 use mmap_rs::{Mmap, MmapOptions};
 use std::{fs::File, marker::PhantomData, path::Path};
@@ -12,19 +18,42 @@ pub struct MarkerIter<'a, T: Field<'a>> {
     _marker: PhantomData<T>,
 }
 
-impl<'a, T: Field<'a>> Iterator for MarkerIter<'a, T> {
-    type Item = T;
+impl<'a, T: Field<'a>> MarkerIter<'a, T> {
+    /// Returns the next record along with the offset at which it starts in the file.
+    ///
+    /// The marker may also occur in the data of other records, e.g. as the name of a field in a
+    /// schema. Occurrences that are not followed by an intact trailer are skipped, and the search
+    /// continues before them.
+    pub fn next_with_offset(&mut self) -> Option<(usize, T)> {
+        if self.marker.is_empty() {
+            return None;
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let limit = self.limit.min(self.bytes.len());
+        let finder = FinderRev::new(self.marker);
 
-        let offset = FinderRev::new(self.marker).rfind(&self.bytes[..limit])?;
+        loop {
+            let limit = self.limit.min(self.bytes.len());
+            let offset = finder.rfind(&self.bytes[..limit])?;
+
+            // Occurrences that overlap this one are still found.
+            self.limit = offset + self.marker.len() - 1;
+
+            if let Some((start, value)) = self.record(offset) {
+                self.limit = start;
+
+                return Some((start, value));
+            }
+        }
+    }
 
+    /// Decodes the record whose marker is at `offset`, if its trailer is intact.
+    fn record(&self, offset: usize) -> Option<(usize, T)> {
         let slice = &self.bytes[offset + self.marker.len()..];
         let (size, rest) = usize::from_slice(slice, self.config).ok()?;
         let (checksum, _) = u32::from_slice(rest, self.config).ok()?;
 
-        let slice = &self.bytes[offset - size..][..size];
+        let start = offset.checked_sub(size)?;
+        let slice = &self.bytes[start..offset];
 
         if crc32fast::hash(slice) != checksum {
             return None;
@@ -32,16 +61,23 @@ impl<'a, T: Field<'a>> Iterator for MarkerIter<'a, T> {
 
         let (value, _) = T::from_slice(slice, self.config).ok()?;
 
-        self.limit = offset;
+        Some((start, value))
+    }
+}
 
-        Some(value)
+impl<'a, T: Field<'a>> Iterator for MarkerIter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_offset().map(|(_, value)| value)
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct Reader<'a> {
     bytes: &'a [u8],
     config: Config,
+    indices: Vec<MarkerIndex<'a>>,
 }
 
 impl<'a> TryFrom<&'a [u8]> for Reader<'a> {
@@ -50,17 +86,141 @@ impl<'a> TryFrom<&'a [u8]> for Reader<'a> {
     fn try_from(bytes: &'a [u8]) -> Result<Self, Error> {
         let (config, _) = Config::from_slice(bytes, Default::default())?;
 
-        Ok(Self { bytes, config })
+        Ok(Self {
+            bytes,
+            config,
+            indices: vec![],
+        })
     }
 }
 
+/// Whether the trailer of a record with a marker is intact.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RecordStatus {
+    Ok,
+    /// The checksum in the trailer does not match the data of the record.
+    ChecksumMismatch {
+        expected: u32,
+        actual: u32,
+    },
+    /// The size or the checksum in the trailer could not be decoded, or the size extends beyond
+    /// the start of the file.
+    Truncated,
+}
+
+/// A record with a marker, as found by [`Reader::records`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Record {
+    /// The offset at which the data of the record starts.
+    pub offset: usize,
+    /// The size of the data of the record.
+    pub size: usize,
+    /// The offset at which the marker starts.
+    pub marker_offset: usize,
+    pub status: RecordStatus,
+}
+
 impl<'a> Reader<'a> {
+    pub fn config(&self) -> Config {
+        self.config
+    }
+
+    /// Returns the bytes of the file, including the header.
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Decodes the value that `reference` points to. If the file checksums every record, see
+    /// [`Config::with_checksum`], the record is verified first.
     pub fn read<T: Field<'a>>(&self, reference: &Ref<'a, T>) -> Result<T, Error> {
         if self.bytes.len() < reference.offset {
             return Err(Error::InvalidOffset);
         }
 
-        let (value, _) = T::from_slice(&self.bytes[reference.offset..], self.config)?;
+        self.config
+            .checksum()
+            .verify(self.bytes, reference.offset)?;
+
+        let (value, _) = T::from_slice(&self.bytes[reference.offset..], self.config)
+            .map_err(|error| error.rebase(self.bytes.len()))?;
+
+        Ok(value)
+    }
+
+    /// Returns the value at `reference` in place, without copying or decoding it. This requires
+    /// the value to be encoded as its in-memory representation, see [`Pod`], and to be suitably
+    /// aligned in memory, e.g. by writing it with [`crate::Writer::append_aligned`] into a file
+    /// that is mapped at a page boundary.
+    pub fn read_aligned<T: Pod>(&self, reference: &Ref<'a, T>) -> Result<&'a T, Error> {
+        let Some(slice) = self.bytes.get(reference.offset..) else {
+            return Err(Error::InvalidOffset);
+        };
+
+        let unaligned = Error::Unaligned {
+            offset: reference.offset,
+            type_name: std::any::type_name::<T>(),
+        };
+
+        if !T::is_contiguous(self.config) || !is_native_endian(self.config) {
+            return Err(unaligned);
+        }
+
+        let Some(bytes) = slice.get(..std::mem::size_of::<T>()) else {
+            return Err(Error::incomplete::<T>(slice).rebase(self.bytes.len()));
+        };
+
+        self.config
+            .checksum()
+            .verify(self.bytes, reference.offset)?;
+
+        if bytes.as_ptr().align_offset(std::mem::align_of::<T>()) != 0 {
+            return Err(unaligned);
+        }
+
+        // SAFETY: the bytes are in bounds, suitably aligned, live for `'a`, and hold a `T` in the
+        // host's representation, of which every bit pattern is valid.
+        Ok(unsafe { &*bytes.as_ptr().cast::<T>() })
+    }
+
+    /// Like [`Reader::read`], but on failure returns a [`Diagnostic`] that describes which field
+    /// failed to decode, and includes the bytes around the location where decoding failed.
+    pub fn read_verbose<T: Field<'a>>(&self, reference: &Ref<'a, T>) -> Result<T, Diagnostic> {
+        const CONTEXT: usize = 16;
+
+        self.read(reference).map_err(|error| {
+            let location = error
+                .context()
+                .map(|context| context.offset)
+                .unwrap_or(reference.offset)
+                .min(self.bytes.len());
+            let start = location.saturating_sub(CONTEXT);
+            let end = location.saturating_add(CONTEXT).min(self.bytes.len());
+
+            Diagnostic {
+                type_name: std::any::type_name::<T>(),
+                offset: reference.offset,
+                error,
+                bytes_offset: start,
+                bytes: self.bytes[start..end].to_vec(),
+            }
+        })
+    }
+
+    /// Decodes the value at `offset` as described by `schema`. See [`Value`].
+    pub fn read_value<'s: 'a>(
+        &self,
+        offset: usize,
+        schema: &Schema<'s>,
+        schemas: &Schemas<'s>,
+    ) -> Result<Value<'a>, Error> {
+        let Some(slice) = self.bytes.get(offset..) else {
+            return Err(Error::InvalidOffset);
+        };
+
+        self.config.checksum().verify(self.bytes, offset)?;
+
+        let (value, _) = Value::from_slice(slice, self.config, schema, schemas)
+            .map_err(|error| error.rebase(self.bytes.len()))?;
 
         Ok(value)
     }
@@ -74,17 +234,254 @@ impl<'a> Reader<'a> {
             _marker: PhantomData,
         }
     }
+
+    /// Returns every occurrence of the given marker along with the status of its record, from the
+    /// most recent to the oldest. Unlike [`Reader::find`], this does not stop at the first record
+    /// that is damaged. Note that the data of a record may happen to contain the marker, in which
+    /// case the occurrence is most likely reported as a damaged record.
+    pub fn records(&self, marker: &[u8]) -> Vec<Record> {
+        if marker.is_empty() {
+            return vec![];
+        }
+
+        let finder = FinderRev::new(marker);
+        let mut records = vec![];
+        let mut limit = self.bytes.len();
+
+        while let Some(marker_offset) = finder.rfind(&self.bytes[..limit]) {
+            limit = marker_offset;
+
+            let trailer = &self.bytes[marker_offset + marker.len()..];
+
+            let Some((size, checksum)) = usize::from_slice(trailer, self.config)
+                .and_then(|(size, rest)| Ok((size, u32::from_slice(rest, self.config)?.0)))
+                .ok()
+                .filter(|(size, _)| *size <= marker_offset)
+            else {
+                records.push(Record {
+                    offset: marker_offset,
+                    size: 0,
+                    marker_offset,
+                    status: RecordStatus::Truncated,
+                });
+
+                continue;
+            };
+
+            let offset = marker_offset - size;
+            let actual = crc32fast::hash(&self.bytes[offset..marker_offset]);
+
+            let status = if actual == checksum {
+                RecordStatus::Ok
+            } else {
+                RecordStatus::ChecksumMismatch {
+                    expected: checksum,
+                    actual,
+                }
+            };
+
+            records.push(Record {
+                offset,
+                size,
+                marker_offset,
+                status,
+            });
+        }
+
+        records
+    }
+
+    /// Returns the most recent schema written with [`crate::Writer::append_schema`] for the
+    /// records with the given marker.
+    pub fn schema(&self, marker: &[u8]) -> Option<SchemaRecord<'a>> {
+        self.find::<SchemaRecord>(SCHEMA_MARKER)
+            .find(|record| record.describes(marker))
+    }
+
+    /// Returns the most recent schema for every marker that has one.
+    pub fn schemas(&self) -> Vec<SchemaRecord<'a>> {
+        let mut records: Vec<SchemaRecord> = vec![];
+
+        for record in self.find::<SchemaRecord>(SCHEMA_MARKER) {
+            if records
+                .iter()
+                .all(|other| other.inverted_marker != record.inverted_marker)
+            {
+                records.push(record);
+            }
+        }
+
+        records
+    }
+
+    /// Attaches an index over the records of a marker, such that [`Reader::nth_record`] and
+    /// [`Reader::search_timestamp`] no longer have to scan the file for that marker. An index
+    /// that was not built for this file is ignored, see [`MarkerIndex::matches`].
+    pub fn with_index(mut self, index: MarkerIndex<'a>) -> Self {
+        if !index.matches(&self) {
+            return self;
+        }
+
+        self.indices
+            .retain(|other| other.marker() != index.marker());
+        self.indices.push(index);
+        self
+    }
+
+    fn index(&self, marker: &[u8]) -> Option<&MarkerIndex<'a>> {
+        self.indices.iter().find(|index| index.marker() == marker)
+    }
+
+    /// Returns `true` if an intact record with `marker` starts at `offset`, i.e. if the first
+    /// occurrence of the marker after `offset` is followed by the size and the checksum of the
+    /// bytes in between.
+    pub(crate) fn is_record(&self, marker: &[u8], offset: usize) -> bool {
+        let Some(slice) = self.bytes.get(offset..) else {
+            return false;
+        };
+
+        let Some(size) = memchr::memmem::find(slice, marker) else {
+            return false;
+        };
+
+        let trailer = &slice[size + marker.len()..];
+
+        let Ok((stored_size, rest)) = usize::from_slice(trailer, self.config) else {
+            return false;
+        };
+
+        let Ok((checksum, _)) = u32::from_slice(rest, self.config) else {
+            return false;
+        };
+
+        stored_size == size && crc32fast::hash(&slice[..size]) == checksum
+    }
+
+    /// Returns the offsets of the records with the given marker that were written after the last
+    /// record in `index`, from the oldest to the most recent. Returns `None` if the last entry of
+    /// the index does not point to a record with the marker.
+    fn unindexed(&self, marker: &'a [u8], index: &MarkerIndex<'a>) -> Option<Vec<usize>> {
+        let last = match index.len().checked_sub(1) {
+            Some(n) => {
+                let offset = index.get(n)?.offset as usize;
+
+                if !self.is_record(marker, offset) {
+                    return None;
+                }
+
+                Some(offset)
+            }
+            None => None,
+        };
+
+        let mut iter = self.find::<()>(marker);
+        let mut offsets = vec![];
+
+        while let Some((offset, _)) = iter.next_with_offset() {
+            if last.is_some_and(|last| offset <= last) {
+                break;
+            }
+
+            offsets.push(offset);
+        }
+
+        offsets.reverse();
+
+        Some(offsets)
+    }
+
+    /// Returns the `n`-th record written with the given marker, counting from the start of the
+    /// file. This is O(1) if an index has been attached for the marker, and otherwise requires
+    /// scanning the file for every record with the marker. The records written after the last
+    /// record in the index are found by scanning the file back to that record. The whole file is
+    /// scanned if the index does not point to a record with the marker.
+    pub fn nth_record<T: 'a + Field<'a>>(&self, marker: &'a [u8], n: usize) -> Option<T> {
+        if let Some(index) = self.index(marker) {
+            match index.get(n) {
+                Some(entry) if self.is_record(marker, entry.offset as usize) => {
+                    return self.read_at(entry.offset as usize);
+                }
+                Some(_) => {}
+                None => {
+                    if let Some(offsets) = self.unindexed(marker, index) {
+                        return self.read_at(*offsets.get(n - index.len())?);
+                    }
+                }
+            }
+        }
+
+        let mut iter = self.find::<()>(marker);
+        let mut offsets = vec![];
+
+        while let Some((offset, _)) = iter.next_with_offset() {
+            offsets.push(offset);
+        }
+
+        self.read_at(*offsets.iter().rev().nth(n)?)
+    }
+
+    /// Returns the sequence number of the first record with the given marker whose timestamp is
+    /// at or after `timestamp`, or the number of records if there is no such record, using a
+    /// binary search over the attached index. The records written after the last record in the
+    /// index are decoded as a `T`, and their timestamps extracted with `key`, as when building
+    /// the index with [`crate::IndexWriter::rebuild`]. Returns `None` if no index has been
+    /// attached for the marker.
+    pub fn search_timestamp<T: 'a + Field<'a>, F: FnMut(&T) -> i64>(
+        &self,
+        marker: &'a [u8],
+        timestamp: i64,
+        mut key: F,
+    ) -> Option<usize> {
+        let index = self.index(marker)?;
+        let n = index.search(timestamp);
+
+        if n < index.len() {
+            return Some(n);
+        }
+
+        let offsets = self.unindexed(marker, index)?;
+
+        for (i, &offset) in offsets.iter().enumerate() {
+            if key(&self.read_at(offset)?) >= timestamp {
+                return Some(n + i);
+            }
+        }
+
+        Some(n + offsets.len())
+    }
+
+    fn read_at<T: 'a + Field<'a>>(&self, offset: usize) -> Option<T> {
+        let reference = Ref {
+            offset,
+            _marker: &PhantomData,
+        };
+
+        self.read(&reference).ok()
+    }
 }
 
 fn round_up(x: usize, align: usize) -> usize {
     (x + align.saturating_sub(1)) & !(align.saturating_sub(1))
 }
 
+/// The plaintext of an encrypted file, see [`MappedFile::open_encrypted`].
+#[cfg(feature = "encryption")]
+#[derive(Debug)]
+struct Decrypted {
+    encryption: Encryption,
+    /// The decrypted records, at the same offsets as in the file.
+    plaintext: Vec<u8>,
+    /// The offset of the first frame that has not been decrypted.
+    end: usize,
+}
+
 #[derive(Debug)]
 pub struct MappedFile {
     file: Option<File>,
     map: Option<Mmap>,
     size: usize,
+    #[cfg(feature = "encryption")]
+    decrypted: Option<Decrypted>,
 }
 
 impl MappedFile {
@@ -94,6 +491,8 @@ impl MappedFile {
                 file: None,
                 map: None,
                 size: 0,
+                #[cfg(feature = "encryption")]
+                decrypted: None,
             });
         };
 
@@ -109,6 +508,8 @@ impl MappedFile {
                 file: Some(file),
                 map: None,
                 size,
+                #[cfg(feature = "encryption")]
+                decrypted: None,
             });
         }
 
@@ -118,9 +519,56 @@ impl MappedFile {
             file: Some(file),
             map: Some(map),
             size,
+            #[cfg(feature = "encryption")]
+            decrypted: None,
         })
     }
 
+    /// Maps an encrypted file, and authenticates and decrypts its records into memory, such that
+    /// [`MappedFile::reader`] reads the plaintext. See [`crate::Writer::create_encrypted`].
+    ///
+    /// Fails with [`Error::Unauthenticated`] if a record has been tampered with or if the key is
+    /// wrong.
+    #[cfg(feature = "encryption")]
+    pub fn open_encrypted<P: AsRef<Path>>(path: P, key: &Key) -> Result<Self, Error> {
+        let mut file = Self::open(path)?;
+        let bytes = file.mapped();
+
+        let (config, encryption, start) = Encryption::open(bytes, key)?;
+
+        let mut plaintext = vec![0; bytes.len()];
+        let mut header = bytes::BytesMut::new();
+        config.put_bytes(&mut header, Default::default())?;
+        plaintext[..header.len()].copy_from_slice(&header);
+
+        let end = encryption.decrypt(bytes, start, &mut plaintext)?;
+
+        file.decrypted = Some(Decrypted {
+            encryption,
+            plaintext,
+            end,
+        });
+
+        Ok(file)
+    }
+
+    fn mapped(&self) -> &[u8] {
+        self.map
+            .as_ref()
+            .map(|map| &map[..self.size])
+            .unwrap_or(&[])
+    }
+
+    /// Returns the bytes of the file, or the plaintext if the file is encrypted.
+    fn bytes(&self) -> &[u8] {
+        #[cfg(feature = "encryption")]
+        if let Some(decrypted) = &self.decrypted {
+            return &decrypted.plaintext;
+        }
+
+        self.mapped()
+    }
+
     pub fn resize(&mut self) -> Result<(), Error> {
         let Some(file) = self.file.as_ref() else {
             return Ok(());
@@ -147,16 +595,44 @@ impl MappedFile {
         self.map = Some(map);
         self.size = size;
 
+        #[cfg(feature = "encryption")]
+        if let Some(mut decrypted) = self.decrypted.take() {
+            let bytes = self.mapped();
+
+            decrypted.plaintext.resize(bytes.len(), 0);
+            decrypted.end =
+                decrypted
+                    .encryption
+                    .decrypt(bytes, decrypted.end, &mut decrypted.plaintext)?;
+
+            self.decrypted = Some(decrypted);
+        }
+
         Ok(())
     }
 
+    /// Returns a reader for the file, where a file that is empty or does not exist reads as an
+    /// empty file. Fails if the header cannot be read, e.g. with [`Error::UnsupportedVersion`] if
+    /// the file was written in a newer format version, or with [`Error::Encrypted`] if the file
+    /// is encrypted and was not opened with a key.
+    pub fn try_reader(&self) -> Result<Reader<'_>, Error> {
+        let bytes = self.bytes();
+
+        if bytes.is_empty() {
+            return Ok(Reader::default());
+        }
+
+        Reader::try_from(bytes)
+    }
+
+    /// Like [`MappedFile::try_reader`], but reads a file whose header cannot be read as an empty
+    /// file. Use [`MappedFile::try_reader`] to tell the two apart.
     pub fn reader(&self) -> Reader<'_> {
-        Reader::try_from(
-            self.map
-                .as_ref()
-                .map(|map| &map[..self.size])
-                .unwrap_or(&[]),
-        )
-        .unwrap_or_default()
+        self.try_reader().unwrap_or_default()
+    }
+
+    /// Interprets the mapped file as a [`MarkerIndex`].
+    pub fn index(&self) -> Result<MarkerIndex<'_>, Error> {
+        MarkerIndex::try_from(self.bytes())
     }
 }
@@ -0,0 +1,91 @@
+use crate::{
+    Config, Error, Field,
+    schema::{Schema, Schemas},
+};
+use bytes::{BufMut as _, BytesMut};
+use std::num::{
+    NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128, NonZeroIsize, NonZeroU8,
+    NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128, NonZeroUsize,
+};
+
+impl<'a> Field<'a> for bool {
+    fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let input = slice;
+        let (value, rest) = u8::from_slice(slice, config)?;
+        slice = rest;
+
+        match value {
+            0 => Ok((false, slice)),
+            1 => Ok((true, slice)),
+            value => Err(Error::invalid_discriminant::<Self>(input, value)),
+        }
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, _: Config) -> Result<(), Error> {
+        bytes.put_u8(*self as u8);
+
+        Ok(())
+    }
+
+    fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Bool
+    }
+}
+
+impl<'a> Field<'a> for char {
+    fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let input = slice;
+        let (value, rest) = u32::from_slice(slice, config)?;
+        slice = rest;
+
+        let value = char::from_u32(value).ok_or_else(|| Error::corrupt::<Self>(input))?;
+
+        Ok((value, slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        (*self as u32).put_bytes(bytes, config)
+    }
+
+    fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Char
+    }
+}
+
+/// Encodes the non-zero integers like the integers they wrap, rejecting zero when decoding.
+macro_rules! impl_non_zero {
+    ($ty:ty, $inner:ty) => {
+        impl<'a> Field<'a> for $ty {
+            fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+                let input = slice;
+                let (value, rest) = <$inner>::from_slice(slice, config)?;
+                slice = rest;
+
+                let value = <$ty>::new(value).ok_or_else(|| Error::corrupt::<Self>(input))?;
+
+                Ok((value, slice))
+            }
+
+            fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+                self.get().put_bytes(bytes, config)
+            }
+
+            fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+                <$inner>::schema(schemas)
+            }
+        }
+    };
+}
+
+impl_non_zero!(NonZeroU8, u8);
+impl_non_zero!(NonZeroU16, u16);
+impl_non_zero!(NonZeroU32, u32);
+impl_non_zero!(NonZeroU64, u64);
+impl_non_zero!(NonZeroU128, u128);
+impl_non_zero!(NonZeroUsize, usize);
+impl_non_zero!(NonZeroI8, i8);
+impl_non_zero!(NonZeroI16, i16);
+impl_non_zero!(NonZeroI32, i32);
+impl_non_zero!(NonZeroI64, i64);
+impl_non_zero!(NonZeroI128, i128);
+impl_non_zero!(NonZeroIsize, isize);
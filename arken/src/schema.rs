@@ -0,0 +1,498 @@
+//! Self-describing schemas.
+//!
+//! Every [`Field`] describes its encoding through [`Field::schema`], and the `Arken` derive
+//! describes structs and enums in terms of the schemas of their fields. With
+//! [`crate::Writer::append_schema`], the schema of the records written with a marker is stored in
+//! the file itself, such that generic tools can decode those records without the original types.
+//!
+//! A schema is stored as a [`SchemaRecord`] with the marker [`SCHEMA_MARKER`]. Like any other
+//! record, it is encoded with the [`crate::Config`] from the header of the file. For tooling
+//! written in other languages, the encoding of a [`Schema`] is a `u8` tag, in the order of the
+//! variants starting at 0, followed by the fields of the variant. The other types are encoded as
+//! if derived, i.e. enum variants as a `usize` tag followed by their fields, and structs as their
+//! fields in order. With variable width, integers other than `u8` and `i8` are LEB128 varints,
+//! where signed integers are ZigZag encoded first. The marker that a [`SchemaRecord`] describes is
+//! stored with every byte inverted, as the record would otherwise contain an occurrence of that
+//! marker that looks like a damaged record. The names of types and fields are stored as they are,
+//! so a name that equals the marker still occurs in the record, which [`crate::Reader::find`]
+//! skips like any other occurrence of a marker in the data of a record.
+
+use crate as arken;
+
+use crate::{Arken, ByteStr, Config, Endian, Error, Field};
+use bytes::{BufMut as _, BytesMut};
+use std::{borrow::Cow, collections::BTreeMap, fmt};
+
+/// The marker of the records that hold a [`SchemaRecord`].
+pub const SCHEMA_MARKER: &[u8] = b"ARKEN:SCHEMA";
+
+/// Limits the nesting of schemas read from a file, such that corrupt data cannot exhaust the
+/// stack.
+pub(crate) const MAX_DEPTH: usize = 64;
+
+/// Describes the encoding of a type.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Schema<'a> {
+    Unit,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    Usize,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    Isize,
+    F32,
+    F64,
+    /// A NUL-terminated UTF-8 string, i.e. `Cow<str>`.
+    Str,
+    /// A length followed by that many bytes, i.e. [`ByteStr`].
+    Bytes,
+    /// A length followed by that many elements, e.g. `Cow<[T]>`.
+    Seq(Box<Schema<'a>>),
+    /// A fixed number of elements without a length, i.e. `Cow<[T; N]>`.
+    Array(Box<Schema<'a>>, usize),
+    /// A size in bytes followed by the elements, i.e. [`crate::Array`].
+    Packed(Box<Schema<'a>>),
+    Option(Box<Schema<'a>>),
+    /// The offset of a value in the same file, i.e. [`crate::Ref`].
+    Ref(Box<Schema<'a>>),
+    /// The id of a file followed by the offset of a value in that file, i.e. [`crate::FarRef`].
+    FarRef(Box<Schema<'a>>),
+    Uuid,
+    /// An `i128` mantissa followed by a `u32` scale.
+    Decimal,
+    /// An `i128` mantissa with the given scale.
+    FixedDecimal(u32),
+    /// The number of nanoseconds since the Unix epoch as an `i128`.
+    Timestamp,
+    /// A struct or an enum, described by the [`TypeDef`] with this name.
+    Named(Cow<'a, str>),
+    /// A type that does not describe its encoding.
+    Opaque(Cow<'a, str>),
+    /// A `u8` that is either 0 or 1.
+    Bool,
+    /// A `u32` that is a Unicode scalar value.
+    Char,
+}
+
+impl Schema<'_> {
+    fn tag(&self) -> u8 {
+        match self {
+            Self::Unit => 0,
+            Self::U8 => 1,
+            Self::U16 => 2,
+            Self::U32 => 3,
+            Self::U64 => 4,
+            Self::U128 => 5,
+            Self::Usize => 6,
+            Self::I8 => 7,
+            Self::I16 => 8,
+            Self::I32 => 9,
+            Self::I64 => 10,
+            Self::I128 => 11,
+            Self::Isize => 12,
+            Self::F32 => 13,
+            Self::F64 => 14,
+            Self::Str => 15,
+            Self::Bytes => 16,
+            Self::Seq(_) => 17,
+            Self::Array(..) => 18,
+            Self::Packed(_) => 19,
+            Self::Option(_) => 20,
+            Self::Ref(_) => 21,
+            Self::FarRef(_) => 22,
+            Self::Uuid => 23,
+            Self::Decimal => 24,
+            Self::FixedDecimal(_) => 25,
+            Self::Timestamp => 26,
+            Self::Named(_) => 27,
+            Self::Opaque(_) => 28,
+            Self::Bool => 29,
+            Self::Char => 30,
+        }
+    }
+
+    /// Copies the borrowed names, such that the schema no longer borrows from the file.
+    pub fn into_owned(self) -> Schema<'static> {
+        match self {
+            Self::Unit => Schema::Unit,
+            Self::U8 => Schema::U8,
+            Self::U16 => Schema::U16,
+            Self::U32 => Schema::U32,
+            Self::U64 => Schema::U64,
+            Self::U128 => Schema::U128,
+            Self::Usize => Schema::Usize,
+            Self::I8 => Schema::I8,
+            Self::I16 => Schema::I16,
+            Self::I32 => Schema::I32,
+            Self::I64 => Schema::I64,
+            Self::I128 => Schema::I128,
+            Self::Isize => Schema::Isize,
+            Self::F32 => Schema::F32,
+            Self::F64 => Schema::F64,
+            Self::Str => Schema::Str,
+            Self::Bytes => Schema::Bytes,
+            Self::Seq(schema) => Schema::Seq(Box::new(schema.into_owned())),
+            Self::Array(schema, n) => Schema::Array(Box::new(schema.into_owned()), n),
+            Self::Packed(schema) => Schema::Packed(Box::new(schema.into_owned())),
+            Self::Option(schema) => Schema::Option(Box::new(schema.into_owned())),
+            Self::Ref(schema) => Schema::Ref(Box::new(schema.into_owned())),
+            Self::FarRef(schema) => Schema::FarRef(Box::new(schema.into_owned())),
+            Self::Uuid => Schema::Uuid,
+            Self::Decimal => Schema::Decimal,
+            Self::FixedDecimal(scale) => Schema::FixedDecimal(scale),
+            Self::Timestamp => Schema::Timestamp,
+            Self::Named(name) => Schema::Named(Cow::Owned(name.into_owned())),
+            Self::Opaque(name) => Schema::Opaque(Cow::Owned(name.into_owned())),
+            Self::Bool => Schema::Bool,
+            Self::Char => Schema::Char,
+        }
+    }
+
+    fn decode<'b>(
+        mut slice: &'b [u8],
+        config: Config,
+        depth: usize,
+    ) -> Result<(Schema<'b>, &'b [u8]), Error> {
+        let input = slice;
+
+        if depth > MAX_DEPTH {
+            return Err(Error::corrupt::<Schema>(input));
+        }
+
+        let (tag, rest) = u8::from_slice(slice, config)?;
+        slice = rest;
+
+        let schema = match tag {
+            0 => Schema::Unit,
+            1 => Schema::U8,
+            2 => Schema::U16,
+            3 => Schema::U32,
+            4 => Schema::U64,
+            5 => Schema::U128,
+            6 => Schema::Usize,
+            7 => Schema::I8,
+            8 => Schema::I16,
+            9 => Schema::I32,
+            10 => Schema::I64,
+            11 => Schema::I128,
+            12 => Schema::Isize,
+            13 => Schema::F32,
+            14 => Schema::F64,
+            15 => Schema::Str,
+            16 => Schema::Bytes,
+            17..=22 => {
+                let (schema, rest) = Self::decode(slice, config, depth + 1)?;
+                slice = rest;
+                let schema = Box::new(schema);
+
+                match tag {
+                    17 => Schema::Seq(schema),
+                    18 => {
+                        let (n, rest) = usize::from_slice(slice, config)?;
+                        slice = rest;
+
+                        Schema::Array(schema, n)
+                    }
+                    19 => Schema::Packed(schema),
+                    20 => Schema::Option(schema),
+                    21 => Schema::Ref(schema),
+                    _ => Schema::FarRef(schema),
+                }
+            }
+            23 => Schema::Uuid,
+            24 => Schema::Decimal,
+            25 => {
+                let (scale, rest) = u32::from_slice(slice, config)?;
+                slice = rest;
+
+                Schema::FixedDecimal(scale)
+            }
+            26 => Schema::Timestamp,
+            27 | 28 => {
+                let (name, rest) = Cow::<str>::from_slice(slice, config)?;
+                slice = rest;
+
+                match tag {
+                    27 => Schema::Named(name),
+                    _ => Schema::Opaque(name),
+                }
+            }
+            29 => Schema::Bool,
+            30 => Schema::Char,
+            tag => return Err(Error::invalid_tag::<Schema>(input, tag as usize)),
+        };
+
+        Ok((schema, slice))
+    }
+}
+
+impl<'a> Field<'a> for Schema<'a> {
+    fn from_slice(slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        Self::decode(slice, config, 0)
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        bytes.put_u8(self.tag());
+
+        match self {
+            Self::Seq(schema)
+            | Self::Packed(schema)
+            | Self::Option(schema)
+            | Self::Ref(schema)
+            | Self::FarRef(schema) => schema.put_bytes(bytes, config)?,
+            Self::Array(schema, n) => {
+                schema.put_bytes(bytes, config)?;
+                n.put_bytes(bytes, config)?;
+            }
+            Self::FixedDecimal(scale) => scale.put_bytes(bytes, config)?,
+            Self::Named(name) | Self::Opaque(name) => name.put_bytes(bytes, config)?,
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Schema<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unit => write!(f, "()"),
+            Self::U8 => write!(f, "u8"),
+            Self::U16 => write!(f, "u16"),
+            Self::U32 => write!(f, "u32"),
+            Self::U64 => write!(f, "u64"),
+            Self::U128 => write!(f, "u128"),
+            Self::Usize => write!(f, "usize"),
+            Self::I8 => write!(f, "i8"),
+            Self::I16 => write!(f, "i16"),
+            Self::I32 => write!(f, "i32"),
+            Self::I64 => write!(f, "i64"),
+            Self::I128 => write!(f, "i128"),
+            Self::Isize => write!(f, "isize"),
+            Self::F32 => write!(f, "f32"),
+            Self::F64 => write!(f, "f64"),
+            Self::Str => write!(f, "str"),
+            Self::Bytes => write!(f, "bytes"),
+            Self::Seq(schema) => write!(f, "[{schema}]"),
+            Self::Array(schema, n) => write!(f, "[{schema}; {n}]"),
+            Self::Packed(schema) => write!(f, "Array<{schema}>"),
+            Self::Option(schema) => write!(f, "Option<{schema}>"),
+            Self::Ref(schema) => write!(f, "Ref<{schema}>"),
+            Self::FarRef(schema) => write!(f, "FarRef<{schema}>"),
+            Self::Uuid => write!(f, "Uuid"),
+            Self::Decimal => write!(f, "Decimal"),
+            Self::FixedDecimal(scale) => write!(f, "FixedDecimal<{scale}>"),
+            Self::Timestamp => write!(f, "Timestamp"),
+            Self::Named(name) => write!(f, "{name}"),
+            Self::Opaque(name) => write!(f, "opaque {name}"),
+            Self::Bool => write!(f, "bool"),
+            Self::Char => write!(f, "char"),
+        }
+    }
+}
+
+impl<'a> Field<'a> for Endian {
+    fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let input = slice;
+        let (value, rest) = u8::from_slice(slice, config)?;
+        slice = rest;
+
+        let value = Endian::try_from(value)
+            .map_err(|_| Error::invalid_discriminant::<Self>(input, value))?;
+
+        Ok((value, slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, _: Config) -> Result<(), Error> {
+        bytes.put_u8(*self as u8);
+
+        Ok(())
+    }
+}
+
+/// Whether integers are encoded with a fixed size or as varints.
+#[derive(Arken, Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Width {
+    Fixed,
+    Variable,
+}
+
+/// A field of a struct or an enum variant. Fields that are skipped are not encoded, and are
+/// therefore not described.
+#[derive(Arken, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct FieldDef<'a> {
+    /// The name of the field, or its index for tuple structs and variants.
+    pub name: Cow<'a, str>,
+    pub schema: Schema<'a>,
+    /// Overrides the endianness from the header, as set with `#[arken(endian = ...)]`.
+    pub endian: Option<Endian>,
+    /// Overrides the integer width from the header, as set with `#[arken(size = ...)]`.
+    pub width: Option<Width>,
+    /// The version of the struct that added the field, as set with `#[arken(since = ...)]`.
+    pub since: Option<usize>,
+}
+
+impl<'a> FieldDef<'a> {
+    /// Describes a field that is encoded with the configuration from the header.
+    pub fn new<N: Into<Cow<'a, str>>>(name: N, schema: Schema<'a>) -> Self {
+        Self {
+            name: name.into(),
+            schema,
+            endian: None,
+            width: None,
+            since: None,
+        }
+    }
+}
+
+#[derive(Arken, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct VariantDef<'a> {
+    pub name: Cow<'a, str>,
+    pub fields: Cow<'a, [FieldDef<'a>]>,
+}
+
+#[derive(Arken, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum TypeKind<'a> {
+    /// The fields in the order they are encoded.
+    Struct(Cow<'a, [FieldDef<'a>]>),
+    /// The variants in the order of their tags.
+    Enum(Cow<'a, [VariantDef<'a>]>),
+    /// A struct that is encoded with the version it was written with, as set with
+    /// `#[arken(version = ...)]`, followed by the fields that exist in that version.
+    Versioned {
+        version: usize,
+        fields: Cow<'a, [FieldDef<'a>]>,
+    },
+}
+
+#[derive(Arken, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TypeDef<'a> {
+    pub name: Cow<'a, str>,
+    pub kind: TypeKind<'a>,
+}
+
+/// The structs and enums that a schema refers to through [`Schema::Named`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Schemas<'a> {
+    types: BTreeMap<Cow<'a, str>, TypeKind<'a>>,
+}
+
+impl<'a> Schemas<'a> {
+    /// Describes `T`, and returns its schema along with the types it refers to.
+    pub fn describe<T: Field<'a>>() -> (Schema<'static>, Schemas<'static>) {
+        let mut schemas = Schemas::default();
+        let schema = T::schema(&mut schemas);
+
+        (schema, schemas)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TypeKind<'a>> {
+        self.types.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.types.contains_key(name)
+    }
+
+    pub fn insert<N: Into<Cow<'a, str>>>(&mut self, name: N, kind: TypeKind<'a>) {
+        self.types.insert(name.into(), kind);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &TypeKind<'a>)> {
+        self.types.iter().map(|(name, kind)| (name.as_ref(), kind))
+    }
+}
+
+impl Schemas<'static> {
+    /// Describes the struct `T` with the fields returned by `fields`, for types that implement
+    /// [`Field`] by hand. A placeholder is inserted while the fields are described, such that
+    /// recursive types terminate.
+    pub fn describe_struct<T: ?Sized>(
+        &mut self,
+        fields: impl FnOnce(&mut Self) -> Vec<FieldDef<'static>>,
+    ) -> Schema<'static> {
+        let name = std::any::type_name::<T>();
+
+        if !self.contains(name) {
+            self.insert(name, TypeKind::Struct(Default::default()));
+            let fields = fields(self);
+            self.insert(name, TypeKind::Struct(fields.into()));
+        }
+
+        Schema::Named(name.into())
+    }
+
+    /// Describes the enum `T` with the variants returned by `variants`, like
+    /// [`Schemas::describe_struct`].
+    pub fn describe_enum<T: ?Sized>(
+        &mut self,
+        variants: impl FnOnce(&mut Self) -> Vec<VariantDef<'static>>,
+    ) -> Schema<'static> {
+        let name = std::any::type_name::<T>();
+
+        if !self.contains(name) {
+            self.insert(name, TypeKind::Enum(Default::default()));
+            let variants = variants(self);
+            self.insert(name, TypeKind::Enum(variants.into()));
+        }
+
+        Schema::Named(name.into())
+    }
+}
+
+/// Describes the records written with a marker.
+#[derive(Arken, Clone, Debug, Eq, PartialEq)]
+pub struct SchemaRecord<'a> {
+    /// The marker with every byte inverted. See the [module documentation](self).
+    pub inverted_marker: ByteStr<'a>,
+    pub schema: Schema<'a>,
+    pub types: Cow<'a, [TypeDef<'a>]>,
+}
+
+impl<'a> SchemaRecord<'a> {
+    pub fn new(marker: &[u8], schema: Schema<'a>, schemas: Schemas<'a>) -> Self {
+        let types: Vec<TypeDef> = schemas
+            .types
+            .into_iter()
+            .map(|(name, kind)| TypeDef { name, kind })
+            .collect();
+
+        Self {
+            inverted_marker: marker.iter().map(|byte| !byte).collect::<Vec<u8>>().into(),
+            schema,
+            types: types.into(),
+        }
+    }
+
+    /// Returns the marker of the records that this schema describes.
+    pub fn marker(&self) -> Vec<u8> {
+        self.inverted_marker.iter().map(|byte| !byte).collect()
+    }
+
+    /// Returns `true` if this schema describes the records with the given marker.
+    pub fn describes(&self, marker: &[u8]) -> bool {
+        self.inverted_marker.len() == marker.len()
+            && self
+                .inverted_marker
+                .iter()
+                .zip(marker)
+                .all(|(inverted, byte)| *inverted == !byte)
+    }
+
+    /// Returns the types that the schema refers to.
+    pub fn schemas(&self) -> Schemas<'a> {
+        let mut schemas = Schemas::default();
+
+        for def in self.types.iter() {
+            schemas.insert(def.name.clone(), def.kind.clone());
+        }
+
+        schemas
+    }
+}
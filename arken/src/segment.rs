@@ -0,0 +1,334 @@
+use crate::{Config, Error, Field, MappedFile, MarkerIter, MultiReader, Reader, Ref, Writer};
+use bytes::BytesMut;
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Returns the path of segment `id` of the log named `prefix` in `dir`, e.g. `log.000001.ark`.
+fn segment_path(dir: &Path, prefix: &str, id: u64) -> PathBuf {
+    dir.join(format!("{prefix}.{id:06}.ark"))
+}
+
+/// Lists the segments of the log named `prefix` in `dir`, ordered from oldest to newest.
+fn list_segments(dir: &Path, prefix: &str) -> Result<Vec<(u64, PathBuf)>, Error> {
+    let mut segments = vec![];
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+
+        let Some(id) = name
+            .strip_prefix(prefix)
+            .and_then(|name| name.strip_prefix('.'))
+            .and_then(|name| name.strip_suffix(".ark"))
+            .and_then(|id| id.parse::<u64>().ok())
+        else {
+            continue;
+        };
+
+        segments.push((id, path));
+    }
+
+    segments.sort();
+
+    Ok(segments)
+}
+
+/// Determines which segments of a segmented log may be deleted.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct RetentionPolicy {
+    max_age: Option<Duration>,
+    max_segments: Option<usize>,
+}
+
+impl RetentionPolicy {
+    /// Deletes segments that have not been written to for longer than `max_age`.
+    pub fn with_max_age(&mut self, max_age: Duration) -> &mut Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Deletes the oldest segments until at most `max_segments` segments remain.
+    pub fn with_max_segments(&mut self, max_segments: usize) -> &mut Self {
+        self.max_segments = Some(max_segments);
+        self
+    }
+}
+
+/// A writer for a log that is split over multiple files, called segments. The segments are named
+/// after the log followed by a sequence number, e.g. `log.000001.ark`, `log.000002.ark`, etc.
+///
+/// The writer rolls over to a new segment once the active segment exceeds the configured size or
+/// age. As [`Ref`]s can only point into the file they were read from, the writer only rolls over
+/// after a record has been written with [`SegmentedWriter::append_with_marker`], as such a record
+/// is expected to conclude a transaction. Structures that are committed after a rollover should
+/// therefore not reference structures from an older segment.
+#[derive(Debug)]
+pub struct SegmentedWriter {
+    dir: PathBuf,
+    prefix: String,
+    config: Config,
+    max_size: Option<u64>,
+    max_age: Option<Duration>,
+    id: u64,
+    created: SystemTime,
+    writer: Writer<File>,
+}
+
+impl SegmentedWriter {
+    /// Opens the most recent segment of the log named `prefix` in `dir`, or creates the first
+    /// segment with `config` if there is none. An existing log keeps the configuration of its
+    /// most recent segment, such that the segments that follow it are written the same way.
+    pub fn open<P: AsRef<Path>>(dir: P, prefix: &str, config: Config) -> Result<Self, Error> {
+        let dir = dir.as_ref().to_path_buf();
+
+        let (id, writer, created) = match list_segments(&dir, prefix)?.pop() {
+            Some((id, path)) => {
+                let metadata = std::fs::metadata(&path)?;
+
+                // Not every file system records when a file was created, in which case the last
+                // write is the best estimate.
+                let created = metadata.created().or_else(|_| metadata.modified())?;
+
+                (id, Writer::open(path)?, created)
+            }
+            None => (
+                1,
+                Writer::create(segment_path(&dir, prefix, 1), config)?,
+                SystemTime::now(),
+            ),
+        };
+
+        Ok(Self {
+            dir,
+            prefix: prefix.to_string(),
+            config: writer.config(),
+            max_size: None,
+            max_age: None,
+            id,
+            created,
+            writer,
+        })
+    }
+
+    /// Returns the configuration of the segments.
+    pub fn config(&self) -> Config {
+        self.config
+    }
+
+    /// Rolls over to a new segment once the active segment is at least `max_size` bytes.
+    pub fn with_max_size(&mut self, max_size: u64) -> &mut Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Rolls over to a new segment once the active segment was created at least `max_age` ago,
+    /// which includes the time before the writer was opened.
+    pub fn with_max_age(&mut self, max_age: Duration) -> &mut Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Returns the sequence number of the active segment.
+    pub fn segment(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the path of the active segment.
+    pub fn path(&self) -> PathBuf {
+        segment_path(&self.dir, &self.prefix, self.id)
+    }
+
+    pub fn append<'a, T: Field<'a>>(
+        &mut self,
+        bytes: &mut BytesMut,
+        data: &T,
+    ) -> Result<Ref<'a, T>, Error> {
+        self.writer.append(bytes, data)
+    }
+
+    /// Appends a record to the active segment, aligned to a multiple of `align` bytes. See
+    /// [`Writer::append_aligned`].
+    pub fn append_aligned<'a, T: Field<'a>>(
+        &mut self,
+        bytes: &mut BytesMut,
+        align: usize,
+        data: &T,
+    ) -> Result<Ref<'a, T>, Error> {
+        self.writer.append_aligned(bytes, align, data)
+    }
+
+    /// Appends a record with a marker to the active segment, and rolls over to a new segment if
+    /// the active segment has exceeded its size or age.
+    pub fn append_with_marker<'a, T: Field<'a>>(
+        &mut self,
+        bytes: &mut BytesMut,
+        marker: &'a [u8],
+        data: &T,
+    ) -> Result<Ref<'a, T>, Error> {
+        let reference = self.writer.append_with_marker(bytes, marker, data)?;
+
+        let size = std::fs::metadata(self.path())?.len();
+        let age = self.created.elapsed().unwrap_or_default();
+
+        if self.max_size.is_some_and(|max_size| size >= max_size)
+            || self.max_age.is_some_and(|max_age| age >= max_age)
+        {
+            self.rotate()?;
+        }
+
+        Ok(reference)
+    }
+
+    /// Rolls over to a new segment.
+    pub fn rotate(&mut self) -> Result<(), Error> {
+        self.writer.flush()?;
+
+        let id = self.id + 1;
+        self.writer = Writer::create(segment_path(&self.dir, &self.prefix, id), self.config)?;
+        self.id = id;
+        self.created = SystemTime::now();
+
+        Ok(())
+    }
+
+    /// Deletes the segments that are no longer retained according to the policy, and returns
+    /// their paths. The active segment is never deleted. Note that a segment is deleted even if
+    /// a [`crate::FarRef`] still points into it, see [`SegmentedWriter::apply_retention_with`].
+    pub fn apply_retention(&self, policy: &RetentionPolicy) -> Result<Vec<PathBuf>, Error> {
+        self.apply_retention_with(policy, |_| false)
+    }
+
+    /// Like [`SegmentedWriter::apply_retention`], but never deletes the segments for which
+    /// `is_referenced` returns `true`, e.g. as the application still holds [`crate::FarRef`]s
+    /// into them. Referenced segments still count towards the maximum number of segments.
+    pub fn apply_retention_with<F: FnMut(u64) -> bool>(
+        &self,
+        policy: &RetentionPolicy,
+        mut is_referenced: F,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let mut segments = list_segments(&self.dir, &self.prefix)?;
+        segments.retain(|(id, _)| *id != self.id);
+
+        let mut count = segments.len() + 1;
+        segments.retain(|(id, _)| !is_referenced(*id));
+
+        let mut deleted = vec![];
+
+        for (_, path) in segments {
+            let age = std::fs::metadata(&path)?
+                .modified()?
+                .elapsed()
+                .unwrap_or_default();
+
+            let expired = policy.max_age.is_some_and(|max_age| age >= max_age)
+                || policy
+                    .max_segments
+                    .is_some_and(|max_segments| count > max_segments);
+
+            if !expired {
+                continue;
+            }
+
+            std::fs::remove_file(&path)?;
+            count -= 1;
+            deleted.push(path);
+        }
+
+        Ok(deleted)
+    }
+
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()
+    }
+}
+
+/// Iterates over the records with a given marker across all segments, from the most recent record
+/// in the most recent segment to the oldest record in the oldest segment.
+#[derive(Clone, Debug)]
+pub struct SegmentedMarkerIter<'a, T: Field<'a>> {
+    iters: Vec<MarkerIter<'a, T>>,
+}
+
+impl<'a, T: Field<'a>> Iterator for SegmentedMarkerIter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(iter) = self.iters.last_mut() {
+            if let Some(value) = iter.next() {
+                return Some(value);
+            }
+
+            self.iters.pop();
+        }
+
+        None
+    }
+}
+
+/// A reader for a log that is split over multiple segments by a [`SegmentedWriter`].
+#[derive(Debug)]
+pub struct SegmentedReader {
+    segments: Vec<(u64, MappedFile)>,
+}
+
+impl SegmentedReader {
+    /// Maps every segment of the log named `prefix` in `dir`. Fails if the header of a segment
+    /// cannot be read, see [`MappedFile::try_reader`].
+    pub fn open<P: AsRef<Path>>(dir: P, prefix: &str) -> Result<Self, Error> {
+        let mut segments = vec![];
+
+        for (id, path) in list_segments(dir.as_ref(), prefix)? {
+            let file = MappedFile::open(path)?;
+            file.try_reader()?;
+
+            segments.push((id, file));
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Returns the sequence numbers of the segments, from oldest to newest.
+    pub fn segments(&self) -> impl Iterator<Item = u64> + '_ {
+        self.segments.iter().map(|(id, _)| *id)
+    }
+
+    /// Returns a reader for the segment with the given sequence number.
+    pub fn reader(&self, id: u64) -> Option<Reader<'_>> {
+        let (_, file) = self.segments.iter().find(|(other, _)| *other == id)?;
+
+        Some(file.reader())
+    }
+
+    /// Returns a reader for the most recent segment.
+    pub fn last_reader(&self) -> Option<Reader<'_>> {
+        let (_, file) = self.segments.last()?;
+
+        Some(file.reader())
+    }
+
+    /// Returns a reader that resolves [`crate::FarRef`]s using the sequence numbers of the segments
+    /// as file ids.
+    pub fn multi_reader(&self) -> MultiReader<'_> {
+        self.segments
+            .iter()
+            .fold(MultiReader::default(), |reader, (id, file)| {
+                reader.with_file(*id, file.reader())
+            })
+    }
+
+    pub fn find<'a, T: Field<'a>>(&'a self, marker: &'a [u8]) -> SegmentedMarkerIter<'a, T> {
+        let iters = self
+            .segments
+            .iter()
+            .map(|(_, file)| file.reader().find(marker))
+            .collect();
+
+        SegmentedMarkerIter { iters }
+    }
+}
@@ -0,0 +1,504 @@
+//! Support for exporting collections through serde, and for loading them from serde formats.
+//!
+//! The collections implement [`Serialize`] as maps or sequences of their entries, in the order in
+//! which they iterate. An entry that cannot be read fails serialization with a custom error. To
+//! serialize an arbitrary iterator over a collection, such as a range or a filtered iterator, wrap
+//! it in [`SerdeMap`] or [`SerdeSeq`].
+//!
+//! ```no_run
+//! use arken::{MergeMap, SerdeMap};
+//! use std::borrow::Cow;
+//!
+//! # fn example<'a>(map: &MergeMap<'a, Cow<'a, str>, u64>) -> Result<(), Box<dyn std::error::Error>> {
+//! let json = serde_json::to_string(map)?;
+//! let entries = map.iter().collect::<Result<Vec<_>, _>>()?;
+//! let json = serde_json::to_string(&SerdeMap::new(entries.into_iter().filter(|(_, value)| **value > 10)))?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The `load` methods do the opposite: they insert every entry from a [`Deserializer`] into a
+//! collection and commit it, such that a file can be seeded from e.g. a JSON fixture.
+//!
+//! Finally, [`Serde`] stores any type that implements [`Serialize`] and [`Deserialize`] as a
+//! [`Field`], for types that cannot derive `Arken`, such as types from other crates.
+
+mod format;
+
+use crate::{
+    ByteStr, Config, Error, Field, HashMap, HashRootRef, HashSet, MergeMap, MergeRootRef, MergeSet,
+    TrigramIter, TrigramMap, Value, Writer,
+};
+use ::serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, MapAccess, SeqAccess, Visitor},
+    ser::{self, SerializeMap as _, SerializeSeq as _, SerializeStruct as _},
+};
+use bytes::BytesMut;
+use std::{
+    cell::Cell,
+    collections::HashSet as StdHashSet,
+    fmt,
+    hash::Hash,
+    io::{Seek, Write},
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+/// Stores a `T` through serde rather than through a [`Field`] implementation of its own. The serde
+/// data format used matches the encoding of [`Field`] for the same shapes: integers are varints
+/// or fixed size depending on the [`Config`], strings are NUL-terminated, sequences and maps are
+/// prefixed with their length, structs are their fields in order and enum variants are a `usize`
+/// tag followed by their fields. A `Serde<Vec<u64>>` therefore reads the same bytes as a
+/// `Cow<[u64]>`, and a struct that derives both serde and `Arken` encodes identically through
+/// either, as long as it does not skip fields or override their encoding.
+///
+/// As the format is not self-describing, types that rely on `deserialize_any`, such as untagged
+/// enums, cannot be decoded. Such a `T` is described as [`crate::schema::Schema::Opaque`].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Serde<T>(pub T);
+
+impl<T> Serde<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Serde<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> Deref for Serde<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Serde<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<'a, T: Serialize + Deserialize<'a>> Field<'a> for Serde<T> {
+    fn from_slice(slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let mut deserializer = format::Deserializer { slice, config };
+        let value = T::deserialize(&mut deserializer)?;
+
+        Ok((Self(value), deserializer.slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        self.0.serialize(&mut format::Serializer { bytes, config })
+    }
+}
+
+/// Serializes the key-value pairs produced by an iterator as a map. As serializing consumes the
+/// iterator, serializing the same `SerdeMap` a second time fails.
+pub struct SerdeMap<I>(Cell<Option<I>>);
+
+impl<I> SerdeMap<I> {
+    pub fn new(iter: I) -> Self {
+        Self(Cell::new(Some(iter)))
+    }
+}
+
+impl<K: Serialize, V: Serialize, I: Iterator<Item = (K, V)>> Serialize for SerdeMap<I> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let iter = self
+            .0
+            .take()
+            .ok_or_else(|| ser::Error::custom("the iterator has already been serialized"))?;
+
+        serializer.collect_map(iter)
+    }
+}
+
+/// Serializes the items produced by an iterator as a sequence. As serializing consumes the
+/// iterator, serializing the same `SerdeSeq` a second time fails.
+pub struct SerdeSeq<I>(Cell<Option<I>>);
+
+impl<I> SerdeSeq<I> {
+    pub fn new(iter: I) -> Self {
+        Self(Cell::new(Some(iter)))
+    }
+}
+
+impl<I: Iterator<Item: Serialize>> Serialize for SerdeSeq<I> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let iter = self
+            .0
+            .take()
+            .ok_or_else(|| ser::Error::custom("the iterator has already been serialized"))?;
+
+        serializer.collect_seq(iter)
+    }
+}
+
+/// Serializes the entries of a collection as a map, failing on the first entry that cannot be
+/// read.
+fn collect_map<S, K, V>(
+    serializer: S,
+    iter: impl Iterator<Item = Result<(K, V), Error>>,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    K: Serialize,
+    V: Serialize,
+{
+    let mut map = serializer.serialize_map(None)?;
+
+    for entry in iter {
+        let (key, value) = entry.map_err(ser::Error::custom)?;
+        map.serialize_entry(&key, &value)?;
+    }
+
+    map.end()
+}
+
+/// Serializes the keys of a collection as a sequence, like [`collect_map`].
+fn collect_seq<S: Serializer, K: Serialize>(
+    serializer: S,
+    iter: impl Iterator<Item = Result<K, Error>>,
+) -> Result<S::Ok, S::Error> {
+    let mut seq = serializer.serialize_seq(None)?;
+
+    for key in iter {
+        seq.serialize_element(&key.map_err(ser::Error::custom)?)?;
+    }
+
+    seq.end()
+}
+
+impl<'a, K, V> Serialize for MergeMap<'a, K, V>
+where
+    K: 'a + Clone + Field<'a> + Ord + Serialize,
+    V: 'a + Clone + Field<'a> + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        collect_map(serializer, self.iter())
+    }
+}
+
+impl<'a, K> Serialize for MergeSet<'a, K>
+where
+    K: 'a + Clone + Field<'a> + Ord + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        collect_seq(serializer, self.iter())
+    }
+}
+
+impl<'a, K, V> Serialize for HashMap<'a, K, V>
+where
+    K: 'a + Clone + Field<'a> + Hash + PartialEq + Serialize,
+    V: 'a + Clone + Field<'a> + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        collect_map(serializer, self.iter())
+    }
+}
+
+impl<'a, K> Serialize for HashSet<'a, K>
+where
+    K: 'a + Clone + Field<'a> + Hash + PartialEq + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        collect_seq(serializer, self.iter())
+    }
+}
+
+/// Serializes the key-value pairs of the map, rather than the trigrams it consists of. As every
+/// key is stored once for each of its trigrams, this has to keep track of the keys it has seen.
+/// Keys are serialized as strings if they are valid UTF-8, and as bytes otherwise.
+impl<'a, V, T> Serialize for TrigramMap<'a, V, T>
+where
+    V: 'a + Clone + Field<'a> + Serialize,
+    T: TrigramIter,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seen = StdHashSet::new();
+        let mut map = serializer.serialize_map(None)?;
+
+        for entry in self.trigrams() {
+            let (_, postings) = entry.map_err(ser::Error::custom)?;
+
+            for key_value in postings.iter() {
+                if !seen.insert(key_value.key().to_vec()) {
+                    continue;
+                }
+
+                match std::str::from_utf8(key_value.key()) {
+                    Ok(key) => map.serialize_key(key)?,
+                    Err(_) => map.serialize_key(&ByteStr::from(key_value.key()))?,
+                }
+
+                map.serialize_value(key_value.value())?;
+            }
+        }
+
+        map.end()
+    }
+}
+
+impl Serialize for ByteStr<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self)
+    }
+}
+
+struct ByteStrVisitor;
+
+impl<'de> Visitor<'de> for ByteStrVisitor {
+    type Value = ByteStr<'de>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a byte string")
+    }
+
+    fn visit_borrowed_bytes<E: de::Error>(self, bytes: &'de [u8]) -> Result<Self::Value, E> {
+        Ok(ByteStr::from(bytes))
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        Ok(ByteStr::from(bytes.to_vec()))
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, s: &'de str) -> Result<Self::Value, E> {
+        Ok(ByteStr::from(s.as_bytes()))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+        Ok(ByteStr::from(s.as_bytes().to_vec()))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+
+        Ok(ByteStr::from(bytes))
+    }
+}
+
+/// Accepts bytes, strings and sequences of bytes, as formats such as JSON have no bytes of their
+/// own.
+impl<'de> Deserialize<'de> for ByteStr<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_bytes(ByteStrVisitor)
+    }
+}
+
+/// Serializes structs as maps from the names to the values of their fields, and enum variants as
+/// their name if they have no fields, or as a map from their name to their fields otherwise.
+/// UUIDs and decimals are serialized as strings.
+impl Serialize for Value<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        struct Fields<'b, 'a>(&'b [(std::borrow::Cow<'a, str>, Value<'a>)]);
+
+        impl Serialize for Fields<'_, '_> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_map(self.0.iter().map(|(name, value)| (name, value)))
+            }
+        }
+
+        match self {
+            Self::Unit => serializer.serialize_unit(),
+            Self::U8(value) => value.serialize(serializer),
+            Self::U16(value) => value.serialize(serializer),
+            Self::U32(value) => value.serialize(serializer),
+            Self::U64(value) => value.serialize(serializer),
+            Self::U128(value) => value.serialize(serializer),
+            Self::Usize(value) => value.serialize(serializer),
+            Self::I8(value) => value.serialize(serializer),
+            Self::I16(value) => value.serialize(serializer),
+            Self::I32(value) => value.serialize(serializer),
+            Self::I64(value) => value.serialize(serializer),
+            Self::I128(value) => value.serialize(serializer),
+            Self::Isize(value) => value.serialize(serializer),
+            Self::F32(value) => value.serialize(serializer),
+            Self::F64(value) => value.serialize(serializer),
+            Self::Bool(value) => value.serialize(serializer),
+            Self::Char(value) => value.serialize(serializer),
+            Self::Str(value) => value.serialize(serializer),
+            Self::Bytes(value) => serializer.serialize_bytes(value),
+            Self::Seq(values) => values.serialize(serializer),
+            Self::Option(value) => value.serialize(serializer),
+            Self::Ref(offset) => offset.serialize(serializer),
+            Self::FarRef { file, offset } => {
+                let mut state = serializer.serialize_struct("FarRef", 2)?;
+                state.serialize_field("file", file)?;
+                state.serialize_field("offset", offset)?;
+                state.end()
+            }
+            Self::Uuid(_) | Self::Decimal { .. } => serializer.collect_str(self),
+            Self::Timestamp(value) => value.serialize(serializer),
+            Self::Struct(fields) | Self::Versioned { fields, .. } => {
+                Fields(fields).serialize(serializer)
+            }
+            Self::Variant { name, fields, .. } if fields.is_empty() => name.serialize(serializer),
+            Self::Variant { name, fields, .. } => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(name, &Fields(fields))?;
+                map.end()
+            }
+        }
+    }
+}
+
+/// Collects the entries of a map, such that a collection is only changed once every entry has
+/// been read.
+struct MapEntries<K, V>(PhantomData<(K, V)>);
+
+impl<'de, K, V> Visitor<'de> for MapEntries<K, V>
+where
+    K: Deserialize<'de>,
+    V: Deserialize<'de>,
+{
+    type Value = Vec<(K, V)>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut entries = Vec::new();
+
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Reads the entries of a map from `deserializer`.
+fn map_entries<'de, D, K, V>(deserializer: D) -> Result<Vec<(K, V)>, Error>
+where
+    D: Deserializer<'de>,
+    K: Deserialize<'de>,
+    V: Deserialize<'de>,
+{
+    deserializer
+        .deserialize_map(MapEntries(PhantomData))
+        .map_err(|error| Error::Serde(error.to_string()))
+}
+
+/// Reads the elements of a sequence from `deserializer`, like [`map_entries`].
+fn seq_elements<'de, D, K>(deserializer: D) -> Result<Vec<K>, Error>
+where
+    D: Deserializer<'de>,
+    K: Deserialize<'de>,
+{
+    Vec::deserialize(deserializer).map_err(|error| Error::Serde(error.to_string()))
+}
+
+impl<'a, K, V> MergeMap<'a, K, V>
+where
+    K: 'a + Clone + Field<'a> + Ord,
+    V: 'a + Clone + Field<'a>,
+{
+    /// Inserts every entry of the map read from `deserializer`, and commits the map. The map is
+    /// left as is if `deserializer` fails.
+    pub fn load<'de, D, W>(
+        &mut self,
+        deserializer: D,
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+    ) -> Result<Option<MergeRootRef<'a, K, V>>, Error>
+    where
+        D: Deserializer<'de>,
+        K: Deserialize<'de>,
+        V: Deserialize<'de>,
+        W: Seek + Write,
+    {
+        for (key, value) in map_entries(deserializer)? {
+            self.insert(key, value);
+        }
+
+        self.commit(bytes, writer)
+    }
+}
+
+impl<'a, K> MergeSet<'a, K>
+where
+    K: 'a + Clone + Field<'a> + Ord,
+{
+    /// Inserts every element of the sequence read from `deserializer`, and commits the set. The
+    /// set is left as is if `deserializer` fails.
+    pub fn load<'de, D, W>(
+        &mut self,
+        deserializer: D,
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+    ) -> Result<Option<MergeRootRef<'a, K, ()>>, Error>
+    where
+        D: Deserializer<'de>,
+        K: Deserialize<'de>,
+        W: Seek + Write,
+    {
+        for key in seq_elements(deserializer)? {
+            self.insert(key);
+        }
+
+        self.commit(bytes, writer)
+    }
+}
+
+impl<'a, K, V> HashMap<'a, K, V>
+where
+    K: 'a + Clone + Field<'a> + Hash + PartialEq,
+    V: 'a + Clone + Field<'a>,
+{
+    /// Inserts every entry of the map read from `deserializer`, and commits the map. The map is
+    /// left as is if `deserializer` fails.
+    pub fn load<'de, D, W>(
+        &mut self,
+        deserializer: D,
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+    ) -> Result<Option<HashRootRef<'a, K, V>>, Error>
+    where
+        D: Deserializer<'de>,
+        K: Deserialize<'de>,
+        V: Deserialize<'de>,
+        W: Seek + Write,
+    {
+        for (key, value) in map_entries(deserializer)? {
+            self.insert(key, value);
+        }
+
+        self.commit(bytes, writer)
+    }
+}
+
+impl<'a, K> HashSet<'a, K>
+where
+    K: 'a + Clone + Field<'a> + Hash + PartialEq,
+{
+    /// Inserts every element of the sequence read from `deserializer`, and commits the set. The
+    /// set is left as is if `deserializer` fails.
+    pub fn load<'de, D, W>(
+        &mut self,
+        deserializer: D,
+        bytes: &mut BytesMut,
+        writer: &mut Writer<W>,
+    ) -> Result<Option<HashRootRef<'a, K, ()>>, Error>
+    where
+        D: Deserializer<'de>,
+        K: Deserialize<'de>,
+        W: Seek + Write,
+    {
+        for key in seq_elements(deserializer)? {
+            self.insert(key);
+        }
+
+        self.commit(bytes, writer)
+    }
+}
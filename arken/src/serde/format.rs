@@ -0,0 +1,723 @@
+//! A serde data format that matches the encoding of [`Field`].
+//!
+//! Integers and floats are encoded like their [`Field`] implementations, i.e. as varints or with
+//! a fixed size depending on the [`Config`], strings are NUL-terminated, byte strings and
+//! sequences are prefixed with their length, and options are prefixed with a `u8` tag. Structs
+//! and tuples are encoded as their fields in order, and enum variants as a `usize` tag followed by
+//! their fields, just like the `Arken` derive does. Booleans are encoded as a `u8` and characters
+//! as a `u32`.
+//!
+//! The format is not self-describing, so types that rely on `deserialize_any`, such as untagged
+//! enums, cannot be decoded.
+
+use crate::{ByteStr, Config, Error, Field, MAX_EMPTY_VALUES};
+use ::serde::{
+    de::{
+        self, DeserializeSeed, EnumAccess, SeqAccess, VariantAccess, Visitor,
+        value::U32Deserializer,
+    },
+    ser::{self, Serialize},
+};
+use bytes::{BufMut as _, BytesMut};
+use std::{borrow::Cow, fmt::Display, marker::PhantomData};
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self::Serde(msg.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self::Serde(msg.to_string())
+    }
+}
+
+pub(crate) struct Serializer<'b> {
+    pub(crate) bytes: &'b mut BytesMut,
+    pub(crate) config: Config,
+}
+
+/// Serializes the elements of a sequence, the entries of a map or the fields of a struct. If the
+/// number of elements of a sequence or a map is not known up front, they are buffered such that
+/// they can be prefixed with their number.
+pub(crate) struct Compound<'s, 'b> {
+    serializer: &'s mut Serializer<'b>,
+    buffer: Option<(BytesMut, usize)>,
+}
+
+impl Compound<'_, '_> {
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        match &mut self.buffer {
+            Some((bytes, count)) => {
+                *count += 1;
+
+                value.serialize(&mut Serializer {
+                    bytes,
+                    config: self.serializer.config,
+                })
+            }
+            None => value.serialize(&mut *self.serializer),
+        }
+    }
+
+    /// Serializes a value without counting it, i.e. the value of a map entry.
+    fn value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        match &mut self.buffer {
+            Some((bytes, _)) => value.serialize(&mut Serializer {
+                bytes,
+                config: self.serializer.config,
+            }),
+            None => value.serialize(&mut *self.serializer),
+        }
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        if let Some((buffer, count)) = self.buffer {
+            count.put_bytes(self.serializer.bytes, self.serializer.config)?;
+            self.serializer.bytes.put_slice(&buffer[..]);
+        }
+
+        Ok(())
+    }
+}
+
+impl<'s, 'b> Serializer<'b> {
+    fn compound(&'s mut self, len: Option<usize>) -> Result<Compound<'s, 'b>, Error> {
+        let buffer = match len {
+            Some(len) => {
+                len.put_bytes(self.bytes, self.config)?;
+                None
+            }
+            None => Some((BytesMut::new(), 0)),
+        };
+
+        Ok(Compound {
+            serializer: self,
+            buffer,
+        })
+    }
+
+    fn fields(&'s mut self) -> Compound<'s, 'b> {
+        Compound {
+            serializer: self,
+            buffer: None,
+        }
+    }
+}
+
+impl<'s, 'b> ser::Serializer for &'s mut Serializer<'b> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Compound<'s, 'b>;
+    type SerializeTuple = Compound<'s, 'b>;
+    type SerializeTupleStruct = Compound<'s, 'b>;
+    type SerializeTupleVariant = Compound<'s, 'b>;
+    type SerializeMap = Compound<'s, 'b>;
+    type SerializeStruct = Compound<'s, 'b>;
+    type SerializeStructVariant = Compound<'s, 'b>;
+
+    fn serialize_bool(self, value: bool) -> Result<(), Error> {
+        (value as u8).put_bytes(self.bytes, self.config)
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<(), Error> {
+        value.put_bytes(self.bytes, self.config)
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<(), Error> {
+        value.put_bytes(self.bytes, self.config)
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<(), Error> {
+        value.put_bytes(self.bytes, self.config)
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<(), Error> {
+        value.put_bytes(self.bytes, self.config)
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<(), Error> {
+        value.put_bytes(self.bytes, self.config)
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<(), Error> {
+        value.put_bytes(self.bytes, self.config)
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<(), Error> {
+        value.put_bytes(self.bytes, self.config)
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<(), Error> {
+        value.put_bytes(self.bytes, self.config)
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<(), Error> {
+        value.put_bytes(self.bytes, self.config)
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<(), Error> {
+        value.put_bytes(self.bytes, self.config)
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<(), Error> {
+        value.put_bytes(self.bytes, self.config)
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<(), Error> {
+        value.put_bytes(self.bytes, self.config)
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), Error> {
+        (value as u32).put_bytes(self.bytes, self.config)
+    }
+
+    fn serialize_str(self, value: &str) -> Result<(), Error> {
+        // The string would end at the NUL when read back.
+        if value.contains('\0') {
+            return Err(ser::Error::custom("string contains a NUL character"));
+        }
+
+        Cow::Borrowed(value).put_bytes(self.bytes, self.config)
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), Error> {
+        ByteStr::from(value).put_bytes(self.bytes, self.config)
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.bytes.put_u8(0);
+
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), Error> {
+        self.bytes.put_u8(1);
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        index: u32,
+        _: &'static str,
+    ) -> Result<(), Error> {
+        (index as usize).put_bytes(self.bytes, self.config)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        index: u32,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        (index as usize).put_bytes(self.bytes, self.config)?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        self.compound(len)
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, Error> {
+        Ok(self.fields())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Ok(self.fields())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        index: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        (index as usize).put_bytes(self.bytes, self.config)?;
+
+        Ok(self.fields())
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        self.compound(len)
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct, Error> {
+        Ok(self.fields())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        index: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        (index as usize).put_bytes(self.bytes, self.config)?;
+
+        Ok(self.fields())
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+impl ser::SerializeSeq for Compound<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for Compound<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeMap for Compound<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        self.element(key)
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.value(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for Compound<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for Compound<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+pub(crate) struct Deserializer<'a> {
+    pub(crate) slice: &'a [u8],
+    pub(crate) config: Config,
+}
+
+impl<'a> Deserializer<'a> {
+    fn read<T: Field<'a>>(&mut self) -> Result<T, Error> {
+        let (value, rest) = T::from_slice(self.slice, self.config)?;
+        self.slice = rest;
+
+        Ok(value)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Error> {
+        Err(Error::Serde(
+            "the arken format is not self-describing".to_string(),
+        ))
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let input = self.slice;
+
+        match self.read::<u8>()? {
+            0 => visitor.visit_bool(false),
+            1 => visitor.visit_bool(true),
+            value => Err(Error::invalid_discriminant::<bool>(input, value)),
+        }
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_i8(self.read()?)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_i16(self.read()?)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_i32(self.read()?)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_i64(self.read()?)
+    }
+
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_i128(self.read()?)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_u8(self.read()?)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_u16(self.read()?)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_u32(self.read()?)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_u64(self.read()?)
+    }
+
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_u128(self.read()?)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_f32(self.read()?)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_f64(self.read()?)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let input = self.slice;
+        let value = self.read::<u32>()?;
+
+        let value = char::from_u32(value).ok_or_else(|| Error::corrupt::<char>(input))?;
+
+        visitor.visit_char(value)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.read::<Cow<str>>()? {
+            Cow::Borrowed(value) => visitor.visit_borrowed_str(value),
+            Cow::Owned(value) => visitor.visit_string(value),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.read::<ByteStr>()?.0 {
+            Cow::Borrowed(value) => visitor.visit_borrowed_bytes(value),
+            Cow::Owned(value) => visitor.visit_byte_buf(value),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let input = self.slice;
+
+        match self.read::<u8>()? {
+            0 => visitor.visit_none(),
+            1 => visitor.visit_some(self),
+            value => Err(Error::invalid_discriminant::<Option<()>>(input, value)),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let len = self.read::<usize>()?;
+
+        visitor.visit_seq(Access::<V::Value>::new(self, len))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(Access::<V::Value>::new(self, len))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let len = self.read::<usize>()?;
+
+        visitor.visit_map(Access::<V::Value>::new(self, len))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_tuple(fields.len(), visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_enum(self)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Error> {
+        Err(Error::Serde(
+            "the arken format does not encode identifiers".to_string(),
+        ))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/// Gives access to a fixed number of elements, or entries of a map, of an `S`.
+struct Access<'d, 'de, S: ?Sized> {
+    deserializer: &'d mut Deserializer<'de>,
+    /// The number of elements or entries.
+    n: usize,
+    /// The number of elements or entries left.
+    len: usize,
+    /// The input at the start of the current entry of a map.
+    entry: &'de [u8],
+    _marker: PhantomData<S>,
+}
+
+impl<'d, 'de, S: ?Sized> Access<'d, 'de, S> {
+    fn new(deserializer: &'d mut Deserializer<'de>, n: usize) -> Self {
+        let entry = deserializer.slice;
+
+        Self {
+            deserializer,
+            n,
+            len: n,
+            entry,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Fails as a corrupt `S` if the element or entry that started at `input` took up no bytes
+    /// and there are more of them than there are bytes left, and more than [`MAX_EMPTY_VALUES`],
+    /// like [`crate::decode_seq`] does.
+    fn check(&self, input: &[u8]) -> Result<(), Error> {
+        let slice = self.deserializer.slice;
+
+        if slice.len() == input.len() && self.n > slice.len().max(MAX_EMPTY_VALUES) {
+            return Err(Error::corrupt::<S>(slice).with_index(self.n - self.len - 1));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de, S: ?Sized> SeqAccess<'de> for Access<'_, 'de, S> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+
+        let input = self.deserializer.slice;
+        let value = seed.deserialize(&mut *self.deserializer)?;
+        self.len -= 1;
+        self.check(input)?;
+
+        Ok(Some(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        // The length has not been validated yet, so do not trust it for allocations.
+        Some(self.len.min(self.deserializer.slice.len()))
+    }
+}
+
+impl<'de, S: ?Sized> de::MapAccess<'de> for Access<'_, 'de, S> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+
+        self.entry = self.deserializer.slice;
+
+        seed.deserialize(&mut *self.deserializer).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let value = seed.deserialize(&mut *self.deserializer)?;
+        self.len -= 1;
+        self.check(self.entry)?;
+
+        Ok(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len.min(self.deserializer.slice.len()))
+    }
+}
+
+impl<'de> EnumAccess<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+        let input = self.slice;
+        let index = self.read::<usize>()?;
+
+        let index =
+            u32::try_from(index).map_err(|_| Error::invalid_tag::<V::Value>(input, index))?;
+        let value = seed.deserialize(U32Deserializer::<Error>::new(index))?;
+
+        Ok((value, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_tuple(self, len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_tuple(self, fields.len(), visitor)
+    }
+}
@@ -1,11 +1,15 @@
-use crate::{Config, Endian, Error, Field};
+use crate::{
+    Config, Endian, Error, Field,
+    schema::{Schema, Schemas},
+    varint,
+};
 use bytes::{BufMut as _, BytesMut};
 use pastey::paste;
 
 impl<'a> Field<'a> for i8 {
     fn from_slice(mut slice: &'a [u8], _: Config) -> Result<(Self, &'a [u8]), Error> {
         if slice.is_empty() {
-            return Err(Error::Incomplete);
+            return Err(Error::incomplete::<Self>(slice));
         }
 
         let value = slice[0] as i8;
@@ -19,10 +23,16 @@ impl<'a> Field<'a> for i8 {
 
         Ok(())
     }
+
+    fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::I8
+    }
 }
 
+/// Implements [`Field`] for a signed integer. With variable width, the value is ZigZag encoded into
+/// `$unsigned`, i.e. 0, -1, 1, -2, ... map to 0, 1, 2, 3, ..., before being written as a varint.
 macro_rules! impl_signed_primitive {
-    ($signed:ty, $unsigned:ty) => {
+    ($signed:ty, $unsigned:ty, $decode:ident) => {
         paste! {
             impl<'a> Field<'a> for $signed {
                 fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
@@ -30,7 +40,7 @@ macro_rules! impl_signed_primitive {
                         const N: usize = std::mem::size_of::<$signed>();
 
                         if slice.len() < N {
-                            return Err(Error::Incomplete);
+                            return Err(Error::incomplete::<Self>(slice));
                         }
 
                         let mut bytes = [0u8; N];
@@ -43,27 +53,13 @@ macro_rules! impl_signed_primitive {
                             Endian::Native => $signed::from_ne_bytes(bytes),
                         }
                     } else {
-                        let mut value = 0;
-                        let mut shift = 0;
-
-                        loop {
-                            let (byte, rest) = u8::from_slice(slice, config)?;
-                            slice = rest;
-
-                            let next = byte as $unsigned;
-                            value += (next & 0x7f) << shift;
-
-                            if byte & 0x80 == 0 {
-                                break;
-                            }
+                        const BITS: u32 = <$unsigned>::BITS;
+                        const BYTES: usize = (BITS as usize).div_ceil(7);
 
-                            shift += 7;
-
-                            if shift > std::mem::size_of::<$unsigned>() * 8 {
-                                return Err(Error::Overflow);
-                            }
-                        }
+                        let (value, rest) = varint::$decode::<Self, BITS, BYTES>(slice)?;
+                        slice = rest;
 
+                        let value = value as $unsigned;
                         ((value >> 1) as $signed) ^ (-((value & 1) as $signed))
                     };
 
@@ -95,13 +91,17 @@ macro_rules! impl_signed_primitive {
 
                     Ok(())
                 }
+
+                fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+                    Schema::[<$signed:camel>]
+                }
             }
         }
     };
 }
 
-impl_signed_primitive!(i16, u16);
-impl_signed_primitive!(i32, u32);
-impl_signed_primitive!(i64, u64);
-impl_signed_primitive!(i128, u128);
-impl_signed_primitive!(isize, usize);
+impl_signed_primitive!(i16, u16, decode_u64);
+impl_signed_primitive!(i32, u32, decode_u64);
+impl_signed_primitive!(i64, u64, decode_u64);
+impl_signed_primitive!(i128, u128, decode_u128);
+impl_signed_primitive!(isize, usize, decode_u64);
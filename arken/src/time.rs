@@ -0,0 +1,78 @@
+use crate::{
+    Config, Error, Field,
+    schema::{FieldDef, Schema, Schemas},
+};
+use bytes::BytesMut;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Encoded as the whole seconds as a `u64` followed by the nanoseconds as a `u32`.
+impl<'a> Field<'a> for Duration {
+    fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let input = slice;
+        let (secs, rest) =
+            u64::from_slice(slice, config).map_err(|error| error.with_field("secs"))?;
+        slice = rest;
+        let (nanos, rest) =
+            u32::from_slice(slice, config).map_err(|error| error.with_field("nanos"))?;
+        slice = rest;
+
+        if nanos >= 1_000_000_000 {
+            return Err(Error::corrupt::<Self>(input));
+        }
+
+        Ok((Duration::new(secs, nanos), slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        self.as_secs().put_bytes(bytes, config)?;
+        self.subsec_nanos().put_bytes(bytes, config)?;
+
+        Ok(())
+    }
+
+    fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+        schemas.describe_struct::<Self>(|_| {
+            vec![
+                FieldDef::new("secs", Schema::U64),
+                FieldDef::new("nanos", Schema::U32),
+            ]
+        })
+    }
+}
+
+/// Encoded as the number of nanoseconds since the Unix epoch as an `i128`, like
+/// `jiff::Timestamp`.
+impl<'a> Field<'a> for SystemTime {
+    fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+        let input = slice;
+        let (value, rest) = i128::from_slice(slice, config)?;
+        slice = rest;
+
+        let duration = u64::try_from(value.unsigned_abs() / 1_000_000_000)
+            .map(|secs| Duration::new(secs, (value.unsigned_abs() % 1_000_000_000) as u32))
+            .map_err(|_| Error::corrupt::<Self>(input))?;
+
+        let value = if value < 0 {
+            UNIX_EPOCH.checked_sub(duration)
+        } else {
+            UNIX_EPOCH.checked_add(duration)
+        };
+
+        let value = value.ok_or_else(|| Error::corrupt::<Self>(input))?;
+
+        Ok((value, slice))
+    }
+
+    fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+        let nanos = match self.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_nanos() as i128,
+            Err(error) => -(error.duration().as_nanos() as i128),
+        };
+
+        nanos.put_bytes(bytes, config)
+    }
+
+    fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Timestamp
+    }
+}
@@ -1,6 +1,6 @@
 use crate as arken;
 
-use arken::{Arken, Error, Field, MergeMap, MergeRootRef, Reader, Writer};
+use arken::{Arken, ByteStr, Error, Field, MergeMap, MergeRootRef, Reader, Writer};
 use bytes::BytesMut;
 use ordered_float::NotNan;
 use std::{
@@ -64,7 +64,9 @@ impl<'a> From<&'a str> for StringTrigramIter<'a> {
 
 impl<'a> From<&'a [u8]> for StringTrigramIter<'a> {
     fn from(bytes: &'a [u8]) -> Self {
-        Self::from(std::str::from_utf8(&bytes[..]).unwrap())
+        // Only valid UTF-8 consists of characters to form trigrams from, so treat anything else
+        // (e.g. a corrupt key) as having no trigrams rather than panicking.
+        Self::from(std::str::from_utf8(bytes).unwrap_or_default())
     }
 }
 
@@ -83,33 +85,50 @@ impl<'a> Iterator for StringTrigramIter<'a> {
 }
 
 pub trait TrigramIter {
-    fn trigrams<'a>(bytes: &'a [u8]) -> impl Iterator<Item = &'a [u8]>;
+    fn trigrams(bytes: &[u8]) -> impl Iterator<Item = &[u8]>;
 }
 
 impl TrigramIter for ByteTrigramIter<'_> {
-    fn trigrams<'a>(bytes: &'a [u8]) -> impl Iterator<Item = &'a [u8]> {
+    fn trigrams(bytes: &[u8]) -> impl Iterator<Item = &[u8]> {
         ByteTrigramIter::from(bytes)
     }
 }
 
 impl TrigramIter for StringTrigramIter<'_> {
-    fn trigrams<'a>(bytes: &'a [u8]) -> impl Iterator<Item = &'a [u8]> {
+    fn trigrams(bytes: &[u8]) -> impl Iterator<Item = &[u8]> {
         StringTrigramIter::from(bytes)
     }
 }
 
 #[derive(Arken, Clone, Debug)]
 pub struct KeyValue<'a, V: Field<'a>> {
-    key: Cow<'a, [u8]>,
+    key: ByteStr<'a>,
     value: V,
     #[arken(skip_with = &PhantomData)]
     _value_lifetime: &'a PhantomData<V>,
 }
 
-pub type TrigramRootRef<'a, V> = MergeRootRef<'a, Cow<'a, [u8]>, Cow<'a, [KeyValue<'a, V>]>>;
+impl<'a, V: Field<'a>> KeyValue<'a, V> {
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    pub fn value(&self) -> &V {
+        &self.value
+    }
+}
+
+/// The keys that match a query, along with their similarity to the query, from the least to the
+/// most similar.
+pub type QueryResults = BTreeSet<(NotNan<f32>, Vec<u8>)>;
+
+/// The key-value pairs of the keys that contain a trigram.
+pub type Postings<'a, V> = Cow<'a, [KeyValue<'a, V>]>;
+
+pub type TrigramRootRef<'a, V> = MergeRootRef<'a, ByteStr<'a>, Postings<'a, V>>;
 
 pub struct TrigramMap<'a, V: Clone + Field<'a>, T: TrigramIter> {
-    trigram_map: MergeMap<'a, Cow<'a, [u8]>, Cow<'a, [KeyValue<'a, V>]>>,
+    trigram_map: MergeMap<'a, ByteStr<'a>, Cow<'a, [KeyValue<'a, V>]>>,
     _marker: PhantomData<T>,
 }
 
@@ -123,61 +142,65 @@ impl<'a, V: Clone + Field<'a>, T: TrigramIter> TrigramMap<'a, V, T> {
         }
     }
 
-    pub fn contains_key(&self, key: &'a [u8]) -> bool {
-        let Some(trigram) = T::trigrams(key).next() else {
-            return false;
-        };
-
-        let Some(values) = self.trigram_map.get(&Cow::Borrowed(trigram)) else {
-            return false;
-        };
+    /// Gets an iterator over the trigrams in sorted order, along with the key-value pairs of
+    /// the keys that contain each trigram.
+    pub fn trigrams<'b>(
+        &'b self,
+    ) -> impl Iterator<Item = Result<(Cow<'b, ByteStr<'a>>, Cow<'b, Postings<'a, V>>), Error>> + 'b
+    {
+        self.trigram_map.iter()
+    }
 
-        for key_value in values.as_ref().iter() {
-            if key_value.key == key {
-                return true;
-            }
-        }
+    /// Returns the number of trigrams in each of the committed sorted tables, from the oldest to
+    /// the most recent table.
+    pub fn node_sizes(&self) -> Vec<usize> {
+        self.trigram_map.node_sizes()
+    }
 
-        false
+    pub fn contains_key(&self, key: &'a [u8]) -> Result<bool, Error> {
+        Ok(self.get(key)?.is_some())
     }
 
-    pub fn get<'b>(&'b self, key: &'a [u8]) -> Option<Cow<'b, V>> {
-        let trigram = T::trigrams(key).next()?;
-        let values = self.trigram_map.get(&Cow::Borrowed(trigram))?;
+    pub fn get<'b>(&'b self, key: &'a [u8]) -> Result<Option<Cow<'b, V>>, Error> {
+        let Some(trigram) = T::trigrams(key).next() else {
+            return Ok(None);
+        };
+
+        let Some(values) = self.trigram_map.get(&ByteStr::from(trigram))? else {
+            return Ok(None);
+        };
 
         for key_value in values.as_ref().iter() {
-            if key_value.key == key {
-                return Some(Cow::Owned(key_value.value.clone()));
+            if *key_value.key == *key {
+                return Ok(Some(Cow::Owned(key_value.value.clone())));
             }
         }
 
-        None
+        Ok(None)
     }
 
-    pub fn query(&self, key: &'a [u8]) -> BTreeSet<(NotNan<f32>, Vec<u8>)> {
+    pub fn query(&self, key: &'a [u8]) -> Result<QueryResults, Error> {
         let mut results = HashSet::new();
         let mut key_set = HashSet::new();
 
         for trigram in T::trigrams(key) {
             key_set.insert(trigram);
 
-            let Some(values) = self.trigram_map.get(&Cow::Borrowed(trigram)) else {
+            let Some(values) = self.trigram_map.get(&ByteStr::from(trigram))? else {
                 continue;
             };
 
             for key_value in values.as_ref().iter() {
-                let key = key_value.key.clone().into_owned();
-
-                results.insert(key);
+                results.insert(key_value.key.clone());
             }
         }
 
-        let results: BTreeSet<(NotNan<f32>, Vec<u8>)> = results
+        let results: QueryResults = results
             .into_iter()
             .map(|key| {
                 let mut set = HashSet::new();
 
-                for trigram in T::trigrams(&key[..]) {
+                for trigram in T::trigrams(&key) {
                     set.insert(trigram);
                 }
 
@@ -186,42 +209,42 @@ impl<'a, V: Clone + Field<'a>, T: TrigramIter> TrigramMap<'a, V, T> {
                 let similarity =
                     NotNan::new(intersection as f32).unwrap() / NotNan::new(union as f32).unwrap();
 
-                (similarity, key)
+                (similarity, key.into_owned())
             })
             .collect();
 
-        results
+        Ok(results)
     }
 
-    pub fn insert(&mut self, key: &'a [u8], value: V) -> Option<V> {
-        if self.contains_key(key) {
+    pub fn insert(&mut self, key: &'a [u8], value: V) -> Result<Option<V>, Error> {
+        if self.contains_key(key)? {
             return self.remove(key);
         }
 
         for trigram in T::trigrams(key) {
             let mut values = self
                 .trigram_map
-                .get(&Cow::Borrowed(trigram))
+                .get(&ByteStr::from(trigram))?
                 .unwrap_or_default()
                 .into_owned()
                 .to_vec();
 
             values.push(KeyValue {
-                key: Cow::Borrowed(key),
+                key: ByteStr::from(key),
                 value: value.clone(),
                 _value_lifetime: &PhantomData,
             });
 
             self.trigram_map
-                .insert(Cow::Borrowed(trigram), Cow::Owned(values));
+                .insert(ByteStr::from(trigram), Cow::Owned(values));
         }
 
-        None
+        Ok(None)
     }
 
-    pub fn remove(&mut self, key: &'a [u8]) -> Option<V> {
-        if !self.contains_key(key) {
-            return None;
+    pub fn remove(&mut self, key: &'a [u8]) -> Result<Option<V>, Error> {
+        if !self.contains_key(key)? {
+            return Ok(None);
         }
 
         let mut value = None;
@@ -229,12 +252,12 @@ impl<'a, V: Clone + Field<'a>, T: TrigramIter> TrigramMap<'a, V, T> {
         for trigram in T::trigrams(key) {
             let mut values = self
                 .trigram_map
-                .get(&Cow::Borrowed(trigram))
+                .get(&ByteStr::from(trigram))?
                 .unwrap_or_default()
                 .into_owned()
                 .to_vec();
 
-            let Some(index) = values.iter().position(|key_value| key_value.key == key) else {
+            let Some(index) = values.iter().position(|key_value| *key_value.key == *key) else {
                 continue;
             };
 
@@ -242,14 +265,14 @@ impl<'a, V: Clone + Field<'a>, T: TrigramIter> TrigramMap<'a, V, T> {
             value = Some(key_value.value);
 
             if values.is_empty() {
-                self.trigram_map.remove(&Cow::Borrowed(trigram));
+                self.trigram_map.remove(&ByteStr::from(trigram));
             } else {
                 self.trigram_map
-                    .insert(Cow::Borrowed(trigram), Cow::Owned(values));
+                    .insert(ByteStr::from(trigram), Cow::Owned(values));
             }
         }
 
-        value
+        Ok(value)
     }
 
     pub fn commit<W: Seek + Write>(
@@ -268,20 +291,22 @@ impl<'a, T: TrigramIter> TrigramSet<'a, T> {
         Self(TrigramMap::open(reader, root_reference))
     }
 
-    pub fn contains(&self, key: &'a [u8]) -> bool {
+    pub fn contains(&self, key: &'a [u8]) -> Result<bool, Error> {
         self.0.contains_key(key)
     }
 
-    pub fn query(&self, key: &'a [u8]) -> BTreeSet<(NotNan<f32>, Vec<u8>)> {
+    pub fn query(&self, key: &'a [u8]) -> Result<QueryResults, Error> {
         self.0.query(key)
     }
 
-    pub fn insert(&mut self, key: &'a [u8]) {
-        self.0.insert(key, ());
+    pub fn insert(&mut self, key: &'a [u8]) -> Result<(), Error> {
+        self.0.insert(key, ())?;
+
+        Ok(())
     }
 
-    pub fn remove(&mut self, key: &'a [u8]) -> bool {
-        self.0.remove(key).is_some()
+    pub fn remove(&mut self, key: &'a [u8]) -> Result<bool, Error> {
+        Ok(self.0.remove(key)?.is_some())
     }
 
     pub fn commit<W: Seek + Write>(
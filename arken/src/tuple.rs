@@ -0,0 +1,76 @@
+use crate::{
+    Config, Error, Field, Reader, Writer,
+    schema::{FieldDef, Schema, Schemas},
+    verify::Verifier,
+};
+use bytes::BytesMut;
+use std::io::{Seek, Write};
+
+/// Encodes tuples as their elements in order, and describes them as structs with the fields
+/// `0`, `1` and so on.
+macro_rules! impl_tuple {
+    ($($ty:ident $index:tt),+) => {
+        impl<'a, $($ty: Field<'a>),+> Field<'a> for ($($ty,)+) {
+            fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
+                let value = ($(
+                    {
+                        let (value, rest) = $ty::from_slice(slice, config)
+                            .map_err(|error| error.with_index($index))?;
+                        slice = rest;
+                        value
+                    },
+                )+);
+
+                Ok((value, slice))
+            }
+
+            fn put_bytes(&self, bytes: &mut BytesMut, config: Config) -> Result<(), Error> {
+                $(
+                    self.$index.put_bytes(bytes, config)?;
+                )+
+
+                Ok(())
+            }
+
+            fn migrate<W: Seek + Write>(
+                &mut self,
+                bytes: &mut BytesMut,
+                writer: &mut Writer<W>,
+                reader: &Reader<'a>,
+            ) -> Result<(), Error> {
+                $(
+                    self.$index.migrate(bytes, writer, reader)?;
+                )+
+
+                Ok(())
+            }
+
+            fn verify(&self, verifier: &mut Verifier<'a>) {
+                $(
+                    self.$index.verify(verifier);
+                )+
+            }
+
+            fn schema(schemas: &mut Schemas<'static>) -> Schema<'static> {
+                schemas.describe_struct::<Self>(|schemas| vec![
+                    $(
+                        FieldDef::new(stringify!($index), $ty::schema(schemas)),
+                    )+
+                ])
+            }
+        }
+    };
+}
+
+impl_tuple!(A 0);
+impl_tuple!(A 0, B 1);
+impl_tuple!(A 0, B 1, C 2);
+impl_tuple!(A 0, B 1, C 2, D 3);
+impl_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+impl_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+impl_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+impl_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+impl_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
@@ -1,11 +1,15 @@
-use crate::{Config, Endian, Error, Field};
+use crate::{
+    Config, Endian, Error, Field,
+    schema::{Schema, Schemas},
+    varint,
+};
 use bytes::{BufMut as _, BytesMut};
 use pastey::paste;
 
 impl<'a> Field<'a> for u8 {
     fn from_slice(mut slice: &'a [u8], _: Config) -> Result<(Self, &'a [u8]), Error> {
         if slice.is_empty() {
-            return Err(Error::Incomplete);
+            return Err(Error::incomplete::<Self>(slice));
         }
 
         let value = slice[0];
@@ -19,10 +23,14 @@ impl<'a> Field<'a> for u8 {
 
         Ok(())
     }
+
+    fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::U8
+    }
 }
 
 macro_rules! impl_unsigned_primitive {
-    ($ty:ty) => {
+    ($ty:ty, $decode:ident) => {
         paste! {
             impl<'a> Field<'a> for $ty {
                 fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
@@ -30,7 +38,7 @@ macro_rules! impl_unsigned_primitive {
                         const N: usize = std::mem::size_of::<$ty>();
 
                         if slice.len() < N {
-                            return Err(Error::Incomplete);
+                            return Err(Error::incomplete::<Self>(slice));
                         }
 
                         let mut bytes = [0u8; N];
@@ -43,28 +51,13 @@ macro_rules! impl_unsigned_primitive {
                             Endian::Native => $ty::from_ne_bytes(bytes),
                         }
                     } else {
-                        let mut value = 0;
-                        let mut shift = 0;
-
-                        loop {
-                            let (byte, rest) = u8::from_slice(slice, config)?;
-                            slice = rest;
-
-                            let next = byte as $ty;
-                            value += (next & 0x7f) << shift;
-
-                            if byte & 0x80 == 0 {
-                                break;
-                            }
+                        const BITS: u32 = <$ty>::BITS;
+                        const BYTES: usize = (BITS as usize).div_ceil(7);
 
-                            shift += 7;
+                        let (value, rest) = varint::$decode::<Self, BITS, BYTES>(slice)?;
+                        slice = rest;
 
-                            if shift > std::mem::size_of::<$ty>() * 8 {
-                                return Err(Error::Overflow);
-                            }
-                        }
-
-                        value
+                        value as $ty
                     };
 
                     Ok((value, slice))
@@ -92,13 +85,17 @@ macro_rules! impl_unsigned_primitive {
 
                     Ok(())
                 }
+
+                fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+                    Schema::[<$ty:camel>]
+                }
             }
         }
     };
 }
 
-impl_unsigned_primitive!(u16);
-impl_unsigned_primitive!(u32);
-impl_unsigned_primitive!(u64);
-impl_unsigned_primitive!(u128);
-impl_unsigned_primitive!(usize);
+impl_unsigned_primitive!(u16, decode_u64);
+impl_unsigned_primitive!(u32, decode_u64);
+impl_unsigned_primitive!(u64, decode_u64);
+impl_unsigned_primitive!(u128, decode_u128);
+impl_unsigned_primitive!(usize, decode_u64);
@@ -1,11 +1,14 @@
-use crate::{Config, Endian, Error, Field};
+use crate::{
+    Config, Endian, Error, Field,
+    schema::{Schema, Schemas},
+};
 use ::uuid::Uuid;
 use bytes::{BufMut as _, BytesMut};
 
 impl<'a> Field<'a> for Uuid {
     fn from_slice(mut slice: &'a [u8], config: Config) -> Result<(Self, &'a [u8]), Error> {
         if slice.len() < 16 {
-            return Err(Error::Incomplete);
+            return Err(Error::incomplete::<Self>(slice));
         }
 
         let value = match config.endian {
@@ -34,4 +37,8 @@ impl<'a> Field<'a> for Uuid {
 
         Ok(())
     }
+
+    fn schema(_: &mut Schemas<'static>) -> Schema<'static> {
+        Schema::Uuid
+    }
 }
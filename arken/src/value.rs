@@ -0,0 +1,681 @@
+//! Dynamically typed values.
+//!
+//! A [`Value`] holds any value that can be described by a [`Schema`], and is decoded and encoded
+//! given that schema at runtime rather than through a [`Field`] implementation. Together with the
+//! schemas written into a file (see [`crate::schema`]), this allows tools to read and rewrite
+//! records without the types that wrote them.
+//!
+//! ```no_run
+//! use arken::{MappedFile, Value};
+//!
+//! let file = MappedFile::open("map.bin")?;
+//! let reader = file.reader();
+//!
+//! if let Some(record) = reader.schema(b"users") {
+//!     let schemas = record.schemas();
+//!
+//!     if let Some((offset, _)) = reader.find::<()>(b"users").next_with_offset() {
+//!         let value = reader.read_value(offset, &record.schema, &schemas)?;
+//!         println!("{value}");
+//!     }
+//! }
+//! # Ok::<(), arken::Error>(())
+//! ```
+
+use crate::{
+    Config, Endian, Error, Field, decode_seq,
+    schema::{MAX_DEPTH, Schema, Schemas, TypeKind, Width},
+};
+use bytes::{BufMut as _, BytesMut};
+use std::{borrow::Cow, fmt};
+
+/// A value decoded through a [`Schema`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value<'a> {
+    Unit,
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    Usize(usize),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    Isize(isize),
+    F32(f32),
+    F64(f64),
+    Bool(bool),
+    Char(char),
+    Str(Cow<'a, str>),
+    Bytes(Cow<'a, [u8]>),
+    /// The elements of a [`Schema::Seq`], [`Schema::Array`] or [`Schema::Packed`].
+    Seq(Vec<Value<'a>>),
+    Option(Option<Box<Value<'a>>>),
+    /// The offset of the value a [`crate::Ref`] points to.
+    Ref(usize),
+    /// The id of the file and the offset of the value a [`crate::FarRef`] points to.
+    FarRef {
+        file: u64,
+        offset: usize,
+    },
+    /// The bytes of a UUID in the order of RFC 9562, regardless of the endianness of the file.
+    Uuid([u8; 16]),
+    /// A decimal, or a fixed decimal with the scale from its schema.
+    Decimal {
+        mantissa: i128,
+        scale: u32,
+    },
+    /// The number of nanoseconds since the Unix epoch.
+    Timestamp(i128),
+    /// The fields of a struct in the order they are encoded.
+    Struct(Vec<(Cow<'a, str>, Value<'a>)>),
+    /// The version a versioned struct was written with, along with the fields that exist in that
+    /// version.
+    Versioned {
+        version: usize,
+        fields: Vec<(Cow<'a, str>, Value<'a>)>,
+    },
+    /// An enum variant along with its fields.
+    Variant {
+        tag: usize,
+        name: Cow<'a, str>,
+        fields: Vec<(Cow<'a, str>, Value<'a>)>,
+    },
+}
+
+/// Converts the bytes of a UUID between the order of RFC 9562 and the mixed-endian order used for
+/// little endian files. The conversion is its own inverse.
+fn swap_uuid(mut bytes: [u8; 16]) -> [u8; 16] {
+    bytes[0..4].reverse();
+    bytes[4..6].reverse();
+    bytes[6..8].reverse();
+    bytes
+}
+
+fn is_big_endian(config: Config) -> bool {
+    match config.endian() {
+        Endian::Big => true,
+        Endian::Little => false,
+        Endian::Native => cfg!(target_endian = "big"),
+    }
+}
+
+/// Applies the overrides of a field to the configuration it is encoded with.
+fn field_config(mut config: Config, endian: Option<Endian>, width: Option<Width>) -> Config {
+    match width {
+        Some(Width::Fixed) => {
+            config.fixed_width();
+        }
+        Some(Width::Variable) => {
+            config.variable_width();
+        }
+        None => {}
+    }
+
+    if let Some(endian) = endian {
+        config.with_endian(endian);
+    }
+
+    config
+}
+
+impl<'a> Value<'a> {
+    /// Decodes a value described by `schema` from the start of `slice`, looking up the structs
+    /// and enums it refers to in `schemas`.
+    pub fn from_slice<'s: 'a>(
+        slice: &'a [u8],
+        config: Config,
+        schema: &Schema<'s>,
+        schemas: &Schemas<'s>,
+    ) -> Result<(Self, &'a [u8]), Error> {
+        Self::decode(slice, config, schema, schemas, 0)
+    }
+
+    fn decode<'s: 'a>(
+        mut slice: &'a [u8],
+        config: Config,
+        schema: &Schema<'s>,
+        schemas: &Schemas<'s>,
+        depth: usize,
+    ) -> Result<(Self, &'a [u8]), Error> {
+        let input = slice;
+
+        if depth > MAX_DEPTH {
+            return Err(Error::corrupt::<Self>(input));
+        }
+
+        macro_rules! primitive {
+            ($ty:ty, $variant:ident) => {{
+                let (value, rest) = <$ty>::from_slice(slice, config)?;
+                slice = rest;
+
+                Self::$variant(value)
+            }};
+        }
+
+        let value = match schema {
+            Schema::Unit => Self::Unit,
+            Schema::U8 => primitive!(u8, U8),
+            Schema::U16 => primitive!(u16, U16),
+            Schema::U32 => primitive!(u32, U32),
+            Schema::U64 => primitive!(u64, U64),
+            Schema::U128 => primitive!(u128, U128),
+            Schema::Usize => primitive!(usize, Usize),
+            Schema::I8 => primitive!(i8, I8),
+            Schema::I16 => primitive!(i16, I16),
+            Schema::I32 => primitive!(i32, I32),
+            Schema::I64 => primitive!(i64, I64),
+            Schema::I128 => primitive!(i128, I128),
+            Schema::Isize => primitive!(isize, Isize),
+            Schema::F32 => primitive!(f32, F32),
+            Schema::F64 => primitive!(f64, F64),
+            Schema::Bool => primitive!(bool, Bool),
+            Schema::Char => primitive!(char, Char),
+            Schema::Str => primitive!(Cow<str>, Str),
+            Schema::Bytes => {
+                let (value, rest) = crate::ByteStr::from_slice(slice, config)?;
+                slice = rest;
+
+                Self::Bytes(value.0)
+            }
+            Schema::Seq(schema) => {
+                let (n, rest) = usize::from_slice(slice, config)?;
+                let (values, rest) = decode_seq::<_, Self>(rest, n, |slice| {
+                    Self::decode(slice, config, schema, schemas, depth + 1)
+                })?;
+                slice = rest;
+
+                Self::Seq(values)
+            }
+            Schema::Array(schema, n) => {
+                // The length comes from the schema, which is read from the file as well.
+                let (values, rest) = decode_seq::<_, Self>(slice, *n, |slice| {
+                    Self::decode(slice, config, schema, schemas, depth + 1)
+                })?;
+                slice = rest;
+
+                Self::Seq(values)
+            }
+            Schema::Packed(schema) => {
+                let (n, rest) = usize::from_slice(slice, config)?;
+                slice = rest;
+
+                if slice.len() < n {
+                    return Err(Error::incomplete::<Self>(slice));
+                }
+
+                let mut items = &slice[..n];
+                slice = &slice[n..];
+
+                let mut values = vec![];
+
+                while !items.is_empty() {
+                    let (value, rest) = Self::decode(items, config, schema, schemas, depth + 1)
+                        .map_err(|error| error.with_index(values.len()))?;
+
+                    // Elements that take up no space would never exhaust the bytes.
+                    if rest.len() == items.len() {
+                        return Err(Error::corrupt::<Self>(items));
+                    }
+
+                    items = rest;
+                    values.push(value);
+                }
+
+                Self::Seq(values)
+            }
+            Schema::Option(schema) => {
+                let (tag, rest) = u8::from_slice(slice, config)?;
+                slice = rest;
+
+                match tag {
+                    0 => Self::Option(None),
+                    1 => {
+                        let (value, rest) =
+                            Self::decode(slice, config, schema, schemas, depth + 1)?;
+                        slice = rest;
+
+                        Self::Option(Some(Box::new(value)))
+                    }
+                    value => return Err(Error::invalid_discriminant::<Self>(input, value)),
+                }
+            }
+            Schema::Ref(_) => primitive!(usize, Ref),
+            Schema::FarRef(_) => {
+                let (file, rest) = u64::from_slice(slice, config)?;
+                slice = rest;
+                let (offset, rest) = usize::from_slice(slice, config)?;
+                slice = rest;
+
+                Self::FarRef { file, offset }
+            }
+            Schema::Uuid => {
+                let Some(bytes) = slice.get(..16) else {
+                    return Err(Error::incomplete::<Self>(slice));
+                };
+
+                let mut value = [0u8; 16];
+                value.copy_from_slice(bytes);
+                slice = &slice[16..];
+
+                if !is_big_endian(config) {
+                    value = swap_uuid(value);
+                }
+
+                Self::Uuid(value)
+            }
+            Schema::Decimal => {
+                let (mantissa, rest) = i128::from_slice(slice, config)?;
+                slice = rest;
+                let (scale, rest) = u32::from_slice(slice, config)?;
+                slice = rest;
+
+                Self::Decimal { mantissa, scale }
+            }
+            Schema::FixedDecimal(scale) => {
+                let (mantissa, rest) = i128::from_slice(slice, config)?;
+                slice = rest;
+
+                Self::Decimal {
+                    mantissa,
+                    scale: *scale,
+                }
+            }
+            Schema::Timestamp => primitive!(i128, Timestamp),
+            Schema::Named(name) => match schemas.get(name) {
+                Some(TypeKind::Struct(defs)) => {
+                    let mut fields = Vec::with_capacity(defs.len());
+
+                    for (index, def) in defs.iter().enumerate() {
+                        let config = field_config(config, def.endian, def.width);
+                        let (value, rest) =
+                            Self::decode(slice, config, &def.schema, schemas, depth + 1)
+                                .map_err(|error| error.with_index(index))?;
+                        slice = rest;
+                        fields.push((def.name.clone(), value));
+                    }
+
+                    Self::Struct(fields)
+                }
+                Some(TypeKind::Versioned {
+                    version: latest,
+                    fields: defs,
+                }) => {
+                    let (version, rest) = usize::from_slice(slice, config)?;
+                    slice = rest;
+
+                    if version > *latest {
+                        return Err(Error::unknown_version::<Self>(input, version));
+                    }
+
+                    let mut fields = Vec::with_capacity(defs.len());
+
+                    for (index, def) in defs.iter().enumerate() {
+                        if def.since.is_some_and(|since| since > version) {
+                            continue;
+                        }
+
+                        let config = field_config(config, def.endian, def.width);
+                        let (value, rest) =
+                            Self::decode(slice, config, &def.schema, schemas, depth + 1)
+                                .map_err(|error| error.with_index(index))?;
+                        slice = rest;
+                        fields.push((def.name.clone(), value));
+                    }
+
+                    Self::Versioned { version, fields }
+                }
+                Some(TypeKind::Enum(variants)) => {
+                    let (tag, rest) = usize::from_slice(slice, config)?;
+                    slice = rest;
+
+                    let Some(variant) = variants.get(tag) else {
+                        return Err(Error::invalid_tag::<Self>(input, tag));
+                    };
+
+                    let mut fields = Vec::with_capacity(variant.fields.len());
+
+                    for (index, def) in variant.fields.iter().enumerate() {
+                        let config = field_config(config, def.endian, def.width);
+                        let (value, rest) =
+                            Self::decode(slice, config, &def.schema, schemas, depth + 1)
+                                .map_err(|error| error.with_index(index))?;
+                        slice = rest;
+                        fields.push((def.name.clone(), value));
+                    }
+
+                    Self::Variant {
+                        tag,
+                        name: variant.name.clone(),
+                        fields,
+                    }
+                }
+                None => return Err(Error::UnknownSchema(schema.to_string())),
+            },
+            Schema::Opaque(_) => return Err(Error::UnknownSchema(schema.to_string())),
+        };
+
+        Ok((value, slice))
+    }
+
+    /// Encodes the value as described by `schema`. A value decoded with a schema encodes to the
+    /// exact same bytes with that schema.
+    pub fn put_bytes(
+        &self,
+        bytes: &mut BytesMut,
+        config: Config,
+        schema: &Schema,
+        schemas: &Schemas,
+    ) -> Result<(), Error> {
+        let mismatch = || Error::SchemaMismatch(schema.to_string());
+
+        match (schema, self) {
+            (Schema::Unit, Self::Unit) => {}
+            (Schema::U8, Self::U8(value)) => value.put_bytes(bytes, config)?,
+            (Schema::U16, Self::U16(value)) => value.put_bytes(bytes, config)?,
+            (Schema::U32, Self::U32(value)) => value.put_bytes(bytes, config)?,
+            (Schema::U64, Self::U64(value)) => value.put_bytes(bytes, config)?,
+            (Schema::U128, Self::U128(value)) => value.put_bytes(bytes, config)?,
+            (Schema::Usize, Self::Usize(value)) => value.put_bytes(bytes, config)?,
+            (Schema::I8, Self::I8(value)) => value.put_bytes(bytes, config)?,
+            (Schema::I16, Self::I16(value)) => value.put_bytes(bytes, config)?,
+            (Schema::I32, Self::I32(value)) => value.put_bytes(bytes, config)?,
+            (Schema::I64, Self::I64(value)) => value.put_bytes(bytes, config)?,
+            (Schema::I128, Self::I128(value)) => value.put_bytes(bytes, config)?,
+            (Schema::Isize, Self::Isize(value)) => value.put_bytes(bytes, config)?,
+            (Schema::F32, Self::F32(value)) => value.put_bytes(bytes, config)?,
+            (Schema::F64, Self::F64(value)) => value.put_bytes(bytes, config)?,
+            (Schema::Bool, Self::Bool(value)) => value.put_bytes(bytes, config)?,
+            (Schema::Char, Self::Char(value)) => value.put_bytes(bytes, config)?,
+            (Schema::Str, Self::Str(value)) => value.put_bytes(bytes, config)?,
+            (Schema::Bytes, Self::Bytes(value)) => {
+                value.len().put_bytes(bytes, config)?;
+                bytes.put_slice(value);
+            }
+            (Schema::Seq(schema), Self::Seq(values)) => {
+                values.len().put_bytes(bytes, config)?;
+
+                for value in values {
+                    value.put_bytes(bytes, config, schema, schemas)?;
+                }
+            }
+            (Schema::Array(schema, n), Self::Seq(values)) => {
+                if values.len() != *n {
+                    return Err(mismatch());
+                }
+
+                for value in values {
+                    value.put_bytes(bytes, config, schema, schemas)?;
+                }
+            }
+            (Schema::Packed(schema), Self::Seq(values)) => {
+                let mut subbytes = BytesMut::new();
+
+                for value in values {
+                    value.put_bytes(&mut subbytes, config, schema, schemas)?;
+                }
+
+                subbytes.len().put_bytes(bytes, config)?;
+                bytes.put_slice(&subbytes[..]);
+            }
+            (Schema::Option(_), Self::Option(None)) => bytes.put_u8(0),
+            (Schema::Option(schema), Self::Option(Some(value))) => {
+                bytes.put_u8(1);
+                value.put_bytes(bytes, config, schema, schemas)?;
+            }
+            (Schema::Ref(_), Self::Ref(offset)) => offset.put_bytes(bytes, config)?,
+            (Schema::FarRef(_), Self::FarRef { file, offset }) => {
+                file.put_bytes(bytes, config)?;
+                offset.put_bytes(bytes, config)?;
+            }
+            (Schema::Uuid, Self::Uuid(value)) => {
+                if is_big_endian(config) {
+                    bytes.put_slice(value);
+                } else {
+                    bytes.put_slice(&swap_uuid(*value));
+                }
+            }
+            (Schema::Decimal, Self::Decimal { mantissa, scale }) => {
+                mantissa.put_bytes(bytes, config)?;
+                scale.put_bytes(bytes, config)?;
+            }
+            (Schema::FixedDecimal(expected), Self::Decimal { mantissa, scale })
+                if scale == expected =>
+            {
+                mantissa.put_bytes(bytes, config)?;
+            }
+            (Schema::Timestamp, Self::Timestamp(value)) => value.put_bytes(bytes, config)?,
+            (Schema::Named(name), Self::Struct(fields)) => {
+                let Some(TypeKind::Struct(defs)) = schemas.get(name) else {
+                    return Err(mismatch());
+                };
+
+                if fields.len() != defs.len() {
+                    return Err(mismatch());
+                }
+
+                for (def, (_, value)) in defs.iter().zip(fields) {
+                    let config = field_config(config, def.endian, def.width);
+                    value.put_bytes(bytes, config, &def.schema, schemas)?;
+                }
+            }
+            (Schema::Named(name), Self::Versioned { version, fields }) => {
+                let Some(TypeKind::Versioned {
+                    version: latest,
+                    fields: defs,
+                }) = schemas.get(name)
+                else {
+                    return Err(mismatch());
+                };
+
+                if version > latest {
+                    return Err(mismatch());
+                }
+
+                let defs = defs
+                    .iter()
+                    .filter(|def| def.since.is_none_or(|since| since <= *version))
+                    .collect::<Vec<_>>();
+
+                if fields.len() != defs.len() {
+                    return Err(mismatch());
+                }
+
+                version.put_bytes(bytes, config)?;
+
+                for (def, (_, value)) in defs.into_iter().zip(fields) {
+                    let config = field_config(config, def.endian, def.width);
+                    value.put_bytes(bytes, config, &def.schema, schemas)?;
+                }
+            }
+            (Schema::Named(name), Self::Variant { tag, fields, .. }) => {
+                let Some(TypeKind::Enum(variants)) = schemas.get(name) else {
+                    return Err(mismatch());
+                };
+
+                let Some(variant) = variants.get(*tag) else {
+                    return Err(mismatch());
+                };
+
+                if fields.len() != variant.fields.len() {
+                    return Err(mismatch());
+                }
+
+                tag.put_bytes(bytes, config)?;
+
+                for (def, (_, value)) in variant.fields.iter().zip(fields) {
+                    let config = field_config(config, def.endian, def.width);
+                    value.put_bytes(bytes, config, &def.schema, schemas)?;
+                }
+            }
+            _ => return Err(mismatch()),
+        }
+
+        Ok(())
+    }
+
+    /// Returns the value of the field with the given name, if this is a struct or an enum
+    /// variant.
+    pub fn get(&self, name: &str) -> Option<&Value<'a>> {
+        let fields = match self {
+            Self::Struct(fields)
+            | Self::Versioned { fields, .. }
+            | Self::Variant { fields, .. } => fields,
+            _ => return None,
+        };
+
+        fields
+            .iter()
+            .find(|(other, _)| other == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Copies the borrowed strings and bytes, such that the value no longer borrows from the
+    /// file.
+    pub fn into_owned(self) -> Value<'static> {
+        fn fields(fields: Vec<(Cow<str>, Value)>) -> Vec<(Cow<'static, str>, Value<'static>)> {
+            fields
+                .into_iter()
+                .map(|(name, value)| (Cow::Owned(name.into_owned()), value.into_owned()))
+                .collect()
+        }
+
+        match self {
+            Self::Unit => Value::Unit,
+            Self::U8(value) => Value::U8(value),
+            Self::U16(value) => Value::U16(value),
+            Self::U32(value) => Value::U32(value),
+            Self::U64(value) => Value::U64(value),
+            Self::U128(value) => Value::U128(value),
+            Self::Usize(value) => Value::Usize(value),
+            Self::I8(value) => Value::I8(value),
+            Self::I16(value) => Value::I16(value),
+            Self::I32(value) => Value::I32(value),
+            Self::I64(value) => Value::I64(value),
+            Self::I128(value) => Value::I128(value),
+            Self::Isize(value) => Value::Isize(value),
+            Self::F32(value) => Value::F32(value),
+            Self::F64(value) => Value::F64(value),
+            Self::Bool(value) => Value::Bool(value),
+            Self::Char(value) => Value::Char(value),
+            Self::Str(value) => Value::Str(Cow::Owned(value.into_owned())),
+            Self::Bytes(value) => Value::Bytes(Cow::Owned(value.into_owned())),
+            Self::Seq(values) => Value::Seq(values.into_iter().map(Self::into_owned).collect()),
+            Self::Option(value) => Value::Option(value.map(|value| Box::new(value.into_owned()))),
+            Self::Ref(offset) => Value::Ref(offset),
+            Self::FarRef { file, offset } => Value::FarRef { file, offset },
+            Self::Uuid(value) => Value::Uuid(value),
+            Self::Decimal { mantissa, scale } => Value::Decimal { mantissa, scale },
+            Self::Timestamp(value) => Value::Timestamp(value),
+            Self::Struct(values) => Value::Struct(fields(values)),
+            Self::Versioned {
+                version,
+                fields: values,
+            } => Value::Versioned {
+                version,
+                fields: fields(values),
+            },
+            Self::Variant {
+                tag,
+                name,
+                fields: values,
+            } => Value::Variant {
+                tag,
+                name: Cow::Owned(name.into_owned()),
+                fields: fields(values),
+            },
+        }
+    }
+}
+
+fn fmt_fields(f: &mut fmt::Formatter<'_>, fields: &[(Cow<str>, Value)]) -> fmt::Result {
+    write!(f, "{{")?;
+
+    for (index, (name, value)) in fields.iter().enumerate() {
+        if index != 0 {
+            write!(f, ",")?;
+        }
+
+        write!(f, " {name}: {value}")?;
+    }
+
+    write!(f, " }}")
+}
+
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unit => write!(f, "()"),
+            Self::U8(value) => write!(f, "{value}"),
+            Self::U16(value) => write!(f, "{value}"),
+            Self::U32(value) => write!(f, "{value}"),
+            Self::U64(value) => write!(f, "{value}"),
+            Self::U128(value) => write!(f, "{value}"),
+            Self::Usize(value) => write!(f, "{value}"),
+            Self::I8(value) => write!(f, "{value}"),
+            Self::I16(value) => write!(f, "{value}"),
+            Self::I32(value) => write!(f, "{value}"),
+            Self::I64(value) => write!(f, "{value}"),
+            Self::I128(value) => write!(f, "{value}"),
+            Self::Isize(value) => write!(f, "{value}"),
+            Self::F32(value) => write!(f, "{value:?}"),
+            Self::F64(value) => write!(f, "{value:?}"),
+            Self::Bool(value) => write!(f, "{value}"),
+            Self::Char(value) => write!(f, "{value:?}"),
+            Self::Str(value) => write!(f, "{value:?}"),
+            Self::Bytes(value) => write!(f, "b\"{}\"", value.escape_ascii()),
+            Self::Seq(values) => {
+                write!(f, "[")?;
+
+                for (index, value) in values.iter().enumerate() {
+                    if index != 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{value}")?;
+                }
+
+                write!(f, "]")
+            }
+            Self::Option(None) => write!(f, "None"),
+            Self::Option(Some(value)) => write!(f, "Some({value})"),
+            Self::Ref(offset) => write!(f, "Ref({offset:#x})"),
+            Self::FarRef { file, offset } => write!(f, "FarRef({file}, {offset:#x})"),
+            Self::Uuid(value) => {
+                for (index, byte) in value.iter().enumerate() {
+                    if matches!(index, 4 | 6 | 8 | 10) {
+                        write!(f, "-")?;
+                    }
+
+                    write!(f, "{byte:02x}")?;
+                }
+
+                Ok(())
+            }
+            Self::Decimal { mantissa, scale } => {
+                let digits = mantissa.unsigned_abs().to_string();
+                let scale = *scale as usize;
+                let sign = if *mantissa < 0 { "-" } else { "" };
+
+                if scale == 0 {
+                    write!(f, "{sign}{digits}")
+                } else if digits.len() > scale {
+                    let (int, frac) = digits.split_at(digits.len() - scale);
+                    write!(f, "{sign}{int}.{frac}")
+                } else {
+                    write!(f, "{sign}0.{digits:0>scale$}")
+                }
+            }
+            Self::Timestamp(value) => write!(f, "Timestamp({value})"),
+            Self::Struct(fields) | Self::Versioned { fields, .. } => fmt_fields(f, fields),
+            Self::Variant { name, fields, .. } if fields.is_empty() => write!(f, "{name}"),
+            Self::Variant { name, fields, .. } => {
+                write!(f, "{name} ")?;
+                fmt_fields(f, fields)
+            }
+        }
+    }
+}
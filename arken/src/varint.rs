@@ -0,0 +1,90 @@
+//! LEB128 varints, as used for integers with variable width.
+//!
+//! Decoding is strict, such that every value has exactly one encoding: a varint that does not fit
+//! in its type is rejected as [`Error::Overflow`], and a varint that is longer than necessary, i.e.
+//! whose last byte is zero, as [`Error::NonCanonical`].
+
+use crate::Error;
+
+macro_rules! impl_decode {
+    ($name:ident, $ty:ty) => {
+        /// Decodes a varint of at most `BITS` bits, which takes at most `BYTES` bytes, from the
+        /// start of `slice`. Errors are reported for `T`.
+        #[inline]
+        pub(crate) fn $name<T: ?Sized, const BITS: u32, const BYTES: usize>(
+            slice: &[u8],
+        ) -> Result<($ty, &[u8]), Error> {
+            debug_assert_eq!(BYTES, BITS.div_ceil(7) as usize);
+
+            // Most varints, e.g. the lengths of short sequences, take a single byte.
+            if let Some(&byte) = slice.first()
+                && byte < 0x80
+            {
+                return Ok((byte as $ty, &slice[1..]));
+            }
+
+            // With enough input, scan a fixed number of bytes, such that the loop is unrolled
+            // without bounds checks.
+            if let Some(bytes) = slice.first_chunk::<BYTES>() {
+                let mut value: $ty = 0;
+
+                for (index, &byte) in bytes.iter().enumerate() {
+                    value |= ((byte & 0x7f) as $ty) << (7 * index);
+
+                    if byte & 0x80 == 0 {
+                        // The first byte has the continuation bit set, as single bytes have been
+                        // handled above, so a trailing zero byte only adds padding.
+                        if byte == 0
+                            || (index == BYTES - 1
+                                && (byte as u32) >> (BITS - 7 * index as u32) != 0)
+                        {
+                            return Err(invalid::<T, BYTES>(slice));
+                        }
+
+                        return Ok((value, &slice[index + 1..]));
+                    }
+                }
+
+                return Err(invalid::<T, BYTES>(slice));
+            }
+
+            // Otherwise, the varint ends near the end of the input.
+            let mut value: $ty = 0;
+
+            for (index, &byte) in slice.iter().take(BYTES).enumerate() {
+                value |= ((byte & 0x7f) as $ty) << (7 * index);
+
+                if byte & 0x80 != 0 {
+                    continue;
+                }
+
+                if byte == 0
+                    || (index == BYTES - 1 && (byte as u32) >> (BITS - 7 * index as u32) != 0)
+                {
+                    return Err(invalid::<T, BYTES>(slice));
+                }
+
+                return Ok((value, &slice[index + 1..]));
+            }
+
+            Err(invalid::<T, BYTES>(slice))
+        }
+    };
+}
+
+/// Determines why a varint failed to decode. This is kept out of line, such that the decoders
+/// stay small enough to be inlined.
+#[cold]
+#[inline(never)]
+fn invalid<T: ?Sized, const BYTES: usize>(slice: &[u8]) -> Error {
+    match slice.iter().take(BYTES).position(|&byte| byte & 0x80 == 0) {
+        // The last byte has bits set that do not fit in the type.
+        Some(index) if index == BYTES - 1 && slice[index] != 0 => Error::overflow::<T>(slice),
+        Some(_) => Error::non_canonical::<T>(slice),
+        None if slice.len() < BYTES => Error::incomplete::<T>(slice),
+        None => Error::overflow::<T>(slice),
+    }
+}
+
+impl_decode!(decode_u64, u64);
+impl_decode!(decode_u128, u128);
@@ -0,0 +1,268 @@
+//! Offline verification of whole files.
+//!
+//! A [`Verifier`] checks the header of a file and the checksums of the records with a given
+//! marker. From the records that are intact, it decodes the roots and walks every [`Ref`] that is
+//! reachable from them through [`Field::verify`], confirming that every offset lies within the
+//! file and that the value it points to decodes. For [`crate::MergeMap`]s it also checks that the
+//! keys of every node are sorted, and for [`crate::HashMap`]s that the count in the root matches
+//! the number of entries in the trie.
+//!
+//! ```no_run
+//! use arken::{MappedFile, verify::Verifier};
+//! use std::borrow::Cow;
+//!
+//! let file = MappedFile::open("map.bin")?;
+//! let mut verifier = Verifier::new(file.try_reader()?.bytes());
+//! verifier.check_merge_map::<Cow<str>, u64>(b"map");
+//!
+//! for problem in verifier.problems() {
+//!     println!("{problem}");
+//! }
+//! # Ok::<(), arken::Error>(())
+//! ```
+
+use crate::{Error, Field, HashRootRef, MergeRootRef, Reader, Record, RecordStatus, Ref};
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+/// A problem found by the [`Verifier`].
+#[derive(Debug, Error)]
+pub enum Problem {
+    #[error("invalid header: {0}")]
+    InvalidHeader(Error),
+    #[error("truncated record trailer at offset {offset}")]
+    TruncatedRecord { offset: usize },
+    #[error(
+        "checksum mismatch in record at offset {offset}: expected {expected:#010x}, got {actual:#010x}"
+    )]
+    ChecksumMismatch {
+        offset: usize,
+        expected: u32,
+        actual: u32,
+    },
+    #[error("failed to decode `{type_name}` at offset {offset}: {error}")]
+    Decode {
+        offset: usize,
+        type_name: &'static str,
+        error: Error,
+    },
+    /// The keys of a [`crate::MergeMap`] table are not sorted. For a table of blocks, `index` is
+    /// the index of the block whose first key is out of order.
+    #[error("keys of the node at offset {offset} are not sorted at index {index}")]
+    Unsorted { offset: usize, index: usize },
+    #[error("root at offset {offset} has a count of {expected}, but holds {actual} entries")]
+    CountMismatch {
+        offset: usize,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl Problem {
+    /// Returns the offset in the file at which the problem was found.
+    pub fn offset(&self) -> usize {
+        match self {
+            Self::InvalidHeader(_) => 0,
+            Self::TruncatedRecord { offset }
+            | Self::ChecksumMismatch { offset, .. }
+            | Self::Decode { offset, .. }
+            | Self::Unsorted { offset, .. }
+            | Self::CountMismatch { offset, .. } => *offset,
+        }
+    }
+}
+
+/// Checks a file for problems. See the [module documentation](self).
+#[derive(Debug)]
+pub struct Verifier<'a> {
+    reader: Option<Reader<'a>>,
+    markers: HashSet<Vec<u8>>,
+    visited: HashSet<(usize, &'static str)>,
+    sorted: HashSet<usize>,
+    counts: HashMap<usize, usize>,
+    problems: Vec<Problem>,
+}
+
+impl<'a> Verifier<'a> {
+    /// Checks the header of the file. If the header is invalid, there is nothing else to check,
+    /// and every other check does nothing.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        let mut problems = vec![];
+
+        let reader = Reader::try_from(bytes)
+            .map_err(|error| problems.push(Problem::InvalidHeader(error)))
+            .ok();
+
+        Self {
+            reader,
+            markers: HashSet::new(),
+            visited: HashSet::new(),
+            sorted: HashSet::new(),
+            counts: HashMap::new(),
+            problems,
+        }
+    }
+
+    /// Returns the reader for the file, or `None` if the header is invalid.
+    pub fn reader(&self) -> Option<&Reader<'a>> {
+        self.reader.as_ref()
+    }
+
+    /// Returns the problems found so far.
+    pub fn problems(&self) -> &[Problem] {
+        &self.problems
+    }
+
+    pub fn into_problems(self) -> Vec<Problem> {
+        self.problems
+    }
+
+    /// Returns `true` if no problems have been found so far.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    pub fn report(&mut self, problem: Problem) {
+        self.problems.push(problem);
+    }
+
+    /// Checks the checksum of every record with the given marker, and returns the records that
+    /// are intact, from the most recent to the oldest. Damaged records are only reported the first
+    /// time the marker is checked.
+    pub fn check_records(&mut self, marker: &[u8]) -> Vec<Record> {
+        let Some(reader) = self.reader.as_ref() else {
+            return vec![];
+        };
+
+        let records = reader.records(marker);
+        let report = self.markers.insert(marker.to_vec());
+        let mut intact = vec![];
+
+        for record in records {
+            match record.status {
+                RecordStatus::Ok => intact.push(record),
+                _ if !report => {}
+                RecordStatus::ChecksumMismatch { expected, actual } => {
+                    self.problems.push(Problem::ChecksumMismatch {
+                        offset: record.offset,
+                        expected,
+                        actual,
+                    });
+                }
+                RecordStatus::Truncated => {
+                    self.problems.push(Problem::TruncatedRecord {
+                        offset: record.marker_offset,
+                    });
+                }
+            }
+        }
+
+        intact
+    }
+
+    /// Decodes every intact record with the given marker as a `T`, and walks the values that are
+    /// reachable from it. Returns the decoded roots along with the offsets of their records.
+    pub fn check_root<T: Field<'a>>(&mut self, marker: &[u8]) -> Vec<(usize, T)> {
+        let Some(reader) = self.reader.clone() else {
+            return vec![];
+        };
+
+        let mut roots = vec![];
+
+        for record in self.check_records(marker) {
+            let slice = &reader.bytes()[record.offset..record.marker_offset];
+
+            match T::from_slice(slice, reader.config()) {
+                Ok((root, _)) => {
+                    root.verify(self);
+                    roots.push((record.offset, root));
+                }
+                Err(error) => self.problems.push(Problem::Decode {
+                    offset: record.offset,
+                    type_name: std::any::type_name::<T>(),
+                    error: error.rebase(record.marker_offset),
+                }),
+            }
+        }
+
+        roots
+    }
+
+    /// Checks the [`crate::MergeMap`]s committed with the given marker, including the keys of
+    /// every node being sorted. This also works for a [`crate::TrigramMap`].
+    pub fn check_merge_map<K, V>(&mut self, marker: &[u8])
+    where
+        K: 'a + Clone + Field<'a> + Ord,
+        V: 'a + Clone + Field<'a>,
+    {
+        for (_, reference) in self.check_root::<MergeRootRef<'a, K, V>>(marker) {
+            let Some(root) = self.read(&reference) else {
+                continue;
+            };
+
+            root.verify_sorted(self);
+        }
+    }
+
+    /// Checks the [`crate::HashMap`]s committed with the given marker, including the count in
+    /// every root matching the number of entries in its trie.
+    pub fn check_hash_map<K, V>(&mut self, marker: &[u8])
+    where
+        K: 'a + Clone + Field<'a>,
+        V: 'a + Clone + Field<'a>,
+    {
+        for (_, reference) in self.check_root::<HashRootRef<'a, K, V>>(marker) {
+            let Some(root) = self.read(&reference) else {
+                continue;
+            };
+
+            root.verify_count(reference.offset(), self);
+        }
+    }
+
+    /// Reads the value that `reference` points to, unless it has been visited before. Problems
+    /// with reading the value are reported. This is used by [`Field::verify`] to walk every
+    /// reachable [`Ref`] once.
+    pub fn visit<T: Field<'a>>(&mut self, reference: &Ref<'a, T>) -> Option<T> {
+        let key = (reference.offset(), std::any::type_name::<T>());
+
+        if !self.visited.insert(key) {
+            return None;
+        }
+
+        self.read(reference)
+    }
+
+    /// Reads the value that `reference` points to, and reports a problem if that fails.
+    pub(crate) fn read<T: Field<'a>>(&mut self, reference: &Ref<'a, T>) -> Option<T> {
+        let reader = self.reader.as_ref()?;
+
+        match reader.read(reference) {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.problems.push(Problem::Decode {
+                    offset: reference.offset(),
+                    type_name: std::any::type_name::<T>(),
+                    error,
+                });
+
+                None
+            }
+        }
+    }
+
+    /// Marks the [`crate::MergeMap`] node at `offset` as checked for sortedness, and returns
+    /// `false` if it had been checked before.
+    pub(crate) fn mark_sorted(&mut self, offset: usize) -> bool {
+        self.sorted.insert(offset)
+    }
+
+    /// Returns the cached number of entries below the [`crate::HashMap`] node at `offset`.
+    pub(crate) fn count(&self, offset: usize) -> Option<usize> {
+        self.counts.get(&offset).copied()
+    }
+
+    pub(crate) fn set_count(&mut self, offset: usize, count: usize) {
+        self.counts.insert(offset, count);
+    }
+}
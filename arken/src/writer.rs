@@ -1,9 +1,16 @@
-use crate::{Config, Error, Field, Reader, Ref};
+#[cfg(feature = "encryption")]
+use crate::{Cipher, Key, encryption::Encryption};
+use crate::{
+    Config, Error, Field, Reader, Ref,
+    checksum::MAX_HEADER_SIZE,
+    schema::{SCHEMA_MARKER, SchemaRecord, Schemas},
+};
 use bytes::{BufMut as _, BytesMut};
 use mmap_rs::MmapOptions;
 use std::{
+    collections::HashSet,
     fs::{File, OpenOptions},
-    io::{Seek, SeekFrom, Write},
+    io::{Read as _, Seek, SeekFrom, Write},
     marker::PhantomData,
     path::Path,
 };
@@ -13,6 +20,10 @@ use tempfile::NamedTempFile;
 pub struct Writer<W: Seek + Write> {
     file: W,
     config: Config,
+    /// The markers and types for which a schema has been written.
+    described: HashSet<(Vec<u8>, &'static str)>,
+    #[cfg(feature = "encryption")]
+    encryption: Option<Encryption>,
 }
 
 impl Writer<NamedTempFile> {
@@ -23,18 +34,51 @@ impl Writer<NamedTempFile> {
         config.put_bytes(&mut bytes, Default::default())?;
         file.write_all(&bytes[..])?;
 
-        Ok(Self { file, config })
+        Ok(Self {
+            file,
+            config,
+            described: HashSet::new(),
+            #[cfg(feature = "encryption")]
+            encryption: None,
+        })
     }
 
     pub fn persist<P: AsRef<Path>>(self, new_path: P) -> Result<Writer<File>, Error> {
         let file = self.file.persist(new_path)?;
-        let config = self.config;
 
-        Ok(Writer { file, config })
+        Ok(Writer {
+            file,
+            config: self.config,
+            described: self.described,
+            #[cfg(feature = "encryption")]
+            encryption: self.encryption,
+        })
     }
 }
 
 impl Writer<File> {
+    /// Creates a new file at the given path and writes the header. Fails if the file already
+    /// exists.
+    pub fn create<P: AsRef<Path>>(path: P, config: Config) -> Result<Self, Error> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create_new(true)
+            .open(path)?;
+
+        let mut bytes = BytesMut::with_capacity(4);
+        config.put_bytes(&mut bytes, Default::default())?;
+        file.write_all(&bytes[..])?;
+
+        Ok(Self {
+            file,
+            config,
+            described: HashSet::new(),
+            #[cfg(feature = "encryption")]
+            encryption: None,
+        })
+    }
+
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
         let file = OpenOptions::new().read(true).append(true).open(path)?;
 
@@ -45,7 +89,72 @@ impl Writer<File> {
         };
         let (config, _) = Config::from_slice(&map[..], Default::default())?;
 
-        Ok(Self { file, config })
+        Ok(Self {
+            file,
+            config,
+            described: HashSet::new(),
+            #[cfg(feature = "encryption")]
+            encryption: None,
+        })
+    }
+
+    /// Creates a new file at the given path whose records are encrypted with `cipher`, using a
+    /// key derived from `key`. Fails if the file already exists. See
+    /// [`crate::MappedFile::open_encrypted`] for reading the file.
+    #[cfg(feature = "encryption")]
+    pub fn create_encrypted<P: AsRef<Path>>(
+        path: P,
+        config: Config,
+        cipher: Cipher,
+        key: &Key,
+    ) -> Result<Self, Error> {
+        let mut bytes = BytesMut::new();
+        let encryption = Encryption::create(config, cipher, key, &mut bytes)?;
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create_new(true)
+            .open(path)?;
+
+        file.write_all(&bytes[..])?;
+
+        Ok(Self {
+            file,
+            config,
+            described: HashSet::new(),
+            encryption: Some(encryption),
+        })
+    }
+
+    /// Opens an encrypted file to append to it. A record at the end of the file that was only
+    /// partly written is truncated, such that the records appended after it can be read.
+    ///
+    /// Fails with [`Error::Unauthenticated`] if the key is wrong or if a record has been tampered
+    /// with, in which case the file is left as is.
+    #[cfg(feature = "encryption")]
+    pub fn open_encrypted<P: AsRef<Path>>(path: P, key: &Key) -> Result<Self, Error> {
+        let file = OpenOptions::new().read(true).append(true).open(path)?;
+        let size = file.metadata()?.len() as usize;
+
+        if size == 0 {
+            return Err(Error::InvalidHeader);
+        }
+
+        let map = unsafe { MmapOptions::new(size)?.with_file(&file, 0).map()? };
+        let (config, encryption, start) = Encryption::open(&map[..], key)?;
+        let end = encryption.authenticate(&map[..], start)?;
+
+        if end < size {
+            file.set_len(end as u64)?;
+        }
+
+        Ok(Self {
+            file,
+            config,
+            described: HashSet::new(),
+            encryption: Some(encryption),
+        })
     }
 }
 
@@ -54,35 +163,74 @@ impl<W: Seek + Write> Writer<W> {
         self.config
     }
 
+    /// Writes a record at the end of the file, padding the file with zeroes such that the record
+    /// starts at a multiple of `align`, and returns the offset of the record. The record is
+    /// preceded by its checksum if the file checksums every record. Records of an encrypted file
+    /// are sealed into a frame instead.
+    fn write_record(&mut self, align: usize, record: &[u8]) -> Result<usize, Error> {
+        if !align.is_power_of_two() {
+            return Err(Error::InvalidAlignment(align));
+        }
+
+        let mut header = [0; MAX_HEADER_SIZE];
+        let header_size = self.config.checksum().put_header(record, &mut header)?;
+        let header = &header[..header_size];
+
+        let end = self.file.seek(SeekFrom::End(0))? as usize;
+
+        #[cfg(feature = "encryption")]
+        if let Some(encryption) = &self.encryption {
+            let (offset, frame) = encryption.seal(end, align, header, record)?;
+            self.file.write_all(&frame)?;
+
+            return Ok(offset);
+        }
+
+        let offset = (end + header_size).next_multiple_of(align);
+        let padding = offset - header_size - end;
+
+        std::io::copy(&mut std::io::repeat(0).take(padding as u64), &mut self.file)?;
+        self.file.write_all(header)?;
+        self.file.write_all(record)?;
+
+        Ok(offset)
+    }
+
+    /// Appends `data` at the end of the file, aligned to [`Field::ALIGN`].
     pub fn append<'a, T: Field<'a>>(
         &mut self,
         bytes: &mut BytesMut,
         data: &T,
     ) -> Result<Ref<'a, T>, Error> {
-        let reference = Ref {
-            offset: self.file.seek(SeekFrom::End(0))? as usize,
-            _marker: &PhantomData,
-        };
+        self.append_aligned(bytes, T::ALIGN, data)
+    }
 
+    /// Appends `data` at the end of the file, padding the file with zeroes such that the record
+    /// starts at a multiple of `align` bytes, or of [`Field::ALIGN`] if that is larger. Together
+    /// with [`Reader::read_aligned`], this allows reading fixed-width data in place.
+    pub fn append_aligned<'a, T: Field<'a>>(
+        &mut self,
+        bytes: &mut BytesMut,
+        align: usize,
+        data: &T,
+    ) -> Result<Ref<'a, T>, Error> {
         bytes.clear();
         data.put_bytes(bytes, self.config)?;
 
-        self.file.write_all(&bytes[..])?;
-
-        Ok(reference)
+        Ok(Ref {
+            offset: self.write_record(align.max(T::ALIGN), &bytes[..])?,
+            _marker: &PhantomData,
+        })
     }
 
+    /// Appends `data` followed by `marker` and a trailer with its size and checksum, such that it
+    /// can be found with [`Reader::find`]. The data is aligned to [`Field::ALIGN`].
     pub fn append_with_marker<'a, T: Field<'a>>(
         &mut self,
         bytes: &mut BytesMut,
         marker: &'a [u8],
         data: &T,
     ) -> Result<Ref<'a, T>, Error> {
-        let reference = Ref {
-            offset: self.file.seek(SeekFrom::End(0))? as usize,
-            _marker: &PhantomData,
-        };
-
         bytes.clear();
         data.put_bytes(bytes, self.config)?;
 
@@ -93,11 +241,15 @@ impl<W: Seek + Write> Writer<W> {
         size.put_bytes(bytes, self.config)?;
         checksum.put_bytes(bytes, self.config)?;
 
-        self.file.write_all(&bytes[..])?;
-
-        Ok(reference)
+        Ok(Ref {
+            offset: self.write_record(T::ALIGN, &bytes[..])?,
+            _marker: &PhantomData,
+        })
     }
 
+    /// Copies the values that `data` refers to from the file of `reader` into this file, see
+    /// [`Field::migrate`], and then appends `data` as with [`Writer::append_with_marker`]. Returns
+    /// the reference to `data`, which is written after the values it refers to.
     pub fn migrate_with_marker<'a, T: Field<'a>>(
         &mut self,
         bytes: &mut BytesMut,
@@ -105,18 +257,34 @@ impl<W: Seek + Write> Writer<W> {
         reader: &Reader<'a>,
         mut data: T,
     ) -> Result<Ref<'a, T>, Error> {
-        let reference = Ref {
-            offset: self.file.seek(SeekFrom::End(0))? as usize,
-            _marker: &PhantomData,
-        };
-
+        // Migrating appends the values that `data` refers to, so `data` itself goes after them.
         bytes.clear();
         data.migrate(bytes, self, reader)?;
-        self.file.write_all(&bytes[..])?;
 
-        self.append_with_marker(bytes, marker, &data)?;
+        self.append_with_marker(bytes, marker, &data)
+    }
 
-        Ok(reference)
+    /// Writes the schema of `T` into the file, describing the records written with `marker`, such
+    /// that they can be decoded without `T`. The schema is written once per marker and type for
+    /// the lifetime of this writer. See [`crate::schema`].
+    pub fn append_schema<'a, T: Field<'a>>(
+        &mut self,
+        bytes: &mut BytesMut,
+        marker: &[u8],
+    ) -> Result<(), Error> {
+        if !self
+            .described
+            .insert((marker.to_vec(), std::any::type_name::<T>()))
+        {
+            return Ok(());
+        }
+
+        let (schema, schemas) = Schemas::describe::<T>();
+        let record = SchemaRecord::new(marker, schema, schemas);
+
+        self.append_with_marker(bytes, SCHEMA_MARKER, &record)?;
+
+        Ok(())
     }
 
     pub fn flush(&mut self) -> Result<(), Error> {
@@ -0,0 +1,264 @@
+use arken::{BlockFormat, Config, Error, MappedFile, MergeMap, MergeRootRef, Writer};
+use bytes::BytesMut;
+use std::{collections::BTreeMap, path::Path};
+
+const MARKER: &[u8] = b"map";
+
+type Map<'a> = MergeMap<'a, String, String>;
+
+fn key(n: usize) -> String {
+    format!("key-{n:06}")
+}
+
+fn format() -> BlockFormat {
+    *BlockFormat::default()
+        .with_block_size(256)
+        .with_restart_interval(4)
+}
+
+/// Opens the latest map in the file at `path`, with block tables enabled if `blocks` is set.
+fn open<'a>(file: &'a MappedFile, blocks: bool) -> Map<'a> {
+    let reader = file.reader();
+    let root = reader.find::<MergeRootRef<String, String>>(MARKER).next();
+    let mut map = Map::open(reader, root);
+
+    if blocks {
+        map.with_blocks(format());
+    }
+
+    map
+}
+
+/// Applies `update` to the latest map in the file and to `model`, and commits the map as a new
+/// table, which is a block table if `blocks` is set.
+fn commit(
+    path: &Path,
+    blocks: bool,
+    model: &mut BTreeMap<String, String>,
+    update: impl Fn(&mut Map<'_>, &mut BTreeMap<String, String>),
+) -> Result<(), Error> {
+    let mut writer = Writer::open(path)?;
+    let mut bytes = BytesMut::new();
+    let file = MappedFile::open(path)?;
+    let mut map = open(&file, blocks);
+
+    update(&mut map, model);
+
+    if let Some(root) = map.commit(&mut bytes, &mut writer)? {
+        writer.append_with_marker(&mut bytes, MARKER, &root)?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Writes three block tables that overwrite and remove each other's keys, each large enough not
+/// to be merged into the next, and returns the expected contents of the map.
+fn write_tables(path: &Path) -> Result<BTreeMap<String, String>, Error> {
+    Writer::create(path, Config::default())?.flush()?;
+    let mut model = BTreeMap::new();
+
+    commit(path, true, &mut model, |map, model| {
+        for n in 0..5000 {
+            map.insert(key(n), format!("first-{n}"));
+            model.insert(key(n), format!("first-{n}"));
+        }
+    })?;
+
+    commit(path, true, &mut model, |map, model| {
+        for n in (0..10000).step_by(2) {
+            map.insert(key(n), format!("second-{n}"));
+            model.insert(key(n), format!("second-{n}"));
+        }
+    })?;
+
+    commit(path, true, &mut model, |map, model| {
+        for n in (0..10000).step_by(3) {
+            map.remove(&key(n));
+            model.remove(&key(n));
+        }
+
+        for n in (1..10000).step_by(7) {
+            map.insert(key(n), format!("third-{n}"));
+            model.insert(key(n), format!("third-{n}"));
+        }
+    })?;
+
+    Ok(model)
+}
+
+#[test]
+fn block_tables_round_trip() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("map.ark");
+    let model = write_tables(&path)?;
+
+    let file = MappedFile::open(&path)?;
+    let map = open(&file, true);
+    assert_eq!(map.node_sizes().len(), 3);
+    assert_eq!(map.len(), model.len());
+
+    for n in 0..10001 {
+        let key = key(n);
+        assert_eq!(
+            map.get(&key)?.map(|value| value.into_owned()),
+            model.get(&key).cloned(),
+            "{key}"
+        );
+    }
+
+    let entries = map
+        .iter()
+        .map(|entry| entry.map(|(key, value)| (key.into_owned(), value.into_owned())))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    assert_eq!(entries, model.into_iter().collect::<Vec<_>>());
+
+    Ok(())
+}
+
+#[test]
+fn block_tables_fail_to_read_without_blocks() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("map.ark");
+    write_tables(&path)?;
+
+    let file = MappedFile::open(&path)?;
+    let map = open(&file, false);
+
+    assert!(matches!(map.get(&key(1)), Err(Error::BlocksRequired(_))));
+    assert!(matches!(
+        map.contains_key(&key(1)),
+        Err(Error::BlocksRequired(_))
+    ));
+    assert!(
+        map.iter()
+            .any(|entry| matches!(entry, Err(Error::BlocksRequired(_))))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn nodes_written_without_blocks_are_read_with_blocks() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("map.ark");
+    let mut model = write_tables(&path)?;
+
+    // Commit a node on top of the block tables, with a map that cannot read them. Removing a key
+    // it cannot look up must still mask it.
+    let mut writer = Writer::open(&path)?;
+    let mut bytes = BytesMut::new();
+
+    {
+        let file = MappedFile::open(&path)?;
+        let mut map = open(&file, false);
+
+        map.insert(key(5), "fourth".into());
+        map.remove(&key(7));
+        model.insert(key(5), "fourth".into());
+        model.remove(&key(7));
+
+        let root = map.commit(&mut bytes, &mut writer)?.unwrap();
+        writer.append_with_marker(&mut bytes, MARKER, &root)?;
+        writer.flush()?;
+    }
+
+    let file = MappedFile::open(&path)?;
+    let map = open(&file, true);
+    assert_eq!(map.node_sizes().len(), 4);
+
+    assert_eq!(
+        map.get(&key(5))?.as_deref().map(String::as_str),
+        Some("fourth")
+    );
+    assert_eq!(map.get(&key(7))?, None);
+
+    let keys = map
+        .keys()
+        .map(|key| key.map(|key| key.into_owned()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    assert_eq!(keys, model.into_keys().collect::<Vec<_>>());
+
+    Ok(())
+}
+
+#[test]
+fn small_tables_are_merged_in_order() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("map.ark");
+    Writer::create(&path, Config::default())?.flush()?;
+    let mut model = BTreeMap::new();
+
+    // A map without blocks cannot merge the block table into its own, so this leaves two small
+    // tables, which the last map merges. Every round only touches some of the keys, such that the
+    // older values of the others must not win.
+    for (round, blocks) in [(1, true), (2, false), (3, true)] {
+        commit(&path, blocks, &mut model, |map, model| {
+            for n in (0..100).filter(|n| n % round == 0) {
+                if round == 3 {
+                    map.remove(&key(n));
+                    model.remove(&key(n));
+                } else {
+                    map.insert(key(n), format!("{round}-{n}"));
+                    model.insert(key(n), format!("{round}-{n}"));
+                }
+            }
+        })?;
+    }
+
+    let file = MappedFile::open(&path)?;
+    let map = open(&file, true);
+    assert_eq!(map.node_sizes().len(), 1);
+    assert_eq!(map.len(), model.len());
+
+    let entries = map
+        .iter()
+        .map(|entry| entry.map(|(key, value)| (key.into_owned(), value.into_owned())))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    assert_eq!(entries, model.into_iter().collect::<Vec<_>>());
+
+    Ok(())
+}
+
+#[test]
+fn corrupt_first_keys_fail_lookups() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("map.ark");
+    Writer::create(&path, Config::default())?.flush()?;
+
+    commit(&path, true, &mut BTreeMap::new(), |map, _| {
+        for n in 0..200 {
+            map.insert(key(n), format!("value-{n}"));
+        }
+    })?;
+
+    // The first keys of the blocks are stored in full as a length followed by the encoded key,
+    // unlike the keys within the blocks. Replace the NUL that terminates them.
+    let mut data = std::fs::read(&path)?;
+    let mut corrupted = 0;
+
+    for start in 0..data.len() - 12 {
+        if data[start] == 11 && data[start + 1..].starts_with(b"key-") && data[start + 11] == 0 {
+            data[start + 11] = b'X';
+            corrupted += 1;
+        }
+    }
+
+    assert!(corrupted > 1);
+    std::fs::write(&path, data)?;
+
+    let file = MappedFile::open(&path)?;
+    let map = open(&file, true);
+
+    assert!(matches!(map.get(&key(100)), Err(Error::Incomplete(_))));
+    assert!(matches!(
+        map.contains_key(&key(100)),
+        Err(Error::Incomplete(_))
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use arken::{ByteStr, Config, Error, Field, MappedFile, Writer};
+use bytes::BytesMut;
+use std::borrow::Cow;
+
+const MARKER: &[u8] = b"blob";
+
+#[test]
+fn byte_strings_borrow_from_the_file() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("file.ark");
+    let data: Vec<u8> = (0..=255).collect();
+
+    let mut writer = Writer::create(&path, Config::default())?;
+    let mut bytes = BytesMut::new();
+    let reference = writer.append(&mut bytes, &ByteStr::from(&data[..]))?;
+    writer.append_with_marker(&mut bytes, MARKER, &ByteStr::from(vec![]))?;
+    writer.flush()?;
+
+    let file = MappedFile::open(&path)?;
+    let reader = file.try_reader()?;
+
+    let value = reader.read(&reference)?;
+    assert!(matches!(value.0, Cow::Borrowed(_)));
+    assert_eq!(&value[..], &data[..]);
+    assert_eq!(value.into_owned(), data);
+
+    let empty: ByteStr = reader.find(MARKER).next().expect("empty byte string");
+    assert!(empty.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn byte_strings_are_encoded_like_byte_slices() -> Result<(), Error> {
+    let data = b"\x00\x01 arbitrary \xff bytes".repeat(20);
+
+    let mut fixed = Config::default();
+    fixed.fixed_width();
+
+    for config in [Config::default(), fixed] {
+        let (mut a, mut b) = (BytesMut::new(), BytesMut::new());
+        ByteStr::from(&data[..]).put_bytes(&mut a, config)?;
+        Cow::Borrowed(&data[..]).put_bytes(&mut b, config)?;
+        assert_eq!(a, b);
+
+        let (value, rest) = ByteStr::from_slice(&b, config)?;
+        assert!(rest.is_empty());
+        assert_eq!(&value[..], &data[..]);
+
+        let (value, rest) = Cow::<[u8]>::from_slice(&a, config)?;
+        assert!(rest.is_empty());
+        assert_eq!(&value[..], &data[..]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn truncated_byte_strings_are_incomplete() -> Result<(), Error> {
+    let config = Config::default();
+    let mut bytes = BytesMut::new();
+    ByteStr::from(&b"truncated"[..]).put_bytes(&mut bytes, config)?;
+
+    for size in 0..bytes.len() {
+        assert!(matches!(
+            ByteStr::from_slice(&bytes[..size], config),
+            Err(Error::Incomplete(_))
+        ));
+    }
+
+    // A length that does not fit in the remaining bytes is not trusted.
+    let mut bytes = BytesMut::new();
+    usize::MAX.put_bytes(&mut bytes, config)?;
+    bytes.extend_from_slice(b"short");
+
+    assert!(matches!(
+        ByteStr::from_slice(&bytes, config),
+        Err(Error::Incomplete(_))
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use arken::{
+    Checksum, Config, Error, HashMap, HashRootRef, MappedFile, MergeMap, MergeRootRef, Writer,
+};
+use bytes::BytesMut;
+use std::path::Path;
+
+const MARKER: &[u8] = b"map";
+
+fn value(n: u64) -> String {
+    format!("value-{n:04}")
+}
+
+/// Flips a byte in the record that holds the value of `n`.
+fn corrupt(path: &Path, n: u64) -> Result<(), Error> {
+    let mut data = std::fs::read(path)?;
+    let needle = value(n).into_bytes();
+
+    let offset = data
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .unwrap();
+
+    data[offset + needle.len() - 1] ^= 0x01;
+    std::fs::write(path, data)?;
+
+    Ok(())
+}
+
+fn config(checksum: Checksum) -> Config {
+    *Config::default().with_checksum(checksum)
+}
+
+fn is_mismatch<T>(result: &Result<T, Error>) -> bool {
+    matches!(result, Err(Error::ChecksumMismatch { .. }))
+}
+
+#[test]
+fn merge_map_reports_checksum_mismatches() -> Result<(), Error> {
+    for checksum in [Checksum::Crc32c, Checksum::Xxh3] {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("map.ark");
+        let mut writer = Writer::create(&path, config(checksum))?;
+        let mut bytes = BytesMut::new();
+
+        {
+            let file = MappedFile::open(&path)?;
+            let mut map = MergeMap::<u64, String>::open(file.reader(), None);
+
+            for n in 0..100 {
+                map.insert(n, value(n));
+            }
+
+            let root = map.commit(&mut bytes, &mut writer)?.unwrap();
+            writer.append_with_marker(&mut bytes, MARKER, &root)?;
+            writer.flush()?;
+        }
+
+        let file = MappedFile::open(&path)?;
+        let reader = file.reader();
+        let root = reader.find::<MergeRootRef<u64, String>>(MARKER).next();
+        let map = MergeMap::<u64, String>::open(reader, root);
+        assert_eq!(map.get(&42)?.as_deref(), Some(&value(42)));
+        drop(map);
+        drop(file);
+
+        corrupt(&path, 42)?;
+
+        let file = MappedFile::open(&path)?;
+        let reader = file.reader();
+        let root = reader.find::<MergeRootRef<u64, String>>(MARKER).next();
+        let map = MergeMap::<u64, String>::open(reader, root);
+
+        assert!(is_mismatch(&map.get(&42)), "{checksum:?}");
+        assert!(is_mismatch(&map.contains_key(&42)), "{checksum:?}");
+        assert!(map.iter().any(|entry| is_mismatch(&entry)), "{checksum:?}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn hash_map_reports_checksum_mismatches() -> Result<(), Error> {
+    for checksum in [Checksum::Crc32c, Checksum::Xxh3] {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("map.ark");
+        let mut writer = Writer::create(&path, config(checksum))?;
+        let mut bytes = BytesMut::new();
+
+        {
+            let file = MappedFile::open(&path)?;
+            let mut map = HashMap::<u64, String>::open(file.reader(), None);
+
+            for n in 0..100 {
+                map.insert(n, value(n));
+            }
+
+            let root = map.commit(&mut bytes, &mut writer)?.unwrap();
+            writer.append_with_marker(&mut bytes, MARKER, &root)?;
+            writer.flush()?;
+        }
+
+        corrupt(&path, 42)?;
+
+        let file = MappedFile::open(&path)?;
+        let reader = file.reader();
+        let root = reader.find::<HashRootRef<u64, String>>(MARKER).next();
+        let map = HashMap::<u64, String>::open(reader, root);
+
+        assert!(is_mismatch(&map.get(&42)), "{checksum:?}");
+        assert_eq!(map.get(&7)?.as_deref(), Some(&value(7)), "{checksum:?}");
+
+        let entries: Vec<_> = map.iter().collect();
+        assert_eq!(entries.len(), 100, "{checksum:?}");
+        assert_eq!(
+            entries.iter().filter(|entry| is_mismatch(entry)).count(),
+            1,
+            "{checksum:?}"
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use arken::{Config, Error, HashMap, HashRootRef, MappedFile, MergeMap, MergeRootRef, Writer};
+use bytes::BytesMut;
+use std::borrow::Cow;
+
+const MARKER: &[u8] = b"map";
+
+fn entries() -> Vec<(u64, String)> {
+    (0..20).map(|n| (n, format!("value {n}"))).collect()
+}
+
+fn owned<T: Clone>(item: Result<Cow<'_, T>, Error>) -> Result<T, Error> {
+    item.map(Cow::into_owned)
+}
+
+#[test]
+fn merge_map_keys_and_values_follow_the_entries() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("map.ark");
+    let mut writer = Writer::create(&path, Config::default())?;
+    let mut bytes = BytesMut::new();
+
+    {
+        let file = MappedFile::open(&path)?;
+        let mut map = MergeMap::<u64, String>::open(file.reader(), None);
+
+        for (key, value) in entries() {
+            map.insert(key, value);
+        }
+
+        let root = map.commit(&mut bytes, &mut writer)?.expect("root");
+        writer.append_with_marker(&mut bytes, MARKER, &root)?;
+        writer.flush()?;
+    }
+
+    let file = MappedFile::open(&path)?;
+    let reader = file.reader();
+    let root = reader.find::<MergeRootRef<u64, String>>(MARKER).next();
+    let mut map = MergeMap::<u64, String>::open(reader, root);
+
+    // Entries in the memtable as well as in the committed tables.
+    map.insert(20, String::from("value 20"));
+    let mut entries = entries();
+    entries.push((20, String::from("value 20")));
+
+    let (keys, values): (Vec<u64>, Vec<String>) = entries.into_iter().unzip();
+    assert_eq!(map.keys().map(owned).collect::<Result<Vec<_>, _>>()?, keys);
+    assert_eq!(
+        map.values().map(owned).collect::<Result<Vec<_>, _>>()?,
+        values
+    );
+
+    Ok(())
+}
+
+#[test]
+fn hash_map_keys_and_values_follow_the_entries() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("map.ark");
+    let mut writer = Writer::create(&path, Config::default())?;
+    let mut bytes = BytesMut::new();
+
+    {
+        let file = MappedFile::open(&path)?;
+        let mut map = HashMap::<u64, String>::open(file.reader(), None);
+
+        for (key, value) in entries() {
+            map.insert(key, value);
+        }
+
+        let root = map.commit(&mut bytes, &mut writer)?.expect("root");
+        writer.append_with_marker(&mut bytes, MARKER, &root)?;
+        writer.flush()?;
+    }
+
+    let file = MappedFile::open(&path)?;
+    let reader = file.reader();
+    let root = reader.find::<HashRootRef<u64, String>>(MARKER).next();
+    let map = HashMap::<u64, String>::open(reader, root);
+
+    // The keys and values come in the same unspecified order as the entries.
+    let mut entries = Vec::new();
+
+    for entry in map.iter() {
+        let (key, value) = entry?;
+        entries.push((key.into_owned(), value.into_owned()));
+    }
+
+    let (keys, values): (Vec<u64>, Vec<String>) = entries.iter().cloned().unzip();
+    assert_eq!(map.keys().map(owned).collect::<Result<Vec<_>, _>>()?, keys);
+    assert_eq!(
+        map.values().map(owned).collect::<Result<Vec<_>, _>>()?,
+        values
+    );
+
+    entries.sort();
+    assert_eq!(entries, self::entries());
+
+    Ok(())
+}
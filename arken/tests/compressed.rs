@@ -0,0 +1,178 @@
+#![cfg(any(feature = "lz4", feature = "zstd"))]
+
+use arken::{Compressed, Compression, Config, Error, Field};
+use bytes::{BufMut as _, BytesMut};
+use std::borrow::Cow;
+
+fn compressions() -> Vec<Compression> {
+    vec![
+        #[cfg(feature = "lz4")]
+        Compression::Lz4,
+        #[cfg(feature = "zstd")]
+        Compression::Zstd(3),
+    ]
+}
+
+fn encode(value: &str, compression: Compression) -> Result<BytesMut, Error> {
+    let mut bytes = BytesMut::new();
+    Compressed::<str>::new(value)
+        .with_compression(compression)
+        .put_bytes(&mut bytes, Config::default())?;
+
+    Ok(bytes)
+}
+
+/// Splits an encoded compressed value into its tag, its decompressed size and the compressed data.
+fn split(bytes: &[u8]) -> Result<(u8, usize, &[u8]), Error> {
+    let config = Config::default();
+    let (tag, rest) = u8::from_slice(bytes, config)?;
+    let (size, rest) = usize::from_slice(rest, config)?;
+    let (len, rest) = usize::from_slice(rest, config)?;
+    assert_eq!(rest.len(), len);
+
+    Ok((tag, size, rest))
+}
+
+fn join(tag: u8, size: usize, data: &[u8]) -> Result<BytesMut, Error> {
+    let config = Config::default();
+    let mut bytes = BytesMut::new();
+    bytes.put_u8(tag);
+    size.put_bytes(&mut bytes, config)?;
+    data.len().put_bytes(&mut bytes, config)?;
+    bytes.put_slice(data);
+
+    Ok(bytes)
+}
+
+#[test]
+fn large_values_are_compressed() -> Result<(), Error> {
+    let text = "compressible text ".repeat(100);
+
+    for compression in compressions() {
+        let bytes = encode(&text, compression)?;
+        assert!(bytes.len() < text.len() / 4);
+
+        let (tag, size, _) = split(&bytes)?;
+        assert_ne!(tag, 0);
+        assert_eq!(size, text.len() + 1);
+
+        let (value, rest) = Compressed::<str>::from_slice(&bytes, Config::default())?;
+        assert!(rest.is_empty());
+        assert!(matches!(value.into_inner(), Cow::Owned(value) if value == text));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn small_and_incompressible_values_are_stored_as_is() -> Result<(), Error> {
+    // Bytes from an xorshift generator, which neither algorithm can make any smaller.
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let noise: Vec<u8> = (0..1024)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect();
+
+    for compression in compressions() {
+        let bytes = encode("short", compression)?;
+        assert_eq!(&bytes[..], b"\0short\0");
+
+        let (value, _) = Compressed::<str>::from_slice(&bytes, Config::default())?;
+        assert!(matches!(value.into_inner(), Cow::Borrowed("short")));
+
+        let mut bytes = BytesMut::new();
+        Compressed::<[u8]>::new(&noise[..])
+            .with_compression(compression)
+            .put_bytes(&mut bytes, Config::default())?;
+        assert_eq!(bytes[0], 0);
+
+        let (value, _) = Compressed::<[u8]>::from_slice(&bytes, Config::default())?;
+        assert_eq!(&value[..], &noise[..]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn corrupt_compressed_data_is_rejected() -> Result<(), Error> {
+    let text = "compressible text ".repeat(100);
+
+    for compression in compressions() {
+        let bytes = encode(&text, compression)?;
+        let (tag, size, data) = split(&bytes)?;
+
+        let mut damaged = data.to_vec();
+        damaged.truncate(data.len() / 2);
+        assert!(matches!(
+            Compressed::<str>::from_slice(&join(tag, size, &damaged)?, Config::default()),
+            Err(Error::Corrupt(_))
+        ));
+
+        // Data that decompresses to fewer or more bytes than its size.
+        for size in [size - 1, size + 1] {
+            assert!(matches!(
+                Compressed::<str>::from_slice(&join(tag, size, data)?, Config::default()),
+                Err(Error::Corrupt(_))
+            ));
+        }
+
+        // Compressed data that is cut short.
+        assert!(matches!(
+            Compressed::<str>::from_slice(&bytes[..bytes.len() - 1], Config::default()),
+            Err(Error::Incomplete(_))
+        ));
+    }
+
+    assert!(matches!(
+        Compressed::<str>::from_slice(&join(9, 4, b"data")?, Config::default()),
+        Err(Error::InvalidDiscriminant { value: 9, .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn oversized_values_are_rejected_without_allocating() -> Result<(), Error> {
+    let text = "compressible text ".repeat(100);
+
+    for compression in compressions() {
+        let bytes = encode(&text, compression)?;
+        let (tag, _, data) = split(&bytes)?;
+
+        assert!(matches!(
+            Compressed::<str>::from_slice(&join(tag, usize::MAX, data)?, Config::default()),
+            Err(Error::Corrupt(_))
+        ));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn errors_in_decompressed_data_are_reported_at_the_value() -> Result<(), Error> {
+    // The decompressed data starts with a length that is not valid UTF-8 when decoded as `str`.
+    let mut data = vec![b'a'; 511];
+    data.push(0);
+
+    for compression in compressions() {
+        let mut bytes = BytesMut::new();
+        Compressed::<[u8]>::new(&data[..])
+            .with_compression(compression)
+            .put_bytes(&mut bytes, Config::default())?;
+        assert_ne!(bytes[0], 0);
+
+        let error =
+            Compressed::<str>::from_slice(&bytes, Config::default()).expect_err("invalid UTF-8");
+        assert!(matches!(error, Error::InvalidUtf8 { .. }));
+        assert_eq!(
+            error.context().map(|context| context.offset),
+            Some(bytes.len())
+        );
+    }
+
+    Ok(())
+}
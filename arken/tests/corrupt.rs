@@ -0,0 +1,79 @@
+use arken::{Config, Error, Field};
+use bytes::BytesMut;
+use std::borrow::Cow;
+
+fn configs() -> [Config; 2] {
+    let mut fixed = Config::default();
+    fixed.fixed_width();
+
+    [Config::default(), fixed]
+}
+
+/// Encodes a sequence length without any values following it.
+fn length(n: usize, config: Config) -> Result<BytesMut, Error> {
+    let mut bytes = BytesMut::new();
+    n.put_bytes(&mut bytes, config)?;
+
+    Ok(bytes)
+}
+
+#[test]
+fn huge_sequences_of_empty_values_are_rejected() -> Result<(), Error> {
+    for config in configs() {
+        let bytes = length(usize::MAX, config)?;
+
+        assert!(matches!(
+            Vec::<()>::from_slice(&bytes, config),
+            Err(Error::Corrupt(_))
+        ));
+        assert!(matches!(
+            Cow::<[()]>::from_slice(&bytes, config),
+            Err(Error::Corrupt(_))
+        ));
+        assert!(matches!(
+            Vec::<Cow<[u8; 0]>>::from_slice(&bytes, config),
+            Err(Error::Corrupt(_))
+        ));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn huge_sequences_fail_without_allocating() -> Result<(), Error> {
+    for config in configs() {
+        let mut bytes = length(usize::MAX, config)?;
+        bytes.extend_from_slice(&[0; 64]);
+
+        assert!(matches!(
+            Vec::<(u64, u64, u64, u64)>::from_slice(&bytes, config),
+            Err(Error::Incomplete(_))
+        ));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn short_sequences_of_empty_values_round_trip() -> Result<(), Error> {
+    for config in configs() {
+        let mut bytes = BytesMut::new();
+        vec![(); 3].put_bytes(&mut bytes, config)?;
+        0u8.put_bytes(&mut bytes, config)?;
+
+        let (values, rest) = Vec::<()>::from_slice(&bytes, config)?;
+        assert_eq!(values, [(), (), ()]);
+        assert_eq!(rest, [0]);
+
+        let bytes = length(1 << 16, config)?;
+        assert_eq!(Vec::<()>::from_slice(&bytes, config)?.0.len(), 1 << 16);
+
+        let bytes = length((1 << 16) + 1, config)?;
+        assert!(matches!(
+            Vec::<()>::from_slice(&bytes, config),
+            Err(Error::Corrupt(_))
+        ));
+    }
+
+    Ok(())
+}
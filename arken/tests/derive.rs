@@ -0,0 +1,283 @@
+use arken::{Arken, Config, Endian, Error, Field};
+use bytes::BytesMut;
+use std::{borrow::Cow, fmt::Debug};
+
+#[derive(Arken, Debug, PartialEq)]
+struct Cached {
+    id: u64,
+    #[arken(skip)]
+    hits: u32,
+    name: String,
+    #[arken(skip_with = 1)]
+    generation: u8,
+}
+
+#[derive(Arken, Debug, PartialEq)]
+enum Shape {
+    Circle {
+        #[arken(skip)]
+        area: Option<u64>,
+        radius: u32,
+    },
+    Label(String, #[arken(skip)] usize),
+    Empty,
+}
+
+mod v1 {
+    use arken::Arken;
+    use std::borrow::Cow;
+
+    #[derive(Arken, Debug, PartialEq)]
+    #[arken(version = 1)]
+    pub struct Account<'a> {
+        pub name: Cow<'a, str>,
+    }
+}
+
+mod v2 {
+    use super::Status;
+    use arken::Arken;
+    use std::borrow::Cow;
+
+    #[derive(Arken, Debug, PartialEq)]
+    #[arken(version = 2)]
+    pub struct Account<'a> {
+        pub name: Cow<'a, str>,
+        #[arken(since = 2, default = Status::Active { since: -1 })]
+        pub status: Status,
+    }
+}
+
+mod v3 {
+    use super::{Status, default_groups};
+    use arken::Arken;
+    use std::borrow::Cow;
+
+    #[derive(Arken, Debug, PartialEq)]
+    #[arken(version = 3)]
+    pub struct Account<'a> {
+        pub name: Cow<'a, str>,
+        #[arken(since = 2, default = Status::Active { since: -1 })]
+        pub status: Status,
+        #[arken(since = 3, default = default_groups())]
+        pub groups: Vec<Cow<'a, str>>,
+        #[arken(since = 3)]
+        pub quota: Option<u64>,
+        #[arken(since = 3, default = Self::DEFAULT_LIMIT, size = "fixed")]
+        pub limit: u32,
+    }
+
+    impl Account<'_> {
+        pub const DEFAULT_LIMIT: u32 = 100;
+    }
+}
+
+#[derive(Arken, Debug, PartialEq)]
+enum Status {
+    Active { since: i64 },
+    Suspended(String),
+}
+
+fn default_groups() -> Vec<Cow<'static, str>> {
+    vec![Cow::Borrowed("users")]
+}
+
+fn configs() -> Vec<Config> {
+    let mut configs = vec![Config::default()];
+
+    for endian in [Endian::Big, Endian::Little] {
+        let mut config = Config::default();
+        config.fixed_width().with_endian(endian);
+        configs.push(config);
+    }
+
+    configs
+}
+
+fn encode<'a, T: Field<'a>>(value: &T) -> Result<BytesMut, Error> {
+    encode_with(value, Config::default())
+}
+
+fn encode_with<'a, T: Field<'a>>(value: &T, config: Config) -> Result<BytesMut, Error> {
+    let mut bytes = BytesMut::new();
+    value.put_bytes(&mut bytes, config)?;
+
+    Ok(bytes)
+}
+
+fn decode<'a, T: Field<'a> + Debug>(bytes: &'a [u8]) -> Result<T, Error> {
+    decode_with(bytes, Config::default())
+}
+
+fn decode_with<'a, T: Field<'a> + Debug>(bytes: &'a [u8], config: Config) -> Result<T, Error> {
+    let (value, rest) = T::from_slice(bytes, config)?;
+    assert!(rest.is_empty(), "{value:?} leaves {rest:02x?}");
+
+    Ok(value)
+}
+
+#[test]
+fn skipped_struct_fields_are_not_encoded() -> Result<(), Error> {
+    let value = Cached {
+        id: 300,
+        hits: 42,
+        name: String::from("cached"),
+        generation: 9,
+    };
+
+    let bytes = encode(&value)?;
+    assert_eq!(bytes, encode(&(300u64, String::from("cached")))?);
+
+    assert_eq!(
+        decode::<Cached>(&bytes)?,
+        Cached {
+            hits: 0,
+            generation: 1,
+            ..value
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn skipped_enum_fields_are_not_encoded() -> Result<(), Error> {
+    let circle = Shape::Circle {
+        area: Some(314),
+        radius: 10,
+    };
+    let bytes = encode(&circle)?;
+    assert_eq!(bytes, encode(&(0usize, 10u32))?);
+    assert_eq!(
+        decode::<Shape>(&bytes)?,
+        Shape::Circle {
+            area: None,
+            radius: 10
+        }
+    );
+
+    let label = Shape::Label(String::from("label"), 5);
+    let bytes = encode(&label)?;
+    assert_eq!(bytes, encode(&(1usize, String::from("label")))?);
+    assert_eq!(
+        decode::<Shape>(&bytes)?,
+        Shape::Label(String::from("label"), 0)
+    );
+
+    // The skipped fields of one variant do not affect decoding the others.
+    let bytes = encode(&Shape::Empty)?;
+    assert_eq!(bytes, encode(&2usize)?);
+    assert_eq!(decode::<Shape>(&bytes)?, Shape::Empty);
+
+    Ok(())
+}
+
+#[test]
+fn older_versions_decode_with_defaults() -> Result<(), Error> {
+    for config in configs() {
+        let bytes = encode_with(
+            &v1::Account {
+                name: "alice".into(),
+            },
+            config,
+        )?;
+
+        assert_eq!(
+            decode_with::<v2::Account>(&bytes, config)?,
+            v2::Account {
+                name: "alice".into(),
+                status: Status::Active { since: -1 },
+            }
+        );
+        assert_eq!(
+            decode_with::<v3::Account>(&bytes, config)?,
+            v3::Account {
+                name: "alice".into(),
+                status: Status::Active { since: -1 },
+                groups: default_groups(),
+                quota: None,
+                limit: v3::Account::DEFAULT_LIMIT,
+            }
+        );
+
+        let bytes = encode_with(
+            &v2::Account {
+                name: "bob".into(),
+                status: Status::Suspended(String::from("spam")),
+            },
+            config,
+        )?;
+
+        assert_eq!(
+            decode_with::<v3::Account>(&bytes, config)?,
+            v3::Account {
+                name: "bob".into(),
+                status: Status::Suspended(String::from("spam")),
+                groups: default_groups(),
+                quota: None,
+                limit: v3::Account::DEFAULT_LIMIT,
+            }
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn newer_versions_are_rejected() -> Result<(), Error> {
+    for config in configs() {
+        let account = v3::Account {
+            name: "carol".into(),
+            status: Status::Active {
+                since: 1_700_000_000,
+            },
+            groups: vec!["admins".into()],
+            quota: Some(1 << 30),
+            limit: 5,
+        };
+        let bytes = encode_with(&account, config)?;
+        assert_eq!(decode_with::<v3::Account>(&bytes, config)?, account);
+
+        assert!(matches!(
+            v2::Account::from_slice(&bytes, config),
+            Err(Error::UnknownVersion { version: 3, .. })
+        ));
+        assert!(matches!(
+            v1::Account::from_slice(&bytes, config),
+            Err(Error::UnknownVersion { version: 3, .. })
+        ));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn versions_are_encoded_with_the_config() -> Result<(), Error> {
+    let account = v1::Account {
+        name: "dave".into(),
+    };
+
+    let mut config = Config::default();
+    config.fixed_width().with_endian(Endian::Big);
+
+    // The version is a `usize`, followed by the fields.
+    assert_eq!(
+        encode_with(&account, config)?,
+        encode_with(&(1usize, Cow::Borrowed("dave")), config)?
+    );
+    assert_eq!(encode(&account)?, encode(&(1usize, Cow::Borrowed("dave")))?);
+
+    // Fields that override the config keep doing so in later versions.
+    let account = v3::Account {
+        name: "".into(),
+        status: Status::Active { since: 0 },
+        groups: vec![],
+        quota: None,
+        limit: 0x0102_0304,
+    };
+    let bytes = encode(&account)?;
+    assert_eq!(bytes[bytes.len() - 4..], 0x0102_0304u32.to_le_bytes());
+    assert_eq!(decode::<v3::Account>(&bytes)?, account);
+
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use arken::{Arken, Config, Error, MappedFile, PathSegment, Ref, Writer};
+use bytes::BytesMut;
+use std::borrow::Cow;
+
+#[derive(Arken, Clone, Debug, PartialEq)]
+enum Tag<'a> {
+    Name(Cow<'a, str>),
+    Level { value: u8 },
+}
+
+#[derive(Arken, Clone, Debug, PartialEq)]
+struct Event<'a> {
+    id: u64,
+    tags: Vec<Tag<'a>>,
+}
+
+#[test]
+fn errors_describe_the_path_to_the_field() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("file.ark");
+    let event = Event {
+        id: 7,
+        tags: vec![Tag::Level { value: 3 }, Tag::Name(Cow::Borrowed("bad"))],
+    };
+
+    let mut writer = Writer::create(&path, Config::default())?;
+    let mut bytes = BytesMut::new();
+    writer.append(&mut bytes, &Cow::Borrowed("padding"))?;
+    let reference = writer.append(&mut bytes, &event)?;
+    writer.flush()?;
+
+    // Turn the name into invalid UTF-8.
+    let mut data = std::fs::read(&path)?;
+    let name = reference.offset()
+        + data[reference.offset()..]
+            .windows(3)
+            .position(|window| window == b"bad")
+            .expect("name");
+    data[name] = 0xff;
+    std::fs::write(&path, data)?;
+
+    let file = MappedFile::open(&path)?;
+    let reader = file.try_reader()?;
+
+    let error = reader.read(&reference).expect_err("invalid UTF-8");
+    assert!(matches!(error, Error::InvalidUtf8 { .. }));
+
+    let context = error.context().expect("context");
+    assert_eq!(context.offset, name);
+    assert_eq!(context.type_name, std::any::type_name::<Cow<str>>());
+    assert_eq!(
+        context.path,
+        [
+            PathSegment::Field("tags"),
+            PathSegment::Index(1),
+            PathSegment::Field("Name"),
+            PathSegment::Field("0"),
+        ]
+    );
+    assert_eq!(context.path(), "tags[1].Name.0");
+    assert!(error.to_string().contains("in field `tags[1].Name.0`"));
+
+    Ok(())
+}
+
+#[test]
+fn verbose_reads_show_the_bytes_around_the_error() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("file.ark");
+    let event = Event {
+        id: 7,
+        tags: vec![Tag::Level { value: 3 }],
+    };
+
+    let mut writer = Writer::create(&path, Config::default())?;
+    let mut bytes = BytesMut::new();
+    let reference = writer.append(&mut bytes, &event)?;
+    writer.flush()?;
+
+    // Replace the tag of the enum with one that has no variant.
+    let mut data = std::fs::read(&path)?;
+    let tag = reference.offset() + 2;
+    assert_eq!(data[tag], 1);
+    data[tag] = 9;
+    std::fs::write(&path, data)?;
+
+    let file = MappedFile::open(&path)?;
+    let reader = file.try_reader()?;
+
+    let diagnostic = reader.read_verbose(&reference).expect_err("invalid tag");
+    assert!(matches!(
+        diagnostic.error,
+        Error::InvalidTag { tag: 9, ref context } if context.offset == tag
+    ));
+    assert_eq!(diagnostic.offset, reference.offset());
+    assert_eq!(diagnostic.type_name, std::any::type_name::<Event>());
+    assert_eq!(diagnostic.bytes[tag - diagnostic.bytes_offset..][..1], [9]);
+
+    let output = diagnostic.to_string();
+    assert!(output.starts_with("failed to read `"), "{output}");
+    assert!(output.contains("  field:  tags[0]\n"), "{output}");
+    assert!(output.contains("(2 bytes into the value)"), "{output}");
+    assert!(output.contains(" [09]"), "{output}");
+
+    Ok(())
+}
+
+#[test]
+fn verbose_reads_of_invalid_offsets_have_no_context() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("file.ark");
+
+    let mut writer = Writer::create(&path, Config::default())?;
+    let mut bytes = BytesMut::new();
+    writer.append(&mut bytes, &42u64)?;
+    writer.flush()?;
+
+    let file = MappedFile::open(&path)?;
+    let reader = file.try_reader()?;
+
+    let diagnostic = reader
+        .read_verbose(&Ref::<u64>::new(1 << 20))
+        .expect_err("invalid offset");
+    assert!(matches!(diagnostic.error, Error::InvalidOffset));
+    assert!(diagnostic.error.context().is_none());
+    assert_eq!(diagnostic.to_string().lines().count(), 1);
+
+    Ok(())
+}
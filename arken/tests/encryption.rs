@@ -0,0 +1,262 @@
+#![cfg(feature = "encryption")]
+
+use arken::{Cipher, Config, Error, Key, MappedFile, Writer};
+use bytes::BytesMut;
+use std::{borrow::Cow, path::Path};
+
+const MARKER: &[u8] = b"record";
+
+/// The size of the header of an encrypted file with the default configuration: the magic, the
+/// flags, the format version, the cipher, the salt and the tag.
+const HEADER_SIZE: usize = 3 + 1 + 1 + 1 + 16 + 16;
+/// The size of the header of a frame: the padding, the size, the epoch and their tag.
+const FRAME_HEADER_SIZE: usize = 4 + 4 + 8 + 16;
+
+fn key() -> Key {
+    Key::new([7; 32])
+}
+
+/// Writes a few records to a new encrypted file, and returns the offset of the first record.
+fn write(path: &Path, cipher: Cipher) -> Result<usize, Error> {
+    let mut writer = Writer::create_encrypted(path, Config::default(), cipher, &key())?;
+    let mut bytes = BytesMut::new();
+
+    let first = writer.append(&mut bytes, &Cow::Borrowed("first record"))?;
+
+    for n in 0..3u64 {
+        writer.append_with_marker(&mut bytes, MARKER, &n)?;
+    }
+
+    writer.flush()?;
+
+    Ok(first.offset())
+}
+
+fn read(path: &Path, key: &Key) -> Result<Vec<u64>, Error> {
+    let file = MappedFile::open_encrypted(path, key)?;
+
+    Ok(file.reader().find::<u64>(MARKER).collect())
+}
+
+/// Flips the lowest bit of the byte at `offset`.
+fn flip(path: &Path, offset: usize) -> Result<(), Error> {
+    let mut data = std::fs::read(path)?;
+    data[offset] ^= 0x01;
+    std::fs::write(path, data)?;
+
+    Ok(())
+}
+
+fn ciphers() -> [Cipher; 2] {
+    [Cipher::Aes256Gcm, Cipher::ChaCha20Poly1305]
+}
+
+#[test]
+fn records_round_trip() -> Result<(), Error> {
+    for cipher in ciphers() {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("file.ark");
+        write(&path, cipher)?;
+
+        assert_eq!(read(&path, &key())?, [2, 1, 0]);
+
+        let data = std::fs::read(&path)?;
+        assert!(!data.windows(12).any(|window| window == b"first record"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn wrong_key_fails_to_authenticate() -> Result<(), Error> {
+    for cipher in ciphers() {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("file.ark");
+        write(&path, cipher)?;
+
+        let result = read(&path, &Key::new([8; 32]));
+        assert!(
+            matches!(result, Err(Error::Unauthenticated(_))),
+            "{cipher:?}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn writers_with_the_wrong_key_are_rejected() -> Result<(), Error> {
+    for cipher in ciphers() {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("file.ark");
+
+        // A file without any records has nothing but its header to check the key with.
+        Writer::create_encrypted(&path, Config::default(), cipher, &key())?.flush()?;
+        let data = std::fs::read(&path)?;
+
+        let result = Writer::open_encrypted(&path, &Key::new([8; 32]));
+        assert!(
+            matches!(result, Err(Error::Unauthenticated(_))),
+            "{cipher:?}"
+        );
+        assert_eq!(std::fs::read(&path)?, data);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn writers_do_not_truncate_tampered_files() -> Result<(), Error> {
+    for cipher in ciphers() {
+        // The highest byte of the size of the first frame, and the ciphertext of its record.
+        for offset in [HEADER_SIZE + 7, HEADER_SIZE + FRAME_HEADER_SIZE + 1] {
+            let dir = tempfile::tempdir()?;
+            let path = dir.path().join("file.ark");
+            write(&path, cipher)?;
+            flip(&path, offset)?;
+            let data = std::fs::read(&path)?;
+
+            let result = Writer::open_encrypted(&path, &key());
+            assert!(
+                matches!(result, Err(Error::Unauthenticated(_))),
+                "{cipher:?} {offset}"
+            );
+            assert_eq!(std::fs::read(&path)?, data);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn tampered_ciphertext_fails_to_authenticate() -> Result<(), Error> {
+    for cipher in ciphers() {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("file.ark");
+        let offset = write(&path, cipher)?;
+        flip(&path, offset + 1)?;
+
+        let result = read(&path, &key());
+        assert!(
+            matches!(result, Err(Error::Unauthenticated(_))),
+            "{cipher:?}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn every_writer_seals_with_its_own_epoch() -> Result<(), Error> {
+    for cipher in ciphers() {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("file.ark");
+        write(&path, cipher)?;
+        let size = std::fs::metadata(&path)?.len() as usize;
+
+        let mut writer = Writer::open_encrypted(&path, &key())?;
+        let mut bytes = BytesMut::new();
+        writer.append_with_marker(&mut bytes, MARKER, &3u64)?;
+        writer.flush()?;
+
+        assert_eq!(read(&path, &key())?, [3, 2, 1, 0]);
+
+        // The epoch of the frame written by the second writer differs from that of the first.
+        let data = std::fs::read(&path)?;
+        let epoch = |start: usize| &data[start + 8..start + 16];
+        assert_ne!(epoch(HEADER_SIZE), epoch(size));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn tampered_frame_header_fails_to_authenticate() -> Result<(), Error> {
+    for cipher in ciphers() {
+        // The padding, the lowest and highest byte of the size, which then extends beyond the
+        // end of the file, the lowest and highest byte of the epoch, and the tag of the first
+        // frame.
+        for offset in [
+            HEADER_SIZE,
+            HEADER_SIZE + 4,
+            HEADER_SIZE + 7,
+            HEADER_SIZE + 8,
+            HEADER_SIZE + 15,
+            HEADER_SIZE + 16,
+            HEADER_SIZE + FRAME_HEADER_SIZE - 1,
+        ] {
+            let dir = tempfile::tempdir()?;
+            let path = dir.path().join("file.ark");
+            let record = write(&path, cipher)?;
+            assert!(record >= HEADER_SIZE + FRAME_HEADER_SIZE);
+            flip(&path, offset)?;
+
+            let result = read(&path, &key());
+            assert!(
+                matches!(result, Err(Error::Unauthenticated(_))),
+                "{cipher:?} {offset}: {result:?}"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn partly_written_frames_are_ignored() -> Result<(), Error> {
+    for cipher in ciphers() {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("file.ark");
+        write(&path, cipher)?;
+        let start = std::fs::metadata(&path)?.len() as usize;
+
+        let mut writer = Writer::open_encrypted(&path, &key())?;
+        let mut bytes = BytesMut::new();
+        writer.append_with_marker(&mut bytes, MARKER, &3u64)?;
+        writer.flush()?;
+        let data = std::fs::read(&path)?;
+
+        // The last frame without the last byte of its tag, with only its header, and with only
+        // part of its header.
+        for end in [
+            data.len() - 1,
+            start + FRAME_HEADER_SIZE,
+            start + FRAME_HEADER_SIZE - 1,
+        ] {
+            std::fs::write(&path, &data[..end])?;
+            assert_eq!(read(&path, &key())?, [2, 1, 0], "{cipher:?} {end}");
+
+            // Writers truncate the incomplete frame before appending.
+            let mut writer = Writer::open_encrypted(&path, &key())?;
+            writer.append_with_marker(&mut bytes, MARKER, &4u64)?;
+            writer.flush()?;
+            assert_eq!(read(&path, &key())?, [4, 2, 1, 0], "{cipher:?} {end}");
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn tampered_file_header_is_rejected() -> Result<(), Error> {
+    for cipher in ciphers() {
+        // Every byte after the magic: the flags, the format version, the cipher and the salt.
+        for offset in 3..HEADER_SIZE {
+            let dir = tempfile::tempdir()?;
+            let path = dir.path().join("file.ark");
+            write(&path, cipher)?;
+            flip(&path, offset)?;
+
+            let result = read(&path, &key());
+            assert!(
+                matches!(
+                    result,
+                    Err(Error::Unauthenticated(_) | Error::InvalidHeader)
+                ),
+                "{cipher:?} {offset}: {result:?}"
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use arken::{
+    Config, Error, FarRef, Field, MappedFile, MultiReader, RetentionPolicy, SegmentedReader,
+    SegmentedWriter, Writer,
+};
+use bytes::BytesMut;
+use std::borrow::Cow;
+
+const MARKER: &[u8] = b"entry";
+
+/// An entry that links to the entry before it, which may be in another segment.
+type Entry<'a> = (u64, Option<FarRef<'a, Cow<'a, str>>>);
+
+#[test]
+fn resolves_references_across_files() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let (base_path, path) = (dir.path().join("base.ark"), dir.path().join("file.ark"));
+    let mut bytes = BytesMut::new();
+
+    let mut base = Writer::create(&base_path, Config::default())?;
+    base.append(&mut bytes, &Cow::Borrowed("padding"))?;
+    let shared = base.append(&mut bytes, &Cow::Borrowed("shared"))?;
+    base.flush()?;
+
+    let mut config = Config::default();
+    config.fixed_width();
+
+    let mut writer = Writer::create(&path, config)?;
+    let own = writer.append(&mut bytes, &Cow::Borrowed("own"))?;
+    let references = [FarRef::new(1, own), FarRef::new(0, shared)];
+    writer.append_with_marker(&mut bytes, MARKER, &Cow::Borrowed(&references[..]))?;
+    writer.flush()?;
+
+    let (base, file) = (MappedFile::open(&base_path)?, MappedFile::open(&path)?);
+    let reader = MultiReader::default()
+        .with_file(0, base.try_reader()?)
+        .with_file(1, file.try_reader()?);
+
+    let references: Cow<[FarRef<Cow<str>>]> =
+        file.try_reader()?.find(MARKER).next().expect("references");
+
+    assert_eq!(references[0].file(), 1);
+    assert_eq!(reader.read(&references[0])?, "own");
+    assert_eq!(references[1].file(), 0);
+    assert_eq!(reader.read(&references[1])?, "shared");
+
+    Ok(())
+}
+
+#[test]
+fn unknown_files_are_reported() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("file.ark");
+    let mut bytes = BytesMut::new();
+
+    let mut writer = Writer::create(&path, Config::default())?;
+    let value = writer.append(&mut bytes, &42u64)?;
+    writer.flush()?;
+
+    let file = MappedFile::open(&path)?;
+    let reader = MultiReader::default().with_file(1, file.try_reader()?);
+
+    assert_eq!(reader.read(&FarRef::new(1, value))?, 42);
+    assert!(matches!(
+        reader.read(&FarRef::new(2, value)),
+        Err(Error::UnknownFile(2))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn far_refs_round_trip() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("file.ark");
+    let mut bytes = BytesMut::new();
+
+    let mut writer = Writer::create(&path, Config::default())?;
+    let value = writer.append(&mut bytes, &7u64)?;
+    writer.flush()?;
+
+    let reference = FarRef::new(u64::MAX, value);
+
+    let mut encoded = BytesMut::new();
+    reference.put_bytes(&mut encoded, Config::default())?;
+    let (decoded, rest) = FarRef::<u64>::from_slice(&encoded, Config::default())?;
+
+    assert!(rest.is_empty());
+    assert_eq!(decoded, reference);
+    assert_eq!(decoded.file(), u64::MAX);
+    assert_eq!(decoded.local().offset(), value.offset());
+
+    Ok(())
+}
+
+#[test]
+fn segments_link_to_older_segments() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let mut writer = SegmentedWriter::open(dir.path(), "log", Config::default())?;
+    let mut bytes = BytesMut::new();
+
+    let mut previous = None;
+
+    for n in 0..3u64 {
+        let name = writer.append(&mut bytes, &Cow::Owned(format!("entry {n}")))?;
+        let entry: Entry = (n, previous);
+        writer.append_with_marker(&mut bytes, MARKER, &entry)?;
+
+        previous = Some(FarRef::new(writer.segment(), name));
+        writer.rotate()?;
+    }
+
+    writer.flush()?;
+
+    let reader = SegmentedReader::open(dir.path(), "log")?;
+    let multi_reader = reader.multi_reader();
+
+    let entries: Vec<Entry> = reader.find(MARKER).collect();
+    assert_eq!(entries.len(), 3);
+
+    for (n, previous) in entries {
+        match previous {
+            Some(previous) => {
+                assert_eq!(previous.file(), n);
+                assert_eq!(multi_reader.read(&previous)?, format!("entry {}", n - 1));
+            }
+            None => assert_eq!(n, 0),
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn retention_keeps_referenced_segments() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let mut writer = SegmentedWriter::open(dir.path(), "log", Config::default())?;
+    let mut bytes = BytesMut::new();
+
+    let name = writer.append(&mut bytes, &Cow::Borrowed("first"))?;
+    let first = FarRef::new(writer.segment(), name);
+    writer.rotate()?;
+
+    for n in 0..3u64 {
+        writer.append_with_marker(&mut bytes, MARKER, &n)?;
+        writer.rotate()?;
+    }
+
+    let mut policy = RetentionPolicy::default();
+    policy.with_max_segments(2);
+
+    let deleted = writer.apply_retention_with(&policy, |id| id == first.file())?;
+    // The referenced segment counts towards the maximum, so the segments after it are deleted.
+    assert_eq!(deleted.len(), 3);
+
+    let reader = SegmentedReader::open(dir.path(), "log")?;
+    assert_eq!(reader.segments().collect::<Vec<_>>(), [1, 5]);
+    assert_eq!(reader.multi_reader().read(&first)?, "first");
+
+    // Without the segment being referenced, it is deleted, and the reference dangles.
+    policy.with_max_segments(1);
+    writer.apply_retention(&policy)?;
+
+    let reader = SegmentedReader::open(dir.path(), "log")?;
+    assert_eq!(reader.segments().collect::<Vec<_>>(), [5]);
+    assert!(matches!(
+        reader.multi_reader().read(&first),
+        Err(Error::UnknownFile(1))
+    ));
+
+    Ok(())
+}
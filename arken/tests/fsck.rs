@@ -0,0 +1,104 @@
+#![cfg(feature = "cli")]
+
+use arken::{BlockFormat, Config, Error, MappedFile, MergeMap, Writer};
+use bytes::BytesMut;
+use std::{path::Path, process::Command};
+
+const MARKER: &[u8] = b"entry";
+
+/// Runs `arken-fsck` and returns whether it succeeded along with its output.
+fn fsck(path: &Path, args: &[&str]) -> Result<(bool, String), Error> {
+    let output = Command::new(env!("CARGO_BIN_EXE_arken-fsck"))
+        .arg(path)
+        .args(args)
+        .output()?;
+
+    Ok((
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+    ))
+}
+
+#[test]
+fn reports_damaged_records() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("file.ark");
+    let mut writer = Writer::create(&path, Config::default())?;
+    let mut bytes = BytesMut::new();
+
+    let mut offsets = vec![];
+
+    for n in 0..3u64 {
+        offsets.push(writer.append_with_marker(&mut bytes, MARKER, &n)?.offset());
+    }
+
+    writer.flush()?;
+
+    let (ok, output) = fsck(&path, &["--marker", "entry"])?;
+    assert!(ok, "{output}");
+    assert!(output.contains("no problems found"));
+
+    let mut data = std::fs::read(&path)?;
+    data[offsets[1]] ^= 0x01;
+    std::fs::write(&path, data)?;
+
+    let (ok, output) = fsck(&path, &["--marker", "entry"])?;
+    assert!(!ok);
+    assert!(output.contains(&format!("{:#010x}: checksum mismatch", offsets[1])));
+    assert!(output.contains("1 problem(s) found"));
+
+    Ok(())
+}
+
+#[test]
+fn reports_newer_format_versions() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("file.ark");
+    std::fs::write(&path, b"ARK\x41\x63")?;
+
+    let (ok, output) = fsck(&path, &[])?;
+    assert!(!ok);
+    assert!(output.contains("unsupported format version 99"), "{output}");
+
+    Ok(())
+}
+
+#[test]
+fn checks_and_prints_maps_with_block_tables() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("file.ark");
+    let mut writer = Writer::create(&path, Config::default())?;
+    let mut bytes = BytesMut::new();
+
+    let file = MappedFile::open(&path)?;
+    let mut map = MergeMap::<String, u64>::open(file.reader(), None);
+    map.with_blocks(BlockFormat::default());
+
+    for n in 0..3u64 {
+        map.insert(format!("key-{n}"), n);
+    }
+
+    if let Some(root) = map.commit(&mut bytes, &mut writer)? {
+        writer.append_with_marker(&mut bytes, b"map", &root)?;
+    }
+
+    writer.flush()?;
+
+    let (ok, output) = fsck(&path, &["--merge-map", "map:str"])?;
+    assert!(ok, "{output}");
+    assert!(output.contains("no problems found"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_arken"))
+        .arg("merge-map")
+        .arg(&path)
+        .args(["map", "--key", "str", "--value", "u64"])
+        .output()?;
+    assert!(output.status.success());
+
+    let output = String::from_utf8_lossy(&output.stdout);
+    assert!(output.contains("entries: 3"), "{output}");
+    assert!(output.contains("\"key-2\" => 2"), "{output}");
+    assert!(!output.contains("error"), "{output}");
+
+    Ok(())
+}
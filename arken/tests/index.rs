@@ -0,0 +1,213 @@
+use arken::{Config, Error, IndexWriter, MappedFile, Writer};
+use bytes::BytesMut;
+use std::path::{Path, PathBuf};
+
+const MARKER: &[u8] = b"event";
+
+type Event = (i64, u32);
+
+fn timestamp(event: &Event) -> i64 {
+    event.0
+}
+
+/// Writes `count` events with timestamps `0, 10, 20, ...` to a new file, along with an index at
+/// `<name>.idx`.
+fn write_log(dir: &Path, name: &str, count: u32, seed: u32) -> Result<PathBuf, Error> {
+    let path = dir.join(name);
+    let mut writer = Writer::create(&path, Config::default())?;
+    let mut bytes = BytesMut::new();
+
+    let file = MappedFile::open(&path)?;
+    let mut index = IndexWriter::tempfile(&mut bytes, &file.reader(), MARKER)?;
+
+    for n in 0..count {
+        let event: Event = (n as i64 * 10, seed + n);
+        let reference = writer.append_with_marker(&mut bytes, MARKER, &event)?;
+        index.push(&mut bytes, &reference, event.0)?;
+    }
+
+    writer.flush()?;
+    index.persist(path.with_extension("idx"))?;
+
+    Ok(path)
+}
+
+#[test]
+fn nth_record_without_index() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = write_log(dir.path(), "log", 10, 100)?;
+
+    let file = MappedFile::open(&path)?;
+    let reader = file.reader();
+
+    for n in 0..10 {
+        assert_eq!(
+            reader.nth_record::<Event>(MARKER, n),
+            Some((n as i64 * 10, 100 + n as u32))
+        );
+    }
+
+    assert_eq!(reader.nth_record::<Event>(MARKER, 10), None);
+    assert_eq!(reader.search_timestamp(MARKER, 0, timestamp), None);
+
+    Ok(())
+}
+
+#[test]
+fn nth_record_and_search_timestamp_with_index() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = write_log(dir.path(), "log", 10, 100)?;
+
+    let file = MappedFile::open(&path)?;
+    let index_file = MappedFile::open(path.with_extension("idx"))?;
+    let index = index_file.index()?;
+    assert_eq!(index.len(), 10);
+    assert!(index.matches(&file.reader()));
+
+    let reader = file.reader().with_index(index);
+
+    for n in 0..10 {
+        assert_eq!(
+            reader.nth_record::<Event>(MARKER, n),
+            Some((n as i64 * 10, 100 + n as u32))
+        );
+    }
+
+    assert_eq!(reader.nth_record::<Event>(MARKER, 10), None);
+
+    assert_eq!(
+        reader.search_timestamp(MARKER, i64::MIN, timestamp),
+        Some(0)
+    );
+    assert_eq!(reader.search_timestamp(MARKER, 0, timestamp), Some(0));
+    assert_eq!(reader.search_timestamp(MARKER, 10, timestamp), Some(1));
+    assert_eq!(reader.search_timestamp(MARKER, 15, timestamp), Some(2));
+    assert_eq!(reader.search_timestamp(MARKER, 90, timestamp), Some(9));
+    assert_eq!(reader.search_timestamp(MARKER, 91, timestamp), Some(10));
+
+    Ok(())
+}
+
+#[test]
+fn rebuild_matches_pushed_index() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = write_log(dir.path(), "log", 25, 0)?;
+
+    let file = MappedFile::open(&path)?;
+    let reader = file.reader();
+
+    let mut bytes = BytesMut::new();
+    let rebuilt_path = dir.path().join("rebuilt.idx");
+    IndexWriter::rebuild(&mut bytes, &reader, MARKER, timestamp)?.persist(&rebuilt_path)?;
+
+    let pushed_file = MappedFile::open(path.with_extension("idx"))?;
+    let rebuilt_file = MappedFile::open(&rebuilt_path)?;
+    let (pushed, rebuilt) = (pushed_file.index()?, rebuilt_file.index()?);
+
+    assert_eq!(rebuilt.len(), 25);
+    assert!(rebuilt.matches(&reader));
+
+    for n in 0..25 {
+        assert_eq!(pushed.get(n), rebuilt.get(n));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn stale_index_is_ignored_and_rebuilt() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = write_log(dir.path(), "log", 10, 0)?;
+    // As both files were empty when their index was created, only the records tell them apart.
+    let other = write_log(dir.path(), "other", 5, 1000)?;
+
+    // Use the index of the other file for this one.
+    let index_path = path.with_extension("idx");
+    std::fs::copy(other.with_extension("idx"), &index_path)?;
+
+    let file = MappedFile::open(&path)?;
+    let index_file = MappedFile::open(&index_path)?;
+    let index = index_file.index()?;
+    assert!(!index.matches(&file.reader()));
+
+    let reader = file.reader().with_index(index);
+    assert_eq!(reader.search_timestamp(MARKER, 0, timestamp), None);
+    assert_eq!(reader.nth_record::<Event>(MARKER, 3), Some((30, 3)));
+    assert_eq!(reader.nth_record::<Event>(MARKER, 9), Some((90, 9)));
+    drop(index_file);
+
+    let mut bytes = BytesMut::new();
+    IndexWriter::open_or_rebuild(&index_path, &mut bytes, &file.reader(), MARKER, timestamp)?;
+
+    let index_file = MappedFile::open(&index_path)?;
+    let index = index_file.index()?;
+    assert_eq!(index.len(), 10);
+
+    let reader = file.reader().with_index(index);
+    assert_eq!(reader.search_timestamp(MARKER, 45, timestamp), Some(5));
+
+    Ok(())
+}
+
+#[test]
+fn index_of_a_truncated_file_does_not_match() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = write_log(dir.path(), "log", 10, 0)?;
+
+    let file = MappedFile::open(&path)?;
+    let index_file = MappedFile::open(path.with_extension("idx"))?;
+    let last = index_file.index()?.get(9).unwrap();
+    drop(file);
+
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(&path)?
+        .set_len(last.offset)?;
+
+    let file = MappedFile::open(&path)?;
+    assert!(!index_file.index()?.matches(&file.reader()));
+
+    Ok(())
+}
+
+#[test]
+fn records_after_a_lagging_index_are_scanned() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = write_log(dir.path(), "log", 5, 0)?;
+
+    // Append records without adding them to the index.
+    let mut writer = Writer::open(&path)?;
+    let mut bytes = BytesMut::new();
+
+    for n in 5..10u32 {
+        let event: Event = (n as i64 * 10, n);
+        writer.append_with_marker(&mut bytes, MARKER, &event)?;
+    }
+
+    writer.flush()?;
+
+    let file = MappedFile::open(&path)?;
+    let index_file = MappedFile::open(path.with_extension("idx"))?;
+    let index = index_file.index()?;
+    assert_eq!(index.len(), 5);
+    assert!(index.matches(&file.reader()));
+
+    let reader = file.reader().with_index(index);
+
+    for n in 0..10 {
+        assert_eq!(
+            reader.nth_record::<Event>(MARKER, n),
+            Some((n as i64 * 10, n as u32))
+        );
+    }
+
+    assert_eq!(reader.nth_record::<Event>(MARKER, 10), None);
+
+    assert_eq!(reader.search_timestamp(MARKER, 40, timestamp), Some(4));
+    assert_eq!(reader.search_timestamp(MARKER, 41, timestamp), Some(5));
+    assert_eq!(reader.search_timestamp(MARKER, 75, timestamp), Some(8));
+    assert_eq!(reader.search_timestamp(MARKER, 80, timestamp), Some(8));
+    assert_eq!(reader.search_timestamp(MARKER, 91, timestamp), Some(10));
+
+    Ok(())
+}
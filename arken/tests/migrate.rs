@@ -0,0 +1,195 @@
+use arken::{
+    Arken, Checksum, Config, ConvertHashMap, ConvertMergeMap, Endian, Error, HashMap, HashRootRef,
+    MapConversion, MappedFile, MergeMap, MergeRootRef, Ref, Writer, migrate_to,
+};
+use bytes::BytesMut;
+use std::{borrow::Cow, path::Path};
+
+const MARKER: &[u8] = b"root";
+
+#[derive(Arken, Clone, Debug)]
+struct UserV1<'a> {
+    name: Cow<'a, str>,
+}
+
+#[derive(Arken, Clone, Debug, PartialEq)]
+struct UserV2<'a> {
+    name: Cow<'a, str>,
+    admin: bool,
+}
+
+impl<'a> From<UserV1<'a>> for UserV2<'a> {
+    fn from(user: UserV1<'a>) -> Self {
+        Self {
+            admin: user.name == "user 0",
+            name: user.name,
+        }
+    }
+}
+
+struct Users;
+
+impl MapConversion for Users {
+    const MARKER: &'static [u8] = b"users";
+
+    type Key<'a> = u64;
+    type Old<'a> = UserV1<'a>;
+    type New<'a> = UserV2<'a>;
+}
+
+const USERS: u64 = 50;
+
+fn config() -> Config {
+    let mut config = Config::default();
+    config
+        .fixed_width()
+        .with_endian(Endian::Big)
+        .with_checksum(Checksum::Crc32c);
+    config
+}
+
+fn user(n: u64) -> UserV1<'static> {
+    UserV1 {
+        name: format!("user {n}").into(),
+    }
+}
+
+fn expected(n: u64) -> UserV2<'static> {
+    UserV2 {
+        name: format!("user {n}").into(),
+        admin: n == 0,
+    }
+}
+
+/// Writes the users into a `MergeMap` if `merge` is set, and into a `HashMap` otherwise.
+fn write_users(path: &Path, merge: bool) -> Result<(), Error> {
+    let mut writer = Writer::create(path, config())?;
+    let mut bytes = BytesMut::new();
+
+    let file = MappedFile::open(path)?;
+    let reader = file.try_reader()?;
+
+    if merge {
+        let mut map = MergeMap::<u64, UserV1>::open(reader, None);
+        (0..USERS).for_each(|n| _ = map.insert(n, user(n)));
+
+        if let Some(root) = map.commit(&mut bytes, &mut writer)? {
+            writer.append_with_marker(&mut bytes, Users::MARKER, &root)?;
+        }
+    } else {
+        let mut map = HashMap::<u64, UserV1>::open(reader, None);
+        (0..USERS).for_each(|n| _ = map.insert(n, user(n)));
+
+        if let Some(root) = map.commit(&mut bytes, &mut writer)? {
+            writer.append_with_marker(&mut bytes, Users::MARKER, &root)?;
+        }
+    }
+
+    writer.flush()
+}
+
+#[test]
+fn merge_maps_are_converted() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let (src, dst) = (dir.path().join("v1.ark"), dir.path().join("v2.ark"));
+    write_users(&src, true)?;
+
+    let mut bytes = BytesMut::new();
+    migrate_to::<_, _, ConvertMergeMap<Users>>(&mut bytes, &dst, &src)?;
+
+    let file = MappedFile::open(&dst)?;
+    let reader = file.try_reader()?;
+    assert_eq!(reader.config(), config());
+
+    let root = reader
+        .find::<MergeRootRef<u64, UserV2>>(Users::MARKER)
+        .next();
+    let map = MergeMap::<u64, UserV2>::open(reader, root);
+    assert_eq!(map.len(), USERS as usize);
+
+    for n in 0..USERS {
+        assert_eq!(map.get(&n)?.as_deref(), Some(&expected(n)));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn hash_maps_are_converted() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let (src, dst) = (dir.path().join("v1.ark"), dir.path().join("v2.ark"));
+    write_users(&src, false)?;
+
+    let mut bytes = BytesMut::new();
+    migrate_to::<_, _, ConvertHashMap<Users>>(&mut bytes, &dst, &src)?;
+
+    let file = MappedFile::open(&dst)?;
+    let reader = file.try_reader()?;
+    assert_eq!(reader.config(), config());
+
+    let root = reader
+        .find::<HashRootRef<u64, UserV2>>(Users::MARKER)
+        .next();
+    let map = HashMap::<u64, UserV2>::open(reader, root);
+    assert_eq!(map.len(), USERS as usize);
+
+    for n in 0..USERS {
+        assert_eq!(map.get(&n)?.as_deref(), Some(&expected(n)));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn files_without_a_map_migrate_to_an_empty_file() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let (src, dst) = (dir.path().join("v1.ark"), dir.path().join("v2.ark"));
+    Writer::create(&src, config())?.flush()?;
+
+    let mut bytes = BytesMut::new();
+    migrate_to::<_, _, ConvertMergeMap<Users>>(&mut bytes, &dst, &src)?;
+
+    assert_eq!(std::fs::read(&dst)?, std::fs::read(&src)?);
+
+    Ok(())
+}
+
+#[test]
+fn migrate_with_marker_returns_the_migrated_record() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let (src, dst) = (dir.path().join("src.ark"), dir.path().join("dst.ark"));
+    let mut bytes = BytesMut::new();
+
+    let mut writer = Writer::create(&src, Config::default())?;
+    writer.append(&mut bytes, &1u64)?;
+    let value = writer.append(&mut bytes, &u64::MAX)?;
+    writer.append_with_marker(&mut bytes, MARKER, &value)?;
+    writer.flush()?;
+
+    let file = MappedFile::open(&src)?;
+    let reader = file.try_reader()?;
+    let root: Ref<u64> = reader.find(MARKER).next().expect("root");
+
+    let mut writer = Writer::create(&dst, Config::default())?;
+    let migrated = writer.migrate_with_marker(&mut bytes, MARKER, &reader, root)?;
+    writer.flush()?;
+
+    let file = MappedFile::open(&dst)?;
+    let reader = file.try_reader()?;
+    let root: Ref<u64> = reader.find(MARKER).next().expect("root");
+
+    assert_eq!(reader.read(&migrated)?, root);
+    assert!(root.offset() < migrated.offset());
+    assert_eq!(reader.read(&root)?, u64::MAX);
+
+    // Only the value and the root are written, in that order.
+    let expected = dir.path().join("expected.ark");
+    let mut writer = Writer::create(&expected, Config::default())?;
+    let value = writer.append(&mut bytes, &u64::MAX)?;
+    writer.append_with_marker(&mut bytes, MARKER, &value)?;
+    writer.flush()?;
+
+    assert_eq!(std::fs::read(&dst)?, std::fs::read(&expected)?);
+
+    Ok(())
+}
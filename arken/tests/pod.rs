@@ -0,0 +1,195 @@
+use arken::{Arken, Config, Endian, Error, Field, MappedFile, Pod, PodSlice, Writer};
+use bytes::BytesMut;
+
+#[derive(Arken, Clone, Copy, Debug, Eq, PartialEq)]
+#[arken(align = 8)]
+#[repr(C)]
+struct Point {
+    x: u64,
+    y: u64,
+}
+
+// SAFETY: `Point` consists of two `u64`s without padding, and is encoded as two fixed-width
+// integers under a fixed-width configuration.
+unsafe impl Pod for Point {}
+
+fn native() -> Config {
+    let mut config = Config::default();
+    config.fixed_width().with_endian(Endian::Native);
+    config
+}
+
+fn foreign() -> Config {
+    let endian = if cfg!(target_endian = "little") {
+        Endian::Big
+    } else {
+        Endian::Little
+    };
+
+    let mut config = Config::default();
+    config.fixed_width().with_endian(endian);
+    config
+}
+
+/// Encodes `value` such that it starts one byte past a multiple of 8 bytes in the returned
+/// buffer, and returns the buffer along with that offset.
+fn misaligned<'a, T: Field<'a>>(value: &T, config: Config) -> Result<(Vec<u8>, usize), Error> {
+    let mut bytes = BytesMut::new();
+    value.put_bytes(&mut bytes, config)?;
+
+    let mut buffer = vec![0; bytes.len() + 16];
+    let start = buffer.as_ptr().align_offset(8) + 1;
+    buffer[start..start + bytes.len()].copy_from_slice(&bytes);
+
+    Ok((buffer, start))
+}
+
+#[test]
+fn pod_slices_borrow_aligned_native_data() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("file.ark");
+    let values: Vec<u64> = (0..100).map(|n| n * 0x0101_0101).collect();
+
+    let mut writer = Writer::create(&path, native())?;
+    let mut bytes = BytesMut::new();
+    writer.append(&mut bytes, &1u8)?;
+    let reference = writer.append_aligned(&mut bytes, 8, &PodSlice::from(&values[..]))?;
+    writer.flush()?;
+
+    let file = MappedFile::open(&path)?;
+    let slice = file.try_reader()?.read(&reference)?;
+
+    assert!(slice.is_borrowed());
+    assert_eq!(&slice[..], &values[..]);
+
+    Ok(())
+}
+
+#[test]
+fn pod_slices_fall_back_to_copying() -> Result<(), Error> {
+    let values: Vec<u32> = (0..100).map(|n| n * 0x0101_0101).collect();
+    let slice = PodSlice::from(&values[..]);
+
+    for config in [Config::default(), foreign()] {
+        let mut bytes = BytesMut::new();
+        slice.put_bytes(&mut bytes, config)?;
+
+        let (decoded, rest) = PodSlice::<u32>::from_slice(&bytes, config)?;
+        assert!(rest.is_empty());
+        assert!(!decoded.is_borrowed());
+        assert_eq!(&decoded[..], &values[..]);
+    }
+
+    let (buffer, start) = misaligned(&slice, native())?;
+    let (decoded, _) = PodSlice::<u32>::from_slice(&buffer[start..], native())?;
+    assert!(!decoded.is_borrowed());
+    assert_eq!(&decoded[..], &values[..]);
+
+    Ok(())
+}
+
+#[test]
+fn truncated_pod_slices_are_rejected() -> Result<(), Error> {
+    let values = [1u64, 2, 3];
+    let mut bytes = BytesMut::new();
+    PodSlice::from(&values[..]).put_bytes(&mut bytes, native())?;
+
+    assert!(matches!(
+        PodSlice::<u64>::from_slice(&bytes[..bytes.len() - 1], native()),
+        Err(Error::Incomplete(_))
+    ));
+
+    let mut bytes = BytesMut::new();
+    usize::MAX.put_bytes(&mut bytes, native())?;
+
+    assert!(matches!(
+        PodSlice::<u64>::from_slice(&bytes, native()),
+        Err(Error::Overflow(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn aligned_records_are_read_in_place() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("file.ark");
+    let point = Point { x: 1, y: u64::MAX };
+
+    let mut writer = Writer::create(&path, native())?;
+    let mut bytes = BytesMut::new();
+    writer.append(&mut bytes, &1u8)?;
+    let point_ref = writer.append(&mut bytes, &point)?;
+    let value_ref = writer.append_aligned(&mut bytes, 8, &42u64)?;
+    writer.flush()?;
+
+    assert_eq!(point_ref.offset() % 8, 0);
+    assert_eq!(value_ref.offset() % 8, 0);
+
+    let file = MappedFile::open(&path)?;
+    let reader = file.try_reader()?;
+
+    assert_eq!(reader.read_aligned(&point_ref)?, &point);
+    assert_eq!(reader.read_aligned(&value_ref)?, &42);
+
+    Ok(())
+}
+
+#[test]
+fn unaligned_records_are_not_read_in_place() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+
+    for (name, config) in [("varint", Config::default()), ("foreign", foreign())] {
+        let path = dir.path().join(name);
+        let mut writer = Writer::create(&path, config)?;
+        let mut bytes = BytesMut::new();
+        let reference = writer.append_aligned(&mut bytes, 8, &42u64)?;
+        writer.flush()?;
+
+        let file = MappedFile::open(&path)?;
+        let reader = file.try_reader()?;
+
+        assert!(matches!(
+            reader.read_aligned(&reference),
+            Err(Error::Unaligned { .. })
+        ));
+        assert_eq!(reader.read(&reference)?, 42);
+    }
+
+    // Without padding, the record directly follows the header and a one byte record.
+    let path = dir.path().join("misaligned");
+    let mut writer = Writer::create(&path, native())?;
+    let mut bytes = BytesMut::new();
+    writer.append(&mut bytes, &1u8)?;
+    let reference = writer.append(&mut bytes, &42u64)?;
+    writer.flush()?;
+
+    assert_ne!(reference.offset() % 8, 0);
+
+    let file = MappedFile::open(&path)?;
+    let reader = file.try_reader()?;
+
+    assert!(matches!(
+        reader.read_aligned(&reference),
+        Err(Error::Unaligned { offset, .. }) if offset == reference.offset()
+    ));
+    assert_eq!(reader.read(&reference)?, 42);
+
+    Ok(())
+}
+
+#[test]
+fn alignments_must_be_powers_of_two() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let mut writer = Writer::create(dir.path().join("file.ark"), native())?;
+    let mut bytes = BytesMut::new();
+
+    for align in [3, 6, 24] {
+        assert!(matches!(
+            writer.append_aligned(&mut bytes, align, &42u64),
+            Err(Error::InvalidAlignment(a)) if a == align
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,260 @@
+use arken::{Config, Endian, Error, Field};
+use bytes::BytesMut;
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt::Debug,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+    num::{NonZeroI8, NonZeroI64, NonZeroU16, NonZeroU128, NonZeroUsize},
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Returns every combination of endianness and integer width.
+fn configs() -> Vec<Config> {
+    let mut configs = vec![];
+
+    for endian in [Endian::Big, Endian::Little] {
+        for fixed in [false, true] {
+            let mut config = Config::default();
+            config.with_endian(endian);
+
+            if fixed {
+                config.fixed_width();
+            }
+
+            configs.push(config);
+        }
+    }
+
+    configs
+}
+
+/// Encodes `value` under every configuration, and checks that it decodes to the same value
+/// without leaving any bytes behind.
+fn round_trip<T>(value: T)
+where
+    T: for<'a> Field<'a> + Debug + PartialEq,
+{
+    for config in configs() {
+        let mut bytes = BytesMut::new();
+        value.put_bytes(&mut bytes, config).unwrap();
+
+        let (decoded, rest) = T::from_slice(&bytes, config).unwrap();
+        assert_eq!(decoded, value, "{config:?}");
+        assert!(rest.is_empty(), "{config:?}");
+    }
+}
+
+#[test]
+fn scalars() {
+    round_trip(false);
+    round_trip(true);
+
+    for c in ['\0', 'a', 'é', '€', '🦀', char::MAX] {
+        round_trip(c);
+    }
+
+    round_trip(NonZeroI8::new(i8::MIN).unwrap());
+    round_trip(NonZeroI64::new(-1).unwrap());
+    round_trip(NonZeroU16::new(300).unwrap());
+    round_trip(NonZeroU128::new(u128::MAX).unwrap());
+    round_trip(NonZeroUsize::new(1).unwrap());
+}
+
+#[test]
+fn zero_is_rejected_for_non_zero_integers() {
+    for config in configs() {
+        let mut bytes = BytesMut::new();
+        0u16.put_bytes(&mut bytes, config).unwrap();
+
+        assert!(NonZeroU16::from_slice(&bytes, config).is_err());
+    }
+}
+
+#[test]
+fn invalid_chars_are_rejected() {
+    for config in configs() {
+        let mut bytes = BytesMut::new();
+        0xd800u32.put_bytes(&mut bytes, config).unwrap();
+
+        assert!(char::from_slice(&bytes, config).is_err());
+    }
+}
+
+#[test]
+fn time() {
+    round_trip(Duration::ZERO);
+    round_trip(Duration::new(1, 999_999_999));
+    round_trip(Duration::MAX);
+
+    round_trip(UNIX_EPOCH);
+    round_trip(UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789));
+    round_trip(UNIX_EPOCH - Duration::new(86_400, 1));
+    round_trip(SystemTime::now());
+}
+
+#[test]
+fn tuples() {
+    round_trip((1u8,));
+    round_trip((-1i32, 'x'));
+    round_trip((u64::MAX, String::from("tuple"), true));
+    round_trip((
+        1u8, 2u16, 3u32, 4u64, 5u128, -6i8, -7i16, -8i32, -9i64, -10i128, 11usize, -12isize,
+    ));
+}
+
+#[test]
+fn owned() {
+    round_trip(String::new());
+    round_trip(String::from("héllo wörld"));
+    round_trip(Vec::<u32>::new());
+    round_trip(vec![0u64, 1, 300, u64::MAX]);
+    round_trip(vec![String::from("a"), String::new(), String::from("c")]);
+    round_trip(Box::new(-42i64));
+    round_trip(PathBuf::from("/var/lib/arken/log.000001.ark"));
+    round_trip(Some(vec![Some(1i16), None]));
+    round_trip(Ok::<u32, String>(7));
+    round_trip(Err::<u32, String>(String::from("error")));
+}
+
+#[test]
+fn net() {
+    round_trip(Ipv4Addr::new(192, 168, 1, 1));
+    round_trip(Ipv6Addr::LOCALHOST);
+    round_trip(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+    round_trip(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    round_trip(IpAddr::V6(Ipv6Addr::new(0xfe80, 0, 0, 0, 1, 2, 3, 4)));
+    round_trip(SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 8080));
+    round_trip(SocketAddrV6::new(Ipv6Addr::LOCALHOST, 443, 7, 3));
+    round_trip(SocketAddr::from(([127, 0, 0, 1], 65535)));
+    round_trip(SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)));
+}
+
+#[test]
+fn strings_with_a_nul_are_rejected() {
+    let config = Config::default();
+    let mut bytes = BytesMut::new();
+
+    assert!(matches!(
+        String::from("nul\0byte").put_bytes(&mut bytes, config),
+        Err(Error::InteriorNul(3))
+    ));
+    assert!(matches!(
+        Cow::Borrowed("\0").put_bytes(&mut bytes, config),
+        Err(Error::InteriorNul(0))
+    ));
+    assert!(matches!(
+        (1u8, String::from("a\0")).put_bytes(&mut bytes, config),
+        Err(Error::InteriorNul(1))
+    ));
+}
+
+#[test]
+fn collections() {
+    round_trip(BTreeMap::<u32, String>::new());
+    round_trip(BTreeMap::from([
+        (3u32, String::from("c")),
+        (1, String::new()),
+        (2, "b".into()),
+    ]));
+    round_trip(BTreeSet::from([-1i64, i64::MIN, 0, i64::MAX]));
+    round_trip(HashMap::<u64, Vec<u8>>::new());
+    round_trip(HashMap::from([
+        (300u64, vec![1u8]),
+        (0, vec![]),
+        (u64::MAX, vec![2, 3]),
+    ]));
+    round_trip(HashSet::from([
+        String::from("b"),
+        String::from("a"),
+        String::new(),
+    ]));
+    round_trip(VecDeque::from([1u16, 2, 3]));
+
+    // A deque that wraps around its buffer is still encoded from front to back.
+    let mut deque = VecDeque::with_capacity(4);
+    deque.extend([2u16, 3, 4]);
+    deque.pop_front();
+    deque.push_back(5);
+    deque.push_back(6);
+    round_trip(deque);
+}
+
+#[test]
+fn equal_hashed_collections_encode_to_the_same_bytes() {
+    let keys: Vec<u64> = (0..64).map(|n| n * 0x0101).collect();
+
+    for config in configs() {
+        let forward: HashMap<u64, u64> = keys.iter().map(|&key| (key, !key)).collect();
+        let backward: HashMap<u64, u64> = keys.iter().rev().map(|&key| (key, !key)).collect();
+
+        let (mut a, mut b) = (BytesMut::new(), BytesMut::new());
+        forward.put_bytes(&mut a, config).unwrap();
+        backward.put_bytes(&mut b, config).unwrap();
+        assert_eq!(a, b, "{config:?}");
+
+        let forward: HashSet<u64> = keys.iter().copied().collect();
+        let backward: HashSet<u64> = keys.iter().rev().copied().collect();
+
+        let (mut a, mut b) = (BytesMut::new(), BytesMut::new());
+        forward.put_bytes(&mut a, config).unwrap();
+        backward.put_bytes(&mut b, config).unwrap();
+        assert_eq!(a, b, "{config:?}");
+    }
+}
+
+#[test]
+fn duplicate_keys_are_rejected() {
+    for config in configs() {
+        // Maps and sets are encoded like a sequence of their entries.
+        let mut bytes = BytesMut::new();
+        vec![(1u32, 10u32), (2, 20), (1, 30)]
+            .put_bytes(&mut bytes, config)
+            .unwrap();
+
+        assert!(matches!(
+            BTreeMap::<u32, u32>::from_slice(&bytes, config),
+            Err(Error::Corrupt(_))
+        ));
+        assert!(matches!(
+            HashMap::<u32, u32>::from_slice(&bytes, config),
+            Err(Error::Corrupt(_))
+        ));
+
+        let mut bytes = BytesMut::new();
+        vec![5u32, 7, 5].put_bytes(&mut bytes, config).unwrap();
+
+        assert!(matches!(
+            BTreeSet::<u32>::from_slice(&bytes, config),
+            Err(Error::Corrupt(_))
+        ));
+        assert!(matches!(
+            HashSet::<u32>::from_slice(&bytes, config),
+            Err(Error::Corrupt(_))
+        ));
+
+        // Deques may hold the same value more than once.
+        let (deque, _) = VecDeque::<u32>::from_slice(&bytes, config).unwrap();
+        assert_eq!(deque, [5, 7, 5]);
+    }
+}
+
+#[test]
+fn unsorted_entries_are_accepted() {
+    for config in configs() {
+        let mut bytes = BytesMut::new();
+        vec![(3u32, 30u32), (1, 10), (2, 20)]
+            .put_bytes(&mut bytes, config)
+            .unwrap();
+
+        let (map, rest) = BTreeMap::<u32, u32>::from_slice(&bytes, config).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(map, BTreeMap::from([(1, 10), (2, 20), (3, 30)]));
+
+        // Encoding the decoded map writes its entries in the canonical order instead.
+        let mut canonical = BytesMut::new();
+        map.put_bytes(&mut canonical, config).unwrap();
+        assert_ne!(canonical, bytes);
+    }
+}
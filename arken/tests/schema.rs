@@ -0,0 +1,252 @@
+use arken::{
+    Arken, Config, Endian, Error, Field, MappedFile, Ref, Value, Writer,
+    schema::{Schema, Schemas, TypeKind},
+};
+use bytes::BytesMut;
+use std::borrow::Cow;
+use uuid::Uuid;
+
+const MARKER: &[u8] = b"users";
+
+#[derive(Arken, Clone, Debug, PartialEq)]
+enum Role<'a> {
+    Guest,
+    Member { since: i64 },
+    Admin(Cow<'a, str>),
+}
+
+#[derive(Arken, Clone, Debug, PartialEq)]
+#[arken(version = 2)]
+struct User<'a> {
+    id: Uuid,
+    name: Cow<'a, str>,
+    #[arken(endian = "big", size = "fixed")]
+    flags: u32,
+    roles: Vec<Role<'a>>,
+    manager: Option<Ref<'a, u64>>,
+    #[arken(since = 2)]
+    active: bool,
+}
+
+fn configs() -> [Config; 2] {
+    let mut fixed = Config::default();
+    fixed.fixed_width().with_endian(Endian::Little);
+
+    [Config::default(), fixed]
+}
+
+fn users() -> Vec<User<'static>> {
+    vec![
+        User {
+            id: Uuid::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef),
+            name: Cow::Borrowed("alice"),
+            flags: 0x0102_0304,
+            roles: vec![Role::Admin(Cow::Borrowed("root")), Role::Guest],
+            manager: None,
+            active: true,
+        },
+        User {
+            id: Uuid::nil(),
+            name: Cow::Borrowed("bob"),
+            flags: 0,
+            roles: vec![Role::Member { since: -1 }],
+            manager: Some(Ref::new(42)),
+            active: false,
+        },
+    ]
+}
+
+#[test]
+fn records_decode_and_encode_through_the_written_schema() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+
+    for (index, config) in configs().into_iter().enumerate() {
+        let path = dir.path().join(format!("{index}.ark"));
+        let mut writer = Writer::create(&path, config)?;
+        let mut bytes = BytesMut::new();
+
+        writer.append_schema::<User>(&mut bytes, MARKER)?;
+
+        for user in users() {
+            writer.append_with_marker(&mut bytes, MARKER, &user)?;
+        }
+
+        writer.flush()?;
+
+        let file = MappedFile::open(&path)?;
+        let reader = file.try_reader()?;
+
+        let record = reader.schema(MARKER).expect("schema");
+        assert!(record.describes(MARKER));
+        assert_eq!(record.marker(), MARKER);
+
+        let schemas = record.schemas();
+        let (schema, expected) = Schemas::describe::<User>();
+        assert_eq!(record.schema.clone().into_owned(), schema);
+        // The borrowed names make `Schemas` invariant over its lifetime.
+        assert_eq!(format!("{schemas:?}"), format!("{expected:?}"));
+
+        let mut records = reader.find::<User>(MARKER);
+        let mut users = users();
+
+        while let Some((offset, user)) = records.next_with_offset() {
+            assert_eq!(Some(user), users.pop());
+
+            let value = reader.read_value(offset, &record.schema, &schemas)?;
+            let Value::Versioned { version, .. } = &value else {
+                panic!("{value:?} is not versioned");
+            };
+            assert_eq!(*version, 2);
+
+            // A value decoded with a schema encodes to the same bytes as the type it describes.
+            let user = reader.read(&Ref::<User>::new(offset))?;
+            let (mut encoded, mut expected) = (BytesMut::new(), BytesMut::new());
+            value.put_bytes(&mut encoded, config, &record.schema, &schemas)?;
+            user.put_bytes(&mut expected, config)?;
+            assert_eq!(encoded, expected);
+
+            assert_eq!(value.get("name"), Some(&Value::Str(user.name.clone())));
+            assert_eq!(value.get("flags"), Some(&Value::U32(user.flags)));
+            assert_eq!(value.get("active"), Some(&Value::Bool(user.active)));
+            assert_eq!(value.get("id"), Some(&Value::Uuid(*user.id.as_bytes())));
+
+            let manager = user
+                .manager
+                .map(|manager| Box::new(Value::Ref(manager.offset())));
+            assert_eq!(value.get("manager"), Some(&Value::Option(manager)));
+        }
+
+        assert!(users.is_empty());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn schemas_describe_enums_and_field_overrides() {
+    let (schema, schemas) = Schemas::describe::<User>();
+
+    let Schema::Named(name) = &schema else {
+        panic!("{schema:?} is not named");
+    };
+    let Some(TypeKind::Versioned { version, fields }) = schemas.get(name) else {
+        panic!("{name} is not versioned");
+    };
+    assert_eq!(*version, 2);
+
+    let names: Vec<&str> = fields.iter().map(|field| field.name.as_ref()).collect();
+    assert_eq!(names, ["id", "name", "flags", "roles", "manager", "active"]);
+
+    let flags = &fields[2];
+    assert_eq!(flags.schema, Schema::U32);
+    assert_eq!(flags.endian, Some(Endian::Big));
+    assert!(flags.width.is_some());
+    assert_eq!(fields[5].since, Some(2));
+    assert_eq!(fields[5].schema, Schema::Bool);
+
+    let Schema::Seq(role) = &fields[3].schema else {
+        panic!("{:?} is not a sequence", fields[3].schema);
+    };
+    let Schema::Named(role) = role.as_ref() else {
+        panic!("{role:?} is not named");
+    };
+    let Some(TypeKind::Enum(variants)) = schemas.get(role) else {
+        panic!("{role} is not an enum");
+    };
+
+    let names: Vec<&str> = variants
+        .iter()
+        .map(|variant| variant.name.as_ref())
+        .collect();
+    assert_eq!(names, ["Guest", "Member", "Admin"]);
+    assert!(variants[0].fields.is_empty());
+    assert_eq!(variants[1].fields[0].schema, Schema::I64);
+    assert_eq!(variants[2].fields[0].schema, Schema::Str);
+}
+
+#[test]
+fn values_that_do_not_match_the_schema_are_rejected() -> Result<(), Error> {
+    let (schema, schemas) = Schemas::describe::<User>();
+    let config = Config::default();
+
+    let mut bytes = BytesMut::new();
+    users()[0].put_bytes(&mut bytes, config)?;
+    let (value, rest) = Value::from_slice(&bytes, config, &schema, &schemas)?;
+    assert!(rest.is_empty());
+
+    let mut encoded = BytesMut::new();
+    assert!(matches!(
+        value.put_bytes(&mut encoded, config, &Schema::U64, &schemas),
+        Err(Error::SchemaMismatch(_))
+    ));
+    assert!(matches!(
+        Value::U64(1).put_bytes(&mut encoded, config, &schema, &schemas),
+        Err(Error::SchemaMismatch(_))
+    ));
+
+    // Records that are cut short fail to decode, like they do with the type itself.
+    assert!(matches!(
+        Value::from_slice(&bytes[..bytes.len() - 1], config, &schema, &schemas),
+        Err(Error::Incomplete(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn schemas_are_written_once_per_marker_and_type() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("file.ark");
+    let mut writer = Writer::create(&path, Config::default())?;
+    let mut bytes = BytesMut::new();
+
+    writer.append_schema::<User>(&mut bytes, MARKER)?;
+    writer.append_schema::<User>(&mut bytes, MARKER)?;
+    writer.append_schema::<u64>(&mut bytes, b"counts")?;
+    writer.append_schema::<Cow<str>>(&mut bytes, b"counts")?;
+    writer.flush()?;
+
+    let file = MappedFile::open(&path)?;
+    let reader = file.try_reader()?;
+
+    let records = reader.schemas();
+    assert_eq!(records.len(), 2);
+
+    // The most recent schema of a marker is the one that describes its records.
+    let counts = reader.schema(b"counts").expect("schema");
+    assert_eq!(counts.schema, Schema::Str);
+    assert_eq!(reader.find::<()>(arken::schema::SCHEMA_MARKER).count(), 3);
+
+    Ok(())
+}
+
+#[derive(Arken, Debug, PartialEq)]
+struct Event {
+    event: u64,
+}
+
+#[test]
+fn names_in_schemas_do_not_hide_records_with_the_same_marker() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("file.ark");
+    let mut writer = Writer::create(&path, Config::default())?;
+    let mut bytes = BytesMut::new();
+
+    // The name of the field of the schema record is the marker of the records.
+    writer.append_with_marker(&mut bytes, b"event", &Event { event: 1 })?;
+    writer.append_schema::<Event>(&mut bytes, b"event")?;
+    writer.append_with_marker(&mut bytes, b"event", &Event { event: 2 })?;
+    writer.flush()?;
+
+    let file = MappedFile::open(&path)?;
+    let reader = file.try_reader()?;
+
+    let events: Vec<u64> = reader
+        .find::<Event>(b"event")
+        .map(|event| event.event)
+        .collect();
+    assert_eq!(events, [2, 1]);
+    assert!(reader.schema(b"event").is_some());
+
+    Ok(())
+}
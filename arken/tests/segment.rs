@@ -0,0 +1,156 @@
+use arken::{Config, Error, RetentionPolicy, SegmentedReader, SegmentedWriter};
+use bytes::BytesMut;
+use std::{
+    fs::File,
+    time::{Duration, SystemTime},
+};
+
+const MARKER: &[u8] = b"entry";
+
+#[test]
+fn rolls_over_by_size() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let mut writer = SegmentedWriter::open(dir.path(), "log", Config::default())?;
+    writer.with_max_size(64);
+
+    let mut bytes = BytesMut::new();
+
+    for n in 0..20u64 {
+        writer.append_with_marker(&mut bytes, MARKER, &n)?;
+    }
+
+    writer.flush()?;
+    assert!(writer.segment() > 1);
+
+    let reader = SegmentedReader::open(dir.path(), "log")?;
+    assert_eq!(reader.segments().count() as u64, writer.segment());
+
+    for id in reader.segments() {
+        let size = std::fs::metadata(dir.path().join(format!("log.{id:06}.ark")))?.len();
+        assert!(id == writer.segment() || size >= 64);
+    }
+
+    let entries: Vec<u64> = reader.find(MARKER).collect();
+    assert_eq!(entries, (0..20).rev().collect::<Vec<_>>());
+
+    Ok(())
+}
+
+#[test]
+fn rolls_over_by_age_across_reopens() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let mut bytes = BytesMut::new();
+
+    let mut writer = SegmentedWriter::open(dir.path(), "log", Config::default())?;
+    writer.with_max_age(Duration::from_millis(200));
+    writer.append_with_marker(&mut bytes, MARKER, &1u64)?;
+    assert_eq!(writer.segment(), 1);
+    drop(writer);
+
+    std::thread::sleep(Duration::from_millis(300));
+
+    // Reopening does not reset the age of the active segment.
+    let mut writer = SegmentedWriter::open(dir.path(), "log", Config::default())?;
+    writer.with_max_age(Duration::from_millis(200));
+    writer.append_with_marker(&mut bytes, MARKER, &2u64)?;
+    assert_eq!(writer.segment(), 2);
+
+    writer.append_with_marker(&mut bytes, MARKER, &3u64)?;
+    assert_eq!(writer.segment(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn reopening_keeps_the_config_of_the_log() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let mut bytes = BytesMut::new();
+
+    let mut config = Config::default();
+    config.fixed_width();
+
+    let mut writer = SegmentedWriter::open(dir.path(), "log", config)?;
+    writer.append_with_marker(&mut bytes, MARKER, &1u64)?;
+    drop(writer);
+
+    let mut writer = SegmentedWriter::open(dir.path(), "log", Config::default())?;
+    assert_eq!(writer.config(), config);
+    writer.append_with_marker(&mut bytes, MARKER, &2u64)?;
+    writer.rotate()?;
+    writer.append_with_marker(&mut bytes, MARKER, &3u64)?;
+    writer.flush()?;
+
+    let reader = SegmentedReader::open(dir.path(), "log")?;
+
+    for id in reader.segments() {
+        assert!(reader.reader(id).unwrap().config().is_fixed_width());
+    }
+
+    let entries: Vec<u64> = reader.find(MARKER).collect();
+    assert_eq!(entries, [3, 2, 1]);
+
+    Ok(())
+}
+
+#[test]
+fn retains_at_most_max_segments() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let mut writer = SegmentedWriter::open(dir.path(), "log", Config::default())?;
+    let mut bytes = BytesMut::new();
+
+    for n in 0..5u64 {
+        writer.append_with_marker(&mut bytes, MARKER, &n)?;
+        writer.rotate()?;
+    }
+
+    let mut policy = RetentionPolicy::default();
+    policy.with_max_segments(3);
+
+    let deleted = writer.apply_retention(&policy)?;
+    assert_eq!(deleted.len(), 3);
+
+    let reader = SegmentedReader::open(dir.path(), "log")?;
+    assert_eq!(reader.segments().collect::<Vec<_>>(), [4, 5, 6]);
+
+    // The active segment is never deleted.
+    policy.with_max_segments(0);
+    writer.apply_retention(&policy)?;
+
+    let reader = SegmentedReader::open(dir.path(), "log")?;
+    assert_eq!(reader.segments().collect::<Vec<_>>(), [6]);
+
+    Ok(())
+}
+
+#[test]
+fn retains_segments_by_age() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let mut writer = SegmentedWriter::open(dir.path(), "log", Config::default())?;
+    let mut bytes = BytesMut::new();
+
+    for n in 0..3u64 {
+        writer.append_with_marker(&mut bytes, MARKER, &n)?;
+        writer.rotate()?;
+    }
+
+    // Make the first two segments look like they were last written an hour ago.
+    let hour_ago = SystemTime::now() - Duration::from_secs(3600);
+
+    for id in 1..=2 {
+        File::options()
+            .write(true)
+            .open(dir.path().join(format!("log.{id:06}.ark")))?
+            .set_modified(hour_ago)?;
+    }
+
+    let mut policy = RetentionPolicy::default();
+    policy.with_max_age(Duration::from_secs(60));
+
+    let deleted = writer.apply_retention(&policy)?;
+    assert_eq!(deleted.len(), 2);
+
+    let reader = SegmentedReader::open(dir.path(), "log")?;
+    assert_eq!(reader.segments().collect::<Vec<_>>(), [3, 4]);
+
+    Ok(())
+}
@@ -0,0 +1,366 @@
+#![cfg(feature = "serde")]
+
+use arken::{
+    Arken, Config, Endian, Error, Field, HashMap, HashRootRef, HashSet, MappedFile, MergeMap,
+    MergeRootRef, MergeSet, Reader, Serde, SerdeMap, SerdeSeq, StringTrigramIter, TrigramMap,
+    Writer,
+};
+use bytes::BytesMut;
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+};
+
+#[derive(Arken, Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct Point {
+    x: i32,
+    y: u64,
+    label: String,
+    shape: Shape,
+}
+
+#[derive(Arken, Clone, Debug, Deserialize, PartialEq, Serialize)]
+enum Shape {
+    Circle { radius: u32 },
+    Label(String, Option<char>),
+    Empty,
+}
+
+fn configs() -> Vec<Config> {
+    let mut configs = vec![Config::default()];
+
+    for endian in [Endian::Big, Endian::Little] {
+        let mut config = Config::default();
+        config.fixed_width().with_endian(endian);
+        configs.push(config);
+    }
+
+    configs
+}
+
+/// Asserts that `value` encodes to the same bytes through serde as through [`Field`] with every
+/// config, and that it decodes from them through serde.
+fn assert_encodes_like_field<T>(value: T) -> Result<(), Error>
+where
+    T: for<'a> Field<'a> + Serialize + for<'de> Deserialize<'de> + Clone + Debug + PartialEq,
+{
+    for config in configs() {
+        let (mut expected, mut bytes) = (BytesMut::new(), BytesMut::new());
+        value.put_bytes(&mut expected, config)?;
+        Serde(value.clone()).put_bytes(&mut bytes, config)?;
+        assert_eq!(bytes, expected, "{value:?} {config:?}");
+
+        let (decoded, rest) = Serde::<T>::from_slice(&bytes, config)?;
+        assert_eq!(decoded.into_inner(), value);
+        assert!(rest.is_empty());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn strings_round_trip() -> Result<(), Error> {
+    let config = Config::default();
+    let mut bytes = BytesMut::new();
+
+    let value = Serde(("hello".to_string(), vec!["a".to_string(), String::new()]));
+    value.put_bytes(&mut bytes, config)?;
+
+    let (decoded, rest) = Serde::<(String, Vec<String>)>::from_slice(&bytes, config)?;
+    assert_eq!(decoded, value);
+    assert!(rest.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn strings_with_a_nul_are_rejected() {
+    let config = Config::default();
+    let mut bytes = BytesMut::new();
+
+    let result = Serde("nul\0byte".to_string()).put_bytes(&mut bytes, config);
+    assert!(matches!(result, Err(Error::Serde(_))));
+
+    let result = Serde(vec!["ok", "\0"]).put_bytes(&mut bytes, config);
+    assert!(matches!(result, Err(Error::Serde(_))));
+}
+
+#[test]
+fn huge_sequences_of_empty_values_are_rejected() -> Result<(), Error> {
+    let config = Config::default();
+    let mut bytes = BytesMut::new();
+    (1usize << 40).put_bytes(&mut bytes, config)?;
+
+    // These would otherwise keep decoding empty values for a very long time.
+    assert!(matches!(
+        Serde::<Vec<()>>::from_slice(&bytes, config),
+        Err(Error::Corrupt(_))
+    ));
+    assert!(matches!(
+        Serde::<BTreeSet<()>>::from_slice(&bytes, config),
+        Err(Error::Corrupt(_))
+    ));
+    assert!(matches!(
+        Serde::<BTreeMap<(), ()>>::from_slice(&bytes, config),
+        Err(Error::Corrupt(_))
+    ));
+
+    // Like with `Field`, short sequences of empty values still decode.
+    bytes.clear();
+    Serde(vec![(); 1000]).put_bytes(&mut bytes, config)?;
+    let (decoded, rest) = Serde::<Vec<()>>::from_slice(&bytes, config)?;
+    assert_eq!(decoded.len(), 1000);
+    assert!(rest.is_empty());
+
+    Ok(())
+}
+
+const MARKER: &[u8] = b"map";
+
+fn json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).expect("JSON")
+}
+
+#[test]
+fn merge_maps_load_and_serialize_json() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("map.ark");
+    let mut writer = Writer::create(&path, Config::default())?;
+    let mut bytes = BytesMut::new();
+    let fixture = r#"{"b":2,"a":1,"c":3}"#;
+
+    {
+        let file = MappedFile::open(&path)?;
+        let mut map = MergeMap::<String, u64>::open(file.reader(), None);
+        let mut deserializer = serde_json::Deserializer::from_str(fixture);
+        let root = map.load(&mut deserializer, &mut bytes, &mut writer)?;
+        writer.append_with_marker(&mut bytes, MARKER, &root.expect("root"))?;
+        writer.flush()?;
+    }
+
+    let file = MappedFile::open(&path)?;
+    let reader = file.reader();
+    let root = reader.find::<MergeRootRef<String, u64>>(MARKER).next();
+    let map = MergeMap::<String, u64>::open(reader, root);
+
+    // Maps serialize in the order in which they iterate.
+    assert_eq!(json(&map), r#"{"a":1,"b":2,"c":3}"#);
+
+    let entries = map.iter().collect::<Result<Vec<_>, _>>()?;
+    let large = entries.into_iter().filter(|(_, value)| **value > 1);
+    assert_eq!(json(&SerdeMap::new(large)), r#"{"b":2,"c":3}"#);
+
+    let keys = map.keys().collect::<Result<Vec<_>, _>>()?;
+    let keys = SerdeSeq::new(keys.iter().rev());
+    assert_eq!(json(&keys), r#"["c","b","a"]"#);
+    // Serializing consumes the iterator.
+    assert!(serde_json::to_string(&keys).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn merge_sets_load_and_serialize_json() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("set.ark");
+    let mut writer = Writer::create(&path, Config::default())?;
+    let mut bytes = BytesMut::new();
+
+    {
+        let file = MappedFile::open(&path)?;
+        let mut set = MergeSet::<u64>::open(file.reader(), None);
+        let mut deserializer = serde_json::Deserializer::from_str("[3,1,2,1]");
+        let root = set.load(&mut deserializer, &mut bytes, &mut writer)?;
+        writer.append_with_marker(&mut bytes, MARKER, &root.expect("root"))?;
+        writer.flush()?;
+    }
+
+    let file = MappedFile::open(&path)?;
+    let reader = file.reader();
+    let root = reader.find::<MergeRootRef<u64, ()>>(MARKER).next();
+    let set = MergeSet::<u64>::open(reader, root);
+
+    assert_eq!(json(&set), "[1,2,3]");
+
+    Ok(())
+}
+
+#[test]
+fn hash_maps_load_and_serialize_json() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("map.ark");
+    let mut writer = Writer::create(&path, Config::default())?;
+    let mut bytes = BytesMut::new();
+    let fixture = r#"{"b":2,"a":1,"c":3}"#;
+
+    {
+        let file = MappedFile::open(&path)?;
+        let mut map = HashMap::<String, u64>::open(file.reader(), None);
+        let mut deserializer = serde_json::Deserializer::from_str(fixture);
+        let root = map.load(&mut deserializer, &mut bytes, &mut writer)?;
+        writer.append_with_marker(&mut bytes, MARKER, &root.expect("root"))?;
+        writer.flush()?;
+    }
+
+    let file = MappedFile::open(&path)?;
+    let reader = file.reader();
+    let root = reader.find::<HashRootRef<String, u64>>(MARKER).next();
+    let map = HashMap::<String, u64>::open(reader, root);
+
+    // The order of a hash map is unspecified.
+    let entries: BTreeMap<String, u64> = serde_json::from_str(&json(&map)).expect("JSON");
+    assert_eq!(entries, serde_json::from_str(fixture).expect("JSON"));
+
+    Ok(())
+}
+
+#[test]
+fn hash_sets_load_and_serialize_json() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("set.ark");
+    let mut writer = Writer::create(&path, Config::default())?;
+    let mut bytes = BytesMut::new();
+
+    {
+        let file = MappedFile::open(&path)?;
+        let mut set = HashSet::<u64>::open(file.reader(), None);
+        let mut deserializer = serde_json::Deserializer::from_str("[3,1,2,1]");
+        let root = set.load(&mut deserializer, &mut bytes, &mut writer)?;
+        writer.append_with_marker(&mut bytes, MARKER, &root.expect("root"))?;
+        writer.flush()?;
+    }
+
+    let file = MappedFile::open(&path)?;
+    let reader = file.reader();
+    let root = reader.find::<HashRootRef<u64, ()>>(MARKER).next();
+    let set = HashSet::<u64>::open(reader, root);
+
+    let mut keys: Vec<u64> = serde_json::from_str(&json(&set)).expect("JSON");
+    keys.sort();
+    assert_eq!(keys, [1, 2, 3]);
+
+    Ok(())
+}
+
+#[test]
+fn trigram_maps_serialize_every_key_once() -> Result<(), Error> {
+    let mut map = TrigramMap::<u64, StringTrigramIter>::open(Reader::default(), None);
+
+    // Both keys contain the trigrams "app" and "ppl".
+    map.insert(b"apple", 1)?;
+    map.insert(b"apply", 2)?;
+    map.insert(b"pear", 3)?;
+
+    let serialized = json(&map);
+    assert_eq!(serialized.matches("apple").count(), 1, "{serialized}");
+    assert_eq!(serialized.matches("apply").count(), 1, "{serialized}");
+
+    let entries: BTreeMap<String, u64> = serde_json::from_str(&serialized).expect("JSON");
+    assert_eq!(
+        entries,
+        BTreeMap::from([
+            ("apple".to_string(), 1),
+            ("apply".to_string(), 2),
+            ("pear".to_string(), 3),
+        ])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn failed_loads_leave_the_collection_as_is() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("map.ark");
+    let mut writer = Writer::create(&path, Config::default())?;
+    let mut bytes = BytesMut::new();
+    let file = MappedFile::open(&path)?;
+
+    let mut map = MergeMap::<String, u64>::open(file.reader(), None);
+    map.insert("a".to_string(), 0);
+
+    // The second value is not a number.
+    let mut deserializer = serde_json::Deserializer::from_str(r#"{"a":1,"b":"two"}"#);
+    let result = map.load(&mut deserializer, &mut bytes, &mut writer);
+    assert!(matches!(result, Err(Error::Serde(_))));
+    assert_eq!(json(&map), r#"{"a":0}"#);
+
+    let mut set = HashSet::<u64>::open(file.reader(), None);
+    let mut deserializer = serde_json::Deserializer::from_str("[1,2,-3]");
+    let result = set.load(&mut deserializer, &mut bytes, &mut writer);
+    assert!(matches!(result, Err(Error::Serde(_))));
+    assert_eq!(json(&set), "[]");
+
+    Ok(())
+}
+
+#[test]
+fn scalars_encode_like_field() -> Result<(), Error> {
+    for value in [0, 1, 127, 128, 300, u64::MAX] {
+        assert_encodes_like_field(value)?;
+        assert_encodes_like_field(value as u16)?;
+        assert_encodes_like_field(value as u32)?;
+        assert_encodes_like_field(value as u128)?;
+    }
+
+    for value in [0, -1, 1, -64, 64, i64::MIN, i64::MAX] {
+        assert_encodes_like_field(value)?;
+        assert_encodes_like_field(value as i16)?;
+        assert_encodes_like_field(value as i32)?;
+        assert_encodes_like_field(value as i128)?;
+    }
+
+    assert_encodes_like_field(0x7fu8)?;
+    assert_encodes_like_field(-1i8)?;
+    assert_encodes_like_field(true)?;
+    assert_encodes_like_field(false)?;
+    assert_encodes_like_field('a')?;
+    assert_encodes_like_field('\u{1f980}')?;
+    assert_encodes_like_field(None::<u64>)?;
+    assert_encodes_like_field(Some(300u64))?;
+    assert_encodes_like_field(String::from("text"))?;
+
+    Ok(())
+}
+
+#[test]
+fn sequences_encode_like_field() -> Result<(), Error> {
+    let values = vec![0, 300, u64::MAX];
+
+    for config in configs() {
+        let (mut expected, mut bytes) = (BytesMut::new(), BytesMut::new());
+        Cow::<[u64]>::Borrowed(&values).put_bytes(&mut expected, config)?;
+        Serde(values.clone()).put_bytes(&mut bytes, config)?;
+        assert_eq!(bytes, expected, "{config:?}");
+
+        let (decoded, _) = Cow::<[u64]>::from_slice(&bytes, config)?;
+        assert_eq!(decoded, values);
+    }
+
+    assert_encodes_like_field(values)?;
+    assert_encodes_like_field(Vec::<String>::new())?;
+
+    Ok(())
+}
+
+#[test]
+fn derived_types_encode_like_field() -> Result<(), Error> {
+    for shape in [
+        Shape::Circle { radius: 10 },
+        Shape::Label(String::from("label"), Some('x')),
+        Shape::Label(String::new(), None),
+        Shape::Empty,
+    ] {
+        assert_encodes_like_field(shape.clone())?;
+        assert_encodes_like_field(Point {
+            x: -5,
+            y: 1 << 40,
+            label: String::from("point"),
+            shape,
+        })?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use arken::{Config, Error, Field};
+use bytes::BytesMut;
+use std::fmt::Debug;
+
+/// Bytes appended to an encoding, such that the decoder takes its fast path, which requires as
+/// many bytes as the longest encoding of the type.
+const PADDING: [u8; 19] = [0; 19];
+
+fn encode<'a, T: Field<'a>>(value: T) -> Result<Vec<u8>, Error> {
+    let mut bytes = BytesMut::new();
+    value.put_bytes(&mut bytes, Config::default())?;
+
+    Ok(bytes.to_vec())
+}
+
+/// Decodes `bytes` as a `T`, both on its own and followed by padding, and checks that both
+/// decode to the same result.
+fn decode<T: for<'a> Field<'a> + Debug + PartialEq>(bytes: &[u8]) -> Result<T, Error> {
+    let padded = [bytes, &PADDING].concat();
+
+    let exact = T::from_slice(bytes, Config::default());
+    let fast = T::from_slice(&padded, Config::default());
+
+    match (exact, fast) {
+        (Ok((exact, rest)), Ok((fast, padding))) => {
+            assert!(rest.is_empty());
+            assert_eq!(padding, &PADDING);
+            assert_eq!(exact, fast);
+
+            Ok(exact)
+        }
+        (Err(exact), Err(fast)) => {
+            assert_eq!(
+                std::mem::discriminant(&exact),
+                std::mem::discriminant(&fast)
+            );
+
+            Err(exact)
+        }
+        (exact, fast) => panic!("{bytes:02x?} decodes to {exact:?} and to {fast:?} with padding"),
+    }
+}
+
+fn check_boundaries<T>(max: T, longest: usize) -> Result<(), Error>
+where
+    T: for<'a> Field<'a> + Copy + Debug + PartialEq + Into<u128>,
+{
+    let bytes = encode(max)?;
+    assert_eq!(bytes.len(), longest);
+    assert_eq!(decode::<T>(&bytes)?, max);
+
+    // One over the maximum takes as many bytes, but sets a bit that does not fit in the type.
+    let bytes = encode(max.into() + 1)?;
+    assert_eq!(bytes.len(), longest);
+    assert!(matches!(decode::<T>(&bytes), Err(Error::Overflow(_))));
+
+    // Every prefix of the longest encoding is incomplete.
+    let bytes = encode(max)?;
+
+    for size in 0..bytes.len() {
+        assert!(matches!(
+            T::from_slice(&bytes[..size], Config::default()),
+            Err(Error::Incomplete(_))
+        ));
+    }
+
+    // A varint that does not end within the longest encoding does not fit in the type.
+    let bytes = vec![0x80; longest];
+    assert!(matches!(decode::<T>(&bytes), Err(Error::Overflow(_))));
+
+    Ok(())
+}
+
+#[test]
+fn maximum_values_and_overflow() -> Result<(), Error> {
+    check_boundaries(u16::MAX, 3)?;
+    check_boundaries(u32::MAX, 5)?;
+    check_boundaries(u64::MAX, 10)?;
+
+    Ok(())
+}
+
+#[test]
+fn u128_boundaries() -> Result<(), Error> {
+    let bytes = encode(u128::MAX)?;
+    assert_eq!(bytes.len(), 19);
+    assert_eq!(bytes[18], 0x03);
+    assert_eq!(decode::<u128>(&bytes)?, u128::MAX);
+
+    // The last byte only has room for the two highest bits.
+    let mut over = bytes.clone();
+    over[18] = 0x04;
+    assert!(matches!(decode::<u128>(&over), Err(Error::Overflow(_))));
+
+    for size in 0..bytes.len() {
+        assert!(matches!(
+            u128::from_slice(&bytes[..size], Config::default()),
+            Err(Error::Incomplete(_))
+        ));
+    }
+
+    assert!(matches!(
+        decode::<u128>(&[0x80; 19]),
+        Err(Error::Overflow(_))
+    ));
+
+    assert_eq!(decode::<i128>(&encode(i128::MIN)?)?, i128::MIN);
+    assert_eq!(decode::<i128>(&encode(i128::MAX)?)?, i128::MAX);
+
+    Ok(())
+}
+
+#[test]
+fn padded_encodings_are_non_canonical() -> Result<(), Error> {
+    assert!(matches!(
+        decode::<u64>(&[0x80, 0x00]),
+        Err(Error::NonCanonical(_))
+    ));
+    assert!(matches!(
+        decode::<u64>(&[0xff, 0x80, 0x00]),
+        Err(Error::NonCanonical(_))
+    ));
+    assert!(matches!(
+        decode::<u16>(&[0x81, 0x80, 0x00]),
+        Err(Error::NonCanonical(_))
+    ));
+    assert!(matches!(
+        decode::<u128>(&[0x80, 0x00]),
+        Err(Error::NonCanonical(_))
+    ));
+
+    // A single zero byte is the canonical encoding of zero.
+    assert_eq!(decode::<u64>(&[0x00])?, 0);
+    assert_eq!(decode::<u64>(&[0x80, 0x01])?, 0x80);
+
+    Ok(())
+}
+
+#[test]
+fn signed_integers_round_trip_at_their_extremes() -> Result<(), Error> {
+    for value in [i64::MIN, -1, 0, 1, i64::MAX] {
+        assert_eq!(decode::<i64>(&encode(value)?)?, value);
+    }
+
+    for value in [i16::MIN, i16::MAX] {
+        assert_eq!(decode::<i16>(&encode(value)?)?, value);
+    }
+
+    // ZigZag encoding keeps values close to zero short, regardless of their sign.
+    assert_eq!(encode(-1i64)?, [0x01]);
+    assert_eq!(encode(1i64)?, [0x02]);
+
+    Ok(())
+}
+
+#[test]
+fn empty_input_is_incomplete() {
+    assert!(matches!(
+        u64::from_slice(&[], Config::default()),
+        Err(Error::Incomplete(_))
+    ));
+    assert!(matches!(
+        u128::from_slice(&[], Config::default()),
+        Err(Error::Incomplete(_))
+    ));
+}
@@ -0,0 +1,221 @@
+use arken::{
+    Config, Error, HashMap, HashRootRef, MappedFile, MergeMap, Ref, Writer,
+    verify::{Problem, Verifier},
+};
+use bytes::BytesMut;
+use std::path::Path;
+
+const MARKER: &[u8] = b"map";
+
+/// The key-value pairs of a `MergeMap<u64, u64>`, i.e. the key followed by an `Option` of the
+/// value.
+type KeyValue = (u64, Option<u64>);
+
+/// A `MergeMap` root in layout 0: the layout, the references to the nodes and the count. Every
+/// node is a sequence of references to key-value pairs.
+type MergeRoot = (usize, Vec<usize>, usize);
+
+/// A `HashMap` root: the layout, the reference to the root node and the count.
+type HashRoot = (usize, usize, usize);
+
+fn write_maps(path: &Path) -> Result<(), Error> {
+    let mut writer = Writer::create(path, Config::default())?;
+    let mut bytes = BytesMut::new();
+
+    let file = MappedFile::open(path)?;
+
+    let mut map = MergeMap::<u64, u64>::open(file.try_reader()?, None);
+    (0..20).for_each(|n| _ = map.insert(n, n * 10));
+    let root = map.commit(&mut bytes, &mut writer)?.unwrap();
+    writer.append_with_marker(&mut bytes, b"merge", &root)?;
+
+    let mut map = HashMap::<u64, u64>::open(file.try_reader()?, None);
+    (0..20).for_each(|n| _ = map.insert(n, n * 10));
+    let root = map.commit(&mut bytes, &mut writer)?.unwrap();
+    writer.append_with_marker(&mut bytes, b"hash", &root)?;
+
+    writer.flush()
+}
+
+/// Writes a `MergeMap<u64, u64>` root with a single node holding `entries`, where an entry is
+/// either a key-value pair that is written, or an offset that is referenced as is.
+fn write_merge_map(path: &Path, entries: &[Result<KeyValue, usize>]) -> Result<(), Error> {
+    let mut writer = Writer::create(path, Config::default())?;
+    let mut bytes = BytesMut::new();
+
+    let mut node = vec![];
+
+    for entry in entries {
+        match entry {
+            Ok(key_value) => node.push(writer.append(&mut bytes, key_value)?.offset()),
+            Err(offset) => node.push(*offset),
+        }
+    }
+
+    let node = writer.append(&mut bytes, &node)?;
+    let root = writer.append(&mut bytes, &(0, vec![node.offset()], entries.len()))?;
+    writer.append_with_marker(&mut bytes, MARKER, &Ref::<MergeRoot>::new(root.offset()))?;
+
+    writer.flush()
+}
+
+#[test]
+fn intact_maps_have_no_problems() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("maps.ark");
+    write_maps(&path)?;
+
+    let file = MappedFile::open(&path)?;
+    let mut verifier = Verifier::new(file.try_reader()?.bytes());
+    verifier.check_merge_map::<u64, u64>(b"merge");
+    verifier.check_hash_map::<u64, u64>(b"hash");
+
+    assert!(verifier.is_ok(), "{:?}", verifier.problems());
+
+    Ok(())
+}
+
+#[test]
+fn invalid_headers_are_reported() {
+    let mut verifier = Verifier::new(b"ARX\x41\x01");
+    verifier.check_merge_map::<u64, u64>(MARKER);
+
+    assert!(verifier.reader().is_none());
+    assert!(matches!(
+        verifier.problems(),
+        [Problem::InvalidHeader(Error::InvalidHeader)]
+    ));
+}
+
+#[test]
+fn records_with_a_bad_checksum_are_reported() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("records.ark");
+    let mut writer = Writer::create(&path, Config::default())?;
+    let mut bytes = BytesMut::new();
+
+    let mut offsets = vec![];
+
+    for n in 0..5u64 {
+        offsets.push(writer.append_with_marker(&mut bytes, MARKER, &n)?.offset());
+    }
+
+    writer.flush()?;
+
+    let mut data = std::fs::read(&path)?;
+    data[offsets[2]] ^= 0x01;
+    std::fs::write(&path, data)?;
+
+    let file = MappedFile::open(&path)?;
+    let mut verifier = Verifier::new(file.try_reader()?.bytes());
+    let intact = verifier.check_records(MARKER);
+
+    assert_eq!(intact.len(), 4);
+    assert!(matches!(
+        verifier.problems(),
+        [Problem::ChecksumMismatch { offset, .. }] if *offset == offsets[2]
+    ));
+
+    // Damaged records are only reported once.
+    verifier.check_records(MARKER);
+    assert_eq!(verifier.problems().len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn dangling_references_in_merge_map_nodes_are_reported() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("merge.ark");
+    write_merge_map(&path, &[Ok((1, Some(10))), Err(1 << 40)])?;
+
+    let file = MappedFile::open(&path)?;
+    let mut verifier = Verifier::new(file.try_reader()?.bytes());
+    verifier.check_merge_map::<u64, u64>(MARKER);
+
+    assert!(matches!(
+        verifier.problems(),
+        [Problem::Decode {
+            offset: 0x100_0000_0000,
+            error: Error::InvalidOffset,
+            ..
+        }]
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn unsorted_merge_map_nodes_are_reported() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("merge.ark");
+    write_merge_map(
+        &path,
+        &[Ok((1, Some(10))), Ok((3, None)), Ok((2, Some(20)))],
+    )?;
+
+    let file = MappedFile::open(&path)?;
+    let mut verifier = Verifier::new(file.try_reader()?.bytes());
+    verifier.check_merge_map::<u64, u64>(MARKER);
+
+    assert!(matches!(
+        verifier.problems(),
+        [Problem::Unsorted { index: 2, .. }]
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn bad_hash_map_roots_are_reported() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("maps.ark");
+    write_maps(&path)?;
+
+    let root = {
+        let file = MappedFile::open(&path)?;
+        let reader = file.try_reader()?;
+        let reference = reader.find::<Ref<HashRoot>>(b"hash").next().unwrap();
+
+        reader.read(&reference)?
+    };
+
+    // Commit a root that claims to hold one more entry than its trie does.
+    let mut writer = Writer::open(&path)?;
+    let mut bytes = BytesMut::new();
+    let (layout, node, count) = root;
+    let forged = writer.append(&mut bytes, &(layout, node, count + 1))?;
+    let forged = Ref::<HashRoot>::new(forged.offset());
+    writer.append_with_marker(&mut bytes, b"hash", &forged)?;
+    writer.flush()?;
+
+    let file = MappedFile::open(&path)?;
+    let mut verifier = Verifier::new(file.try_reader()?.bytes());
+    verifier.check_hash_map::<u64, u64>(b"hash");
+
+    assert!(matches!(
+        verifier.problems(),
+        [Problem::CountMismatch { offset, expected: 21, actual: 20 }] if *offset == forged.offset()
+    ));
+
+    // A root that points past the end of the file.
+    let mut writer = Writer::open(&path)?;
+    let dangling = HashRootRef::<u64, u64>::new(1 << 40);
+    writer.append_with_marker(&mut bytes, b"hash", &dangling)?;
+    writer.flush()?;
+
+    let file = MappedFile::open(&path)?;
+    let mut verifier = Verifier::new(file.try_reader()?.bytes());
+    verifier.check_hash_map::<u64, u64>(b"hash");
+
+    assert!(verifier.problems().iter().any(|problem| matches!(
+        problem,
+        Problem::Decode {
+            offset: 0x100_0000_0000,
+            error: Error::InvalidOffset,
+            ..
+        }
+    )));
+
+    Ok(())
+}
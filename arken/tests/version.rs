@@ -0,0 +1,93 @@
+use arken::{
+    ConvertMergeMap, Error, FORMAT_VERSION, MapConversion, MappedFile, Writer, migrate_to,
+};
+use bytes::BytesMut;
+
+/// The header of a little endian file of format version 99.
+const NEWER_HEADER: &[u8] = b"ARK\x41\x63";
+
+struct Map;
+
+impl MapConversion for Map {
+    const MARKER: &'static [u8] = b"map";
+
+    type Key<'a> = u64;
+    type Old<'a> = u64;
+    type New<'a> = u64;
+}
+
+#[test]
+fn newer_format_versions_are_rejected() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("newer.ark");
+    std::fs::write(&path, NEWER_HEADER)?;
+
+    let file = MappedFile::open(&path)?;
+    assert!(matches!(
+        file.try_reader(),
+        Err(Error::UnsupportedVersion(99))
+    ));
+    assert!(matches!(
+        Writer::open(&path),
+        Err(Error::UnsupportedVersion(99))
+    ));
+
+    let mut bytes = BytesMut::new();
+    assert!(matches!(
+        migrate_to::<_, _, ConvertMergeMap<Map>>(&mut bytes, dir.path().join("dst.ark"), &path),
+        Err(Error::UnsupportedVersion(99))
+    ));
+    assert!(!dir.path().join("dst.ark").exists());
+
+    Ok(())
+}
+
+#[test]
+fn current_and_legacy_format_versions_are_read() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+
+    let path = dir.path().join("current.ark");
+    Writer::create(&path, Default::default())?.flush()?;
+    let file = MappedFile::open(&path)?;
+    assert_eq!(file.try_reader()?.config().format_version(), FORMAT_VERSION);
+
+    // Files written before the format version was stored have no version byte.
+    let path = dir.path().join("legacy.ark");
+    std::fs::write(&path, b"ARK\x01")?;
+    let file = MappedFile::open(&path)?;
+    assert_eq!(file.try_reader()?.config().format_version(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn missing_and_empty_files_read_as_empty() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+
+    let file = MappedFile::open(dir.path().join("missing.ark"))?;
+    assert!(file.try_reader()?.bytes().is_empty());
+
+    let path = dir.path().join("empty.ark");
+    std::fs::write(&path, b"")?;
+    let file = MappedFile::open(&path)?;
+    assert!(file.try_reader()?.bytes().is_empty());
+
+    Ok(())
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn encrypted_files_require_a_key() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("encrypted.ark");
+    let key = arken::Key::new([7; 32]);
+    Writer::create_encrypted(&path, Default::default(), arken::Cipher::Aes256Gcm, &key)?.flush()?;
+
+    let file = MappedFile::open(&path)?;
+    assert!(matches!(file.try_reader(), Err(Error::Encrypted)));
+
+    let file = MappedFile::open_encrypted(&path, &key)?;
+    assert!(file.try_reader().is_ok());
+
+    Ok(())
+}
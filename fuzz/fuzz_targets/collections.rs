@@ -0,0 +1,37 @@
+#![no_main]
+
+use arken::{
+    HashMap, HashRootRef, MergeMap, MergeRootRef, Reader, StringTrigramIter, TrigramRootRef,
+    TrigramSet,
+};
+use libfuzzer_sys::fuzz_target;
+use std::borrow::Cow;
+
+// Treats the input as a whole file, and looks up and iterates the collections rooted at the most
+// recent marker records.
+fuzz_target!(|data: &[u8]| {
+    let Ok(reader) = Reader::try_from(data) else {
+        return;
+    };
+
+    for _ in reader.find::<Cow<str>>(b"msg").take(16) {}
+
+    let root = reader
+        .find::<MergeRootRef<Cow<str>, Cow<str>>>(b"lsm")
+        .next();
+    let map: MergeMap<Cow<str>, Cow<str>> = MergeMap::open(reader.clone(), root);
+    let _ = map.len();
+    let _ = map.get(&Cow::Borrowed("key"));
+    for _ in map.iter().take(16) {}
+
+    let root = reader.find::<HashRootRef<u64, Cow<str>>>(b"hash").next();
+    let map: HashMap<u64, Cow<str>> = HashMap::open(reader.clone(), root);
+    let _ = map.len();
+    let _ = map.get(&42);
+    for _ in map.iter().take(16) {}
+
+    let root = reader.find::<TrigramRootRef<()>>(b"tri").next();
+    let set: TrigramSet<StringTrigramIter> = TrigramSet::open(reader, root);
+    let _ = set.contains(b"key");
+    let _ = set.query(b"query");
+});
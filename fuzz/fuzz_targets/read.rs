@@ -0,0 +1,98 @@
+#![no_main]
+
+use arken::{
+    Array, ByteStr, Compressed, Config, FarRef, Field, FixedDecimal, IndexEntry, PodSlice, Reader,
+    Ref,
+};
+use jiff::Timestamp;
+use libfuzzer_sys::fuzz_target;
+use rust_decimal::Decimal;
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    net::{IpAddr, SocketAddr},
+    num::NonZeroU64,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+use uuid::Uuid;
+
+fn read<'a, T: 'a + Field<'a>>(reader: &Reader<'a>) {
+    let _ = reader.read::<T>(&Ref::new(4));
+}
+
+// The first byte selects the type to decode. The remaining bytes follow the `ARK` magic, such that
+// the fuzzer controls both the header flags and the encoded value.
+fuzz_target!(|data: &[u8]| {
+    let Some((&selector, data)) = data.split_first() else {
+        return;
+    };
+
+    let mut bytes = b"ARK".to_vec();
+    bytes.extend_from_slice(data);
+
+    let Ok(reader) = Reader::try_from(&bytes[..]) else {
+        return;
+    };
+
+    match selector % 54 {
+        0 => read::<u8>(&reader),
+        1 => read::<u16>(&reader),
+        2 => read::<u32>(&reader),
+        3 => read::<u64>(&reader),
+        4 => read::<u128>(&reader),
+        5 => read::<usize>(&reader),
+        6 => read::<i8>(&reader),
+        7 => read::<i16>(&reader),
+        8 => read::<i32>(&reader),
+        9 => read::<i64>(&reader),
+        10 => read::<i128>(&reader),
+        11 => read::<isize>(&reader),
+        12 => read::<f32>(&reader),
+        13 => read::<f64>(&reader),
+        14 => read::<()>(&reader),
+        15 => read::<Option<u64>>(&reader),
+        16 => read::<Cow<str>>(&reader),
+        17 => read::<Cow<[u8]>>(&reader),
+        18 => read::<Cow<[u64]>>(&reader),
+        19 => read::<Cow<[Cow<str>]>>(&reader),
+        20 => read::<Cow<[u8; 16]>>(&reader),
+        21 => read::<Cow<[Option<i32>; 4]>>(&reader),
+        22 => {
+            if let Ok(array) = reader.read::<Array<u32>>(&Ref::new(4)) {
+                for _ in array.iter(Config::default()) {}
+            }
+        }
+        23 => read::<ByteStr>(&reader),
+        24 => read::<PodSlice<u32>>(&reader),
+        25 => read::<PodSlice<f64>>(&reader),
+        26 => read::<Ref<u64>>(&reader),
+        27 => read::<FarRef<u64>>(&reader),
+        28 => read::<IndexEntry>(&reader),
+        29 => read::<Decimal>(&reader),
+        30 => read::<FixedDecimal<4>>(&reader),
+        31 => read::<Timestamp>(&reader),
+        32 => read::<Uuid>(&reader),
+        33 => read::<bool>(&reader),
+        34 => read::<char>(&reader),
+        35 => read::<String>(&reader),
+        36 => read::<Vec<u64>>(&reader),
+        37 => read::<Box<u64>>(&reader),
+        38 => read::<PathBuf>(&reader),
+        39 => read::<(u8, String, bool)>(&reader),
+        40 => read::<Duration>(&reader),
+        41 => read::<SystemTime>(&reader),
+        42 => read::<NonZeroU64>(&reader),
+        43 => read::<IpAddr>(&reader),
+        44 => read::<SocketAddr>(&reader),
+        45 => read::<Result<u32, String>>(&reader),
+        46 => read::<BTreeMap<u64, String>>(&reader),
+        47 => read::<BTreeSet<String>>(&reader),
+        48 => read::<HashMap<String, u64>>(&reader),
+        49 => read::<HashSet<u64>>(&reader),
+        50 => read::<VecDeque<Cow<str>>>(&reader),
+        51 => read::<Compressed<str>>(&reader),
+        52 => read::<Compressed<[u8]>>(&reader),
+        _ => read::<Config>(&reader),
+    }
+});